@@ -140,6 +140,7 @@ async fn main() -> Result<()> {
 	req_get_edit.await?.print().await?;
 
 	// -- Update created edit
+	// (no `If-Match` sent, run the server with `SERVICE_REQUIRE_IF_MATCH=false`)
 	// check that editor can update edit
 	let req_update_edit = hc_auth_tester.do_patch(
 		&edit_id_route,
@@ -0,0 +1,8 @@
+-- Base app schema
+DROP TABLE IF EXISTS "edits";
+DROP TABLE IF EXISTS "posts";
+DROP TABLE IF EXISTS "author_identities";
+DROP TABLE IF EXISTS "recovery_codes";
+DROP TABLE IF EXISTS "authors";
+DROP TYPE IF EXISTS EDIT_STATUS;
+DROP FUNCTION IF EXISTS trigger_set_timestamp();
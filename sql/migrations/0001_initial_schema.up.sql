@@ -1,4 +1,4 @@
----- Base app schema
+-- Base app schema
 
 -- Authors
 CREATE TABLE "authors" (
@@ -8,10 +8,38 @@ CREATE TABLE "authors" (
 	created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
 
 	-- Auth
-	password varchar(256) NOT NULL,
+	password varchar(256), -- NULL for authors signed up with an OpenID Connect provider
 	password_salt uuid NOT NULL DEFAULT gen_random_uuid(),
-	token_salt uuid NOT NULL DEFAULT gen_random_uuid()
-);--#
+	token_salt uuid NOT NULL DEFAULT gen_random_uuid(),
+
+	-- Two-factor auth (TOTP)
+	totp_secret varchar(64),
+	totp_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+	totp_last_step BIGINT
+);
+
+-- Recovery codes (Two-factor auth)
+CREATE TABLE "recovery_codes" (
+	id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+	author_id BIGINT NOT NULL,
+	code_hash varchar(128) NOT NULL,
+	used_at TIMESTAMPTZ,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	FOREIGN KEY (author_id) REFERENCES authors(id) ON DELETE CASCADE
+);
+
+-- Author identities (OpenID Connect)
+CREATE TABLE "author_identities" (
+	id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+	author_id BIGINT NOT NULL,
+	provider varchar(64) NOT NULL,
+	subject varchar(256) NOT NULL,
+	email varchar(128),
+	created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	FOREIGN KEY (author_id) REFERENCES authors(id) ON DELETE CASCADE,
+	UNIQUE (provider, subject),
+	UNIQUE (author_id, provider)
+);
 
 -- Posts
 CREATE TABLE "posts" (
@@ -22,10 +50,10 @@ CREATE TABLE "posts" (
 	created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
 	updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
 	FOREIGN KEY (author_id) REFERENCES authors(id) ON DELETE SET NULL
-);--#
+);
 
 -- Edit Status
-CREATE TYPE EDIT_STATUS AS ENUM ('PENDING', 'ACCEPTED', 'REJECTED');--#
+CREATE TYPE EDIT_STATUS AS ENUM ('PENDING', 'ACCEPTED', 'REJECTED');
 
 -- Edits
 CREATE TABLE "edits" (
@@ -39,7 +67,7 @@ CREATE TABLE "edits" (
 	FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE,
 	FOREIGN KEY (editor_id) REFERENCES authors(id) ON DELETE CASCADE,
 	PRIMARY KEY (editor_id, post_id)
-);--#
+);
 
 CREATE OR REPLACE FUNCTION trigger_set_timestamp()
 RETURNS TRIGGER
@@ -49,14 +77,14 @@ BEGIN
     NEW.updated_at = now();
     RETURN NEW;
 END;
-$$;--#
+$$;
 
 CREATE TRIGGER set_timestamp_edits
 BEFORE UPDATE ON edits
 FOR EACH ROW
-EXECUTE PROCEDURE trigger_set_timestamp();--#
+EXECUTE PROCEDURE trigger_set_timestamp();
 
 CREATE TRIGGER set_timestamp_posts
 BEFORE UPDATE ON posts
 FOR EACH ROW
-EXECUTE PROCEDURE trigger_set_timestamp();--#
\ No newline at end of file
+EXECUTE PROCEDURE trigger_set_timestamp();
\ No newline at end of file
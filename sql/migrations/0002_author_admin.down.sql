@@ -0,0 +1,2 @@
+-- Admin authors
+ALTER TABLE "authors" DROP COLUMN IF EXISTS is_admin;
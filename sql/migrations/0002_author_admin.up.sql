@@ -0,0 +1,2 @@
+-- Admin authors (e.g., created with `author create --admin`)
+ALTER TABLE "authors" ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE;
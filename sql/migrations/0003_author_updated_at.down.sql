@@ -0,0 +1,3 @@
+-- Authors last update
+DROP TRIGGER IF EXISTS set_timestamp_authors ON authors;
+ALTER TABLE "authors" DROP COLUMN IF EXISTS updated_at;
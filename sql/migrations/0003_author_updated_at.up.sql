@@ -0,0 +1,8 @@
+-- Authors last update (e.g., for the `Last-Modified` header)
+ALTER TABLE "authors" ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+UPDATE "authors" SET updated_at = created_at;
+
+CREATE TRIGGER set_timestamp_authors
+BEFORE UPDATE ON authors
+FOR EACH ROW
+EXECUTE PROCEDURE trigger_set_timestamp();
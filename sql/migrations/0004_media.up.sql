@@ -0,0 +1,22 @@
+-- Media library (the content is in the media storage, see `models::storage`)
+CREATE TABLE "media" (
+	id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+	owner_id BIGINT NOT NULL,
+	storage_key varchar(256) NOT NULL UNIQUE,
+	file_name varchar(256) NOT NULL,
+	mime_type varchar(128) NOT NULL,
+	size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+	checksum_sha256 char(64) NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	FOREIGN KEY (owner_id) REFERENCES authors(id) ON DELETE CASCADE
+);
+
+-- Media referenced by posts, in order
+CREATE TABLE "post_media" (
+	post_id BIGINT NOT NULL,
+	media_id BIGINT NOT NULL,
+	position INT NOT NULL,
+	FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE,
+	FOREIGN KEY (media_id) REFERENCES media(id) ON DELETE CASCADE,
+	PRIMARY KEY (post_id, media_id)
+);
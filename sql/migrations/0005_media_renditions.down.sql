@@ -0,0 +1,5 @@
+-- Image processing
+DROP TABLE IF EXISTS "media_renditions";
+ALTER TABLE "media" DROP COLUMN IF EXISTS processing_status;
+ALTER TABLE "media" DROP COLUMN IF EXISTS height;
+ALTER TABLE "media" DROP COLUMN IF EXISTS width;
@@ -0,0 +1,20 @@
+-- Image processing (see `models::media::processor`)
+-- `pending` until processed, `skipped` when not an image (or `media.process_images = false`)
+ALTER TABLE "media" ADD COLUMN width INT;
+ALTER TABLE "media" ADD COLUMN height INT;
+ALTER TABLE "media" ADD COLUMN processing_status varchar(16) NOT NULL DEFAULT 'skipped'
+	CHECK (processing_status IN ('pending', 'done', 'failed', 'skipped'));
+
+-- Renditions of an image, configured (`media.renditions`) or on-demand resizes
+CREATE TABLE "media_renditions" (
+	media_id BIGINT NOT NULL,
+	name varchar(64) NOT NULL,
+	storage_key varchar(256) NOT NULL UNIQUE,
+	mime_type varchar(128) NOT NULL,
+	width INT NOT NULL,
+	height INT NOT NULL,
+	size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+	created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	FOREIGN KEY (media_id) REFERENCES media(id) ON DELETE CASCADE,
+	PRIMARY KEY (media_id, name)
+);
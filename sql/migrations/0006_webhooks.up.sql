@@ -0,0 +1,55 @@
+-- Outgoing webhooks (see `models::webhook`)
+CREATE TABLE "webhook_subscriptions" (
+	id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+	owner_id BIGINT NOT NULL,
+	url varchar(2048) NOT NULL,
+	-- Key of the payload signatures, shared with the receiver
+	secret varchar(256) NOT NULL,
+	event_types varchar(32)[] NOT NULL,
+	active BOOLEAN NOT NULL DEFAULT TRUE,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	FOREIGN KEY (owner_id) REFERENCES authors(id) ON DELETE CASCADE
+);
+
+CREATE TRIGGER set_timestamp_webhook_subscriptions
+BEFORE UPDATE ON webhook_subscriptions
+FOR EACH ROW
+EXECUTE PROCEDURE trigger_set_timestamp();
+
+-- The delivery queue, an event to a subscription, `pending` until `delivered` (or `failed`, out of attempts)
+CREATE TABLE "webhook_deliveries" (
+	id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+	subscription_id BIGINT NOT NULL,
+	-- Same for all the deliveries of an event (receivers dedup on it)
+	event_id varchar(36) NOT NULL,
+	event_type varchar(32) NOT NULL,
+	-- The body sent, as signed
+	payload text NOT NULL,
+	status varchar(16) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'failed')),
+	attempts INT NOT NULL DEFAULT 0,
+	next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	FOREIGN KEY (subscription_id) REFERENCES webhook_subscriptions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX webhook_deliveries_due ON webhook_deliveries (next_attempt_at) WHERE status = 'pending';
+
+CREATE TRIGGER set_timestamp_webhook_deliveries
+BEFORE UPDATE ON webhook_deliveries
+FOR EACH ROW
+EXECUTE PROCEDURE trigger_set_timestamp();
+
+-- The delivery log, one row per attempt
+CREATE TABLE "webhook_delivery_attempts" (
+	id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+	delivery_id BIGINT NOT NULL,
+	attempt INT NOT NULL,
+	-- None when no response (e.g., connection refused, timeout)
+	response_status INT,
+	error text,
+	duration_ms INT NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	FOREIGN KEY (delivery_id) REFERENCES webhook_deliveries(id) ON DELETE CASCADE
+);
@@ -26,7 +26,11 @@ pub async fn init_dev_db() -> Result<(), Box<dyn std::error::Error>> {
 		pexec(&root_db, SQL_RECREATE_DB).await?;
 	}
 
-	// -- Get sql files.
+	// -- Apply the schema migrations.
+	let app_state = AppState::new().await?;
+	app_state.db_migrate_up().await?;
+
+	// -- Get sql files (dev seed).
 	let mut paths: Vec<PathBuf> = fs::read_dir(SQL_DIR)?
 		.filter_map(|entry| entry.ok().map(|e| e.path()))
 		.collect();
@@ -47,7 +51,6 @@ pub async fn init_dev_db() -> Result<(), Box<dyn std::error::Error>> {
 	}
 
 	// -- Init model layer
-	let app_state = AppState::new().await?;
 	let ctx = Ctx::root_ctx();
 
 	// -- Set genesis pwd
@@ -17,16 +17,13 @@ pub async fn init_dev() {
 }
 
 /// Initialize test environment
+///
+/// NOTE: The dev db is only initialized once, but each test gets its own `AppState`,
+///       as a pool cannot outlive the tokio runtime (one per `#[tokio::test]`) it was created on.
 pub async fn init_test() -> AppState {
-	static INIT: OnceCell<AppState> = OnceCell::const_new();
+	init_dev().await;
 
-	let a_s = INIT.get_or_init(|| async {
-		init_dev().await;
-		AppState::new().await.unwrap()
-	})
-	.await;
-
-	a_s.clone()
+	AppState::new().await.unwrap()
 }
 
 pub async fn seed_authors(ctx: &Ctx, app_state: &AppState, authors: &[(&str, &str, &str)]) -> ModelResult<Vec<Author>>{
@@ -0,0 +1,114 @@
+use anyhow::{bail, Result};
+use validator::Validate;
+
+use super::{arg_or_stdin, AuthorCommand};
+use crate::crypt::token::generate_web_token;
+use crate::ctx::Ctx;
+use crate::models::author::{Author, AuthorBmc, AuthorForCreate, AuthorForLogin};
+use crate::models::AppState;
+use crate::utils::validation::validate_pwd_strength;
+
+pub async fn run(command: AuthorCommand) -> Result<()> {
+	let app_state = AppState::new().await?;
+
+	match command {
+		AuthorCommand::Create { name, email, password, admin } => {
+			let password = arg_or_stdin(password, "Password")?;
+			let id = create(&app_state, AuthorForCreate { name, email, password }, admin).await?;
+			println!("{id}");
+		}
+		AuthorCommand::ResetPassword { email, password } => {
+			let password = arg_or_stdin(password, "New password")?;
+			reset_password(&app_state, &email, &password).await?;
+			println!("password reset for {email}");
+		}
+	}
+
+	Ok(())
+}
+
+/// Create the author (as a signup would), returning its id.
+pub async fn create(app_state: &AppState, data: AuthorForCreate, admin: bool) -> Result<i64> {
+	data.validate()?;
+
+	if AuthorBmc::first_by_email::<Author>(app_state, &data.email).await?.is_some() {
+		bail!("author with email '{}' already exists", data.email);
+	}
+
+	let ctx = Ctx::root_ctx();
+	let pwd_clear = data.password.clone();
+	let id = AuthorBmc::create_no_auth(app_state, data).await?;
+	AuthorBmc::update_pwd(&ctx, app_state, id, &pwd_clear).await?;
+
+	if admin {
+		AuthorBmc::set_admin(&ctx, app_state, id, true).await?;
+	}
+
+	Ok(id)
+}
+
+async fn reset_password(app_state: &AppState, email: &str, pwd_clear: &str) -> Result<()> {
+	if let Err(err) = validate_pwd_strength(pwd_clear) {
+		bail!("password {err}");
+	}
+
+	let Some(author) = AuthorBmc::first_by_email::<Author>(app_state, email).await? else {
+		bail!("no author with email '{email}'");
+	};
+
+	let ctx = Ctx::root_ctx();
+	AuthorBmc::update_pwd(&ctx, app_state, author.id, pwd_clear).await?;
+	// (the sessions opened with the old password are logged out)
+	AuthorBmc::rotate_token_salt(&ctx, app_state, author.id).await?;
+
+	Ok(())
+}
+
+pub async fn token_issue(email: &str) -> Result<()> {
+	let app_state = AppState::new().await?;
+
+	let Some(author) = AuthorBmc::first_by_email::<AuthorForLogin>(&app_state, email).await? else {
+		bail!("no author with email '{email}'");
+	};
+
+	let token = generate_web_token(&author.email, &author.token_salt.to_string())?;
+	println!("{token}");
+
+	Ok(())
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::crypt::token::validate_web_token;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_reset_password_ok_token_salt_rotated() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let fx_email = "test_reset_password_ok@mail";
+		let data = AuthorForCreate { name: "fx reset".to_string(), email: fx_email.to_string(), password: "fx-Password-01".to_string() };
+		let id = create(&app_state, data, false).await?;
+		let author: AuthorForLogin = AuthorBmc::get_no_auth(&app_state, id).await?;
+		let fx_token = generate_web_token(fx_email, &author.token_salt.to_string())?;
+
+		// -- Exec
+		reset_password(&app_state, fx_email, "fx-Password-02").await?;
+
+		// -- Check
+		let author_after: AuthorForLogin = AuthorBmc::get_no_auth(&app_state, id).await?;
+		assert_ne!(author_after.token_salt, author.token_salt);
+		assert_ne!(author_after.password, author.password);
+		assert!(validate_web_token(&fx_token, &author_after.token_salt.to_string()).is_err());
+
+		// -- Clean
+		AuthorBmc::delete(&Ctx::root_ctx(), &app_state, id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,283 @@
+//! Command line interface
+//!
+//! Design:
+//!
+//! - `serve` is the default command, so `axum-cms --port 3000` still serves.
+//! - Dev initialization (which recreates the dev db) only runs with `serve --dev-init`.
+//! - Command outputs are printed on stdout (for scripting), logs go to stderr.
+//! - `--config` and `--set` are the CLI layer of the config (see `config`), for all the commands.
+//!
+
+mod author;
+mod seed;
+mod serve;
+
+use std::io::BufRead;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::{Args, Parser, Subcommand};
+use tracing::info;
+
+use crate::config::{init_config, Config, ConfigOverrides};
+use crate::log;
+use crate::models::AppState;
+
+#[derive(Debug, Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+	#[command(subcommand)]
+	pub command: Option<Command>,
+
+	/// TOML config file (instead of `SERVICE_CONFIG_FILE`)
+	#[arg(long, global = true, value_name = "FILE")]
+	pub config: Option<PathBuf>,
+
+	/// Config value, overriding the file and env (e.g., `--set db.max_connections=10`)
+	#[arg(long = "set", global = true, value_name = "KEY=VALUE", value_parser = parse_key_value)]
+	pub sets: Vec<(String, String)>,
+
+	/// (When no command is given, i.e., `serve`)
+	#[command(flatten)]
+	pub serve: ServeArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+	/// Start the server (default command)
+	Serve(ServeArgs),
+	/// Schema migrations
+	Migrate {
+		#[command(subcommand)]
+		command: Option<MigrateCommand>,
+	},
+	/// Seed the db from a JSON fixtures file
+	Seed {
+		#[arg(long)]
+		fixtures: PathBuf,
+	},
+	/// Author administration
+	Author {
+		#[command(subcommand)]
+		command: AuthorCommand,
+	},
+	/// Web tokens, for scripting
+	Token {
+		#[command(subcommand)]
+		command: TokenCommand,
+	},
+	/// Config
+	Config {
+		#[command(subcommand)]
+		command: ConfigCommand,
+	},
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+	/// Overrides `server.bind` (default `127.0.0.1`)
+	#[arg(long)]
+	pub bind: Option<IpAddr>,
+	/// Overrides `server.port` (default `3000`)
+	#[arg(long)]
+	pub port: Option<u16>,
+	/// FOR DEV ONLY - Recreate and seed the dev db before serving
+	#[arg(long)]
+	pub dev_init: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommand {
+	/// Apply the pending migrations (default)
+	Up,
+	/// Revert the migrations above `version`
+	Down { version: i64 },
+	/// List the migrations, applied or pending
+	Status,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AuthorCommand {
+	/// Create an author (password read from stdin when `--password` is not given)
+	Create {
+		#[arg(long)]
+		name: String,
+		#[arg(long)]
+		email: String,
+		#[arg(long)]
+		password: Option<String>,
+		#[arg(long)]
+		admin: bool,
+	},
+	/// Set the password of an author (read from stdin when `--password` is not given), logging out its sessions
+	ResetPassword {
+		#[arg(long)]
+		email: String,
+		#[arg(long)]
+		password: Option<String>,
+	},
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TokenCommand {
+	/// Print a web token of the author (the `auth-token` cookie value)
+	Issue {
+		#[arg(long)]
+		email: String,
+	},
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+	/// Load and validate the config (all the layers), and print it (secrets redacted)
+	Check,
+}
+
+pub async fn run(cli: Cli) -> Result<()> {
+	let command = cli.command.unwrap_or(Command::Serve(cli.serve));
+
+	// -- CLI config layer
+	let mut overrides = ConfigOverrides {
+		file: cli.config,
+		sets: cli.sets,
+	};
+	if let Command::Serve(args) = &command {
+		if let Some(bind) = args.bind {
+			overrides.sets.push(("server.bind".to_string(), bind.to_string()));
+		}
+		if let Some(port) = args.port {
+			overrides.sets.push(("server.port".to_string(), port.to_string()));
+		}
+	}
+
+	if let Command::Config { command: ConfigCommand::Check } = command {
+		return config_check(&overrides);
+	}
+
+	let config = init_config(&overrides)?;
+	log::init_tracing(config.logging.otlp_traces_endpoint.as_deref())?;
+
+	let res = match command {
+		Command::Serve(args) => serve::serve(args).await,
+		Command::Migrate { command } => migrate(command.unwrap_or(MigrateCommand::Up)).await,
+		Command::Seed { fixtures } => seed::seed(&AppState::new().await?, &fixtures).await.map(|_| ()),
+		Command::Author { command } => author::run(command).await,
+		Command::Token { command: TokenCommand::Issue { email } } => author::token_issue(&email).await,
+		Command::Config { .. } => unreachable!("handled above"),
+	};
+
+	log::shutdown_tracing();
+
+	res
+}
+
+async fn migrate(command: MigrateCommand) -> Result<()> {
+	let app_state = AppState::new().await?;
+
+	match command {
+		MigrateCommand::Up => {
+			let versions = app_state.db_migrate_up().await?;
+			info!("{:<12} - applied {versions:?}", "MIGRATION");
+		}
+		MigrateCommand::Down { version } => {
+			let versions = app_state.db_migrate_down(version).await?;
+			info!("{:<12} - reverted {versions:?}", "MIGRATION");
+		}
+		MigrateCommand::Status => {
+			for status in app_state.db_migration_status().await? {
+				let state = match (status.applied_at, status.known, status.checksum_ok) {
+					(_, false, _) => "applied (unknown to this binary)".to_string(),
+					(Some(_), _, false) => "applied (checksum mismatch)".to_string(),
+					(Some(applied_at), ..) => format!("applied {applied_at}"),
+					(None, ..) => "pending".to_string(),
+				};
+				println!("{:>4} {:<24} {state}", status.version, status.name);
+			}
+		}
+	}
+
+	Ok(())
+}
+
+fn config_check(overrides: &ConfigOverrides) -> Result<()> {
+	let config = Config::load(overrides)?;
+
+	println!("config OK");
+	println!("{config:#?}");
+
+	Ok(())
+}
+
+fn parse_key_value(arg: &str) -> Result<(String, String)> {
+	let Some((key, value)) = arg.split_once('=') else {
+		bail!("expected KEY=VALUE");
+	};
+
+	Ok((key.trim().to_string(), value.to_string()))
+}
+
+/// `arg`, or the first line of stdin.
+fn arg_or_stdin(arg: Option<String>, prompt: &str) -> Result<String> {
+	if let Some(arg) = arg {
+		return Ok(arg);
+	}
+
+	eprintln!("{prompt}:");
+	let mut line = String::new();
+	std::io::stdin().lock().read_line(&mut line)?;
+
+	Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use clap::CommandFactory;
+
+	#[test]
+	fn test_cli_debug_assert() {
+		Cli::command().debug_assert();
+	}
+
+	#[test]
+	fn test_cli_default_serve_ok() -> Result<()> {
+		// -- Exec
+		let cli = Cli::try_parse_from(["axum-cms", "--port", "3001"])?;
+
+		// -- Check
+		assert!(cli.command.is_none());
+		assert_eq!(cli.serve.port, Some(3001));
+		assert_eq!(cli.serve.bind, None);
+		assert!(!cli.serve.dev_init);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_cli_subcommands_ok() -> Result<()> {
+		// -- Exec & Check
+		let cli = Cli::try_parse_from(["axum-cms", "migrate", "down", "1"])?;
+		assert!(matches!(
+			cli.command,
+			Some(Command::Migrate { command: Some(MigrateCommand::Down { version: 1 }) })
+		));
+
+		let cli = Cli::try_parse_from(["axum-cms", "author", "create", "--name", "fx", "--email", "fx@mail", "--admin"])?;
+		assert!(matches!(
+			cli.command,
+			Some(Command::Author { command: AuthorCommand::Create { admin: true, password: None, .. } })
+		));
+
+		let cli = Cli::try_parse_from(["axum-cms", "config", "check", "--config", "fx.toml", "--set", "db.max_connections=10"])?;
+		assert_eq!(cli.config.as_deref(), Some(std::path::Path::new("fx.toml")));
+		assert_eq!(cli.sets, [("db.max_connections".to_string(), "10".to_string())]);
+
+		// (serve args are not accepted with another command)
+		assert!(Cli::try_parse_from(["axum-cms", "--port", "3001", "migrate"]).is_err());
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,146 @@
+//! `seed --fixtures <file>`, e.g.:
+//!
+//! ```json
+//! {
+//!   "authors": [{ "name": "Jen", "email": "jen@mail", "password": "welcome1", "admin": true }],
+//!   "posts": [{ "title": "Hello", "content": "World", "author_email": "jen@mail" }]
+//! }
+//! ```
+//!
+//! Authors already in the db (by email) are kept as-is, so a file can be seeded again for its posts.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use tracing::info;
+use validator::Validate;
+
+use crate::ctx::Ctx;
+use crate::models::author::{Author, AuthorBmc, AuthorForCreate};
+use crate::models::post::{PostBmc, PostForCreate};
+use crate::models::AppState;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fixtures {
+	#[serde(default)]
+	pub authors: Vec<AuthorFixture>,
+	#[serde(default)]
+	pub posts: Vec<PostFixture>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorFixture {
+	pub name: String,
+	pub email: String,
+	pub password: String,
+	#[serde(default)]
+	pub admin: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PostFixture {
+	pub title: String,
+	pub content: String,
+	pub author_email: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SeedReport {
+	pub authors_created: usize,
+	pub authors_existing: usize,
+	pub posts_created: usize,
+}
+
+pub async fn seed(app_state: &AppState, fixtures_path: &Path) -> Result<SeedReport> {
+	let content = fs::read_to_string(fixtures_path)
+		.with_context(|| format!("cannot read fixtures file {}", fixtures_path.display()))?;
+	let fixtures: Fixtures = serde_json::from_str(&content)
+		.with_context(|| format!("invalid fixtures file {}", fixtures_path.display()))?;
+
+	let report = seed_fixtures(app_state, fixtures).await?;
+	info!("{:<12} - {report:?}", "SEED");
+
+	Ok(report)
+}
+
+async fn seed_fixtures(app_state: &AppState, fixtures: Fixtures) -> Result<SeedReport> {
+	let ctx = Ctx::root_ctx();
+	let mut report = SeedReport::default();
+
+	for author in fixtures.authors {
+		if AuthorBmc::first_by_email::<Author>(app_state, &author.email).await?.is_some() {
+			report.authors_existing += 1;
+			continue;
+		}
+
+		let data = AuthorForCreate {
+			name: author.name,
+			email: author.email,
+			password: author.password,
+		};
+		super::author::create(app_state, data, author.admin).await?;
+		report.authors_created += 1;
+	}
+
+	for post in fixtures.posts {
+		let author = AuthorBmc::first_by_email::<Author>(app_state, &post.author_email)
+			.await?
+			.ok_or_else(|| anyhow!("post '{}' - no author with email '{}'", post.title, post.author_email))?;
+
+		let data = PostForCreate {
+			title: post.title,
+			content: post.content,
+			author_id: author.id,
+		};
+		data.validate()?;
+		PostBmc::create(&ctx, app_state, data).await?;
+		report.posts_created += 1;
+	}
+
+	Ok(report)
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_seed_ok_authors_kept() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let fx_path = std::env::temp_dir().join(format!("fx-seed-{}.json", uuid::Uuid::new_v4()));
+		fs::write(
+			&fx_path,
+			r#"{
+				"authors": [{ "name": "fx seed", "email": "fx-seed@mail", "password": "fx-pwd-01" }],
+				"posts": [{ "title": "fx seed title", "content": "fx seed content", "author_email": "fx-seed@mail" }]
+			}"#,
+		)?;
+
+		// -- Exec
+		let report_1 = seed(&app_state, &fx_path).await?;
+		let report_2 = seed(&app_state, &fx_path).await?;
+
+		// -- Check
+		assert_eq!(report_1, SeedReport { authors_created: 1, authors_existing: 0, posts_created: 1 });
+		assert_eq!(report_2, SeedReport { authors_created: 0, authors_existing: 1, posts_created: 1 });
+
+		// -- Clean
+		let author: Author = AuthorBmc::first_by_email(&app_state, "fx-seed@mail").await?.unwrap();
+		sqlx::query(r#"DELETE FROM "posts" WHERE author_id = $1"#).bind(author.id).execute(&app_state.pool).await?;
+		AuthorBmc::delete(&Ctx::root_ctx(), &app_state, author.id).await?;
+		fs::remove_file(&fx_path)?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,139 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::Result;
+use axum::{middleware, Router};
+use tower_cookies::CookieManagerLayer;
+use tracing::{info, warn};
+
+use super::ServeArgs;
+use crate::_dev_utils;
+use crate::config;
+use crate::log::RequestLogger;
+use crate::models::AppState;
+use crate::web::handlers::routes_static;
+use crate::web::metrics::{self, init_metrics};
+use crate::web::middlewares::auth::mw_ctx_resolve;
+use crate::web::middlewares::cors::cors_layer;
+use crate::web::middlewares::csrf::mw_csrf_check;
+use crate::web::middlewares::metrics::mw_metrics;
+use crate::web::middlewares::res_map::main_response_mapper;
+use crate::web::middlewares::security_headers::{mw_security_headers, SecurityHeaders};
+use crate::web::middlewares::stamp::mw_req_stamp;
+use crate::web::routes::routes_main;
+use crate::web::{routes_feed, routes_health, routes_login, routes_oidc, routes_sitemap};
+
+/// Max time for the background tasks to end at shutdown (e.g., log sinks flushed)
+const BACKGROUND_TASKS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serve until SIGTERM/SIGINT, then shut down gracefully (see `shutdown`).
+pub async fn serve(args: ServeArgs) -> Result<()> {
+	// -- FOR DEV ONLY
+	if args.dev_init {
+		_dev_utils::init_dev().await;
+	} else if config().server.public_url.is_none() {
+		// (a shared cache would then serve links to whatever host a client sent)
+		warn!("{:<12} - `server.public_url` not set, the feed and sitemap links use the request `Host`", "CONFIG");
+	}
+
+	// Initialize ModelManager.
+	let app_state = AppState::new().await?;
+
+	// -- Schema migrations, refusing to start if the db is ahead of (or, when not migrating, behind) this binary.
+	if config().db.migrate_on_start {
+		let versions = app_state.db_migrate_up().await?;
+		info!("{:<12} - applied {versions:?}", "MIGRATION");
+	} else {
+		app_state.db_schema_check().await?;
+	}
+
+	// Install the metrics recorder.
+	init_metrics()?;
+
+	// Start the request log sinks.
+	let shutdown = app_state.shutdown.clone();
+	let request_logger = RequestLogger::from_config(&config().logging.sinks, &shutdown);
+
+	// Start the media processing (incl. the media left pending).
+	app_state.media_processor.start(&app_state);
+	app_state.webhook_dispatcher.start(&app_state);
+
+	// -- Define Routes
+	let mut routes_all = Router::new()
+		.merge(routes_login::routes(app_state.clone()))
+		.merge(routes_feed::routes(app_state.clone()))
+		.merge(routes_sitemap::routes(app_state.clone()))
+		.merge(routes_oidc::routes(app_state.clone()))
+		.nest("/api", routes_main(app_state.clone()));
+
+	// -- Metrics, on the admin address if set.
+	match config().server.metrics_addr {
+		Some(metrics_addr) => {
+			let routes_metrics = metrics::routes(app_state.clone());
+			info!("{:<12} - {metrics_addr} (metrics)\n", "LISTENING");
+			let metrics_server = axum::Server::try_bind(&metrics_addr)?.serve(routes_metrics.into_make_service());
+			shutdown.spawn(|stop| async move {
+				if let Err(ex) = metrics_server.with_graceful_shutdown(stop.cancelled_owned()).await {
+					warn!("{:<12} - metrics server failed - {ex}", "SHUTDOWN");
+				}
+			});
+		}
+		None => routes_all = routes_all.merge(metrics::routes(app_state.clone())),
+	}
+
+	let routes_all = routes_all
+		.layer(middleware::from_fn(mw_csrf_check))
+		.layer(middleware::map_response_with_state(request_logger, main_response_mapper))
+		.layer(middleware::from_fn_with_state(app_state.clone(), mw_ctx_resolve))
+		.layer(CookieManagerLayer::new())
+		.layer(middleware::from_fn(mw_metrics))
+		.layer(middleware::from_fn(mw_req_stamp))
+		// Health probes, outside the layers above (no auth, request log nor metrics).
+		.merge(routes_health::routes(app_state.clone()))
+		.fallback_service(routes_static::serve_dir().layer(middleware::map_response_with_state(
+			SecurityHeaders::from_config_static(&config().security_headers),
+			mw_security_headers,
+		)))
+		// Security headers and CORS, of all the responses (the static files keeping their own security headers).
+		.layer(middleware::map_response_with_state(
+			SecurityHeaders::from_config(&config().security_headers),
+			mw_security_headers,
+		));
+	let routes_all = match cors_layer(&config().cors) {
+		Some(cors_layer) => routes_all.layer(cors_layer),
+		None => routes_all,
+	};
+
+	// region:    --- Start Server
+	let server_config = &config().server;
+	let shutdown_delay = Duration::from_secs(server_config.shutdown_delay_sec);
+	let drain_timeout = Duration::from_secs(server_config.drain_timeout_sec);
+
+	let addr = SocketAddr::new(server_config.bind, server_config.port);
+	let server = axum::Server::try_bind(&addr)?
+		.serve(routes_all.into_make_service_with_connect_info::<SocketAddr>())
+		.with_graceful_shutdown(async {
+			shutdown.draining().await;
+			tokio::time::sleep(shutdown_delay).await;
+			info!("{:<12} - not accepting connections, draining in-flight requests", "SHUTDOWN");
+		});
+	info!("{:<12} - {addr}\n", "LISTENING");
+	shutdown.listen_signals();
+
+	tokio::select! {
+		res = server => res?,
+		_ = async {
+			shutdown.draining().await;
+			tokio::time::sleep(shutdown_delay + drain_timeout).await;
+		} => warn!("{:<12} - drain timeout ({drain_timeout:?}), in-flight requests dropped", "SHUTDOWN"),
+	}
+	// endregion: --- Start Server
+
+	// region:    --- Shutdown
+	shutdown.stop_tasks(BACKGROUND_TASKS_TIMEOUT).await;
+	app_state.pool.close().await;
+	info!("{:<12} - done", "SHUTDOWN");
+	// endregion: --- Shutdown
+
+	Ok(())
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use super::ConfigSource;
+
+pub type ConfigResult<T> = core::result::Result<T, ConfigError>;
+
+/// All the issues of a config load (not only the first one).
+#[derive(Debug)]
+pub struct ConfigError {
+	pub issues: Vec<ConfigIssue>,
+}
+
+#[derive(Debug)]
+pub enum ConfigIssue {
+	FileRead { path: PathBuf, cause: String },
+	FileParse { path: PathBuf, cause: String },
+	UnknownKey { key: String, source: ConfigSource },
+	Missing { key: &'static str, env: &'static str },
+	WrongFormat { key: &'static str, source: ConfigSource, cause: String },
+	Invalid { key: &'static str, reason: String },
+}
+
+impl ConfigIssue {
+	pub fn key(&self) -> Option<&str> {
+		match self {
+			Self::FileRead { .. } | Self::FileParse { .. } => None,
+			Self::UnknownKey { key, .. } => Some(key),
+			Self::Missing { key, .. } | Self::WrongFormat { key, .. } | Self::Invalid { key, .. } => Some(key),
+		}
+	}
+}
+
+impl fmt::Display for ConfigIssue {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::FileRead { path, cause } => write!(f, "cannot read {} - {cause}", path.display()),
+			Self::FileParse { path, cause } => write!(f, "invalid TOML in {} - {cause}", path.display()),
+			Self::UnknownKey { key, source } => write!(f, "{key} - unknown key (from {source})"),
+			Self::Missing { key, env } => write!(f, "{key} - missing (set it in the config file, or with {env})"),
+			Self::WrongFormat { key, source, cause } => write!(f, "{key} - wrong format (from {source}) - {cause}"),
+			Self::Invalid { key, reason } => write!(f, "{key} - {reason}"),
+		}
+	}
+}
+
+// region:    --- Error Boilerplate
+impl fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} config issue(s)", self.issues.len())?;
+		for issue in &self.issues {
+			write!(f, "\n  - {issue}")?;
+		}
+
+		Ok(())
+	}
+}
+
+impl std::error::Error for ConfigError {}
+// endregion: --- Error Boilerplate
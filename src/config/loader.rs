@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+use super::error::ConfigIssue;
+use super::{ConfigOverrides, ConfigSource};
+
+/// Env variable of the TOML config file (when not given with `--config`)
+pub const CONFIG_FILE_ENV: &str = "SERVICE_CONFIG_FILE";
+
+/// All the config keys (`section.key`), with their env variable.
+pub const KEYS: &[(&str, &str)] = &[
+	// -- Server
+	("server.bind", "SERVICE_BIND"),
+	("server.port", "SERVICE_PORT"),
+	("server.web_folder", "SERVICE_WEB_FOLDER"),
+	("server.metrics_addr", "SERVICE_METRICS_ADDR"),
+	("server.shutdown_delay_sec", "SERVICE_SHUTDOWN_DELAY_SEC"),
+	("server.drain_timeout_sec", "SERVICE_DRAIN_TIMEOUT_SEC"),
+	("server.require_if_match", "SERVICE_REQUIRE_IF_MATCH"),
+	("server.public_url", "SERVICE_PUBLIC_URL"),
+	("server.post_page_url", "SERVICE_POST_PAGE_URL"),
+	("server.author_page_url", "SERVICE_AUTHOR_PAGE_URL"),
+	("server.trusted_proxies", "SERVICE_TRUSTED_PROXIES"),
+	("server.forwarded_header", "SERVICE_FORWARDED_HEADER"),
+	// -- Db
+	("db.url", "SERVICE_DB_URL"),
+	("db.max_connections", "SERVICE_DB_MAX_CONNECTIONS"),
+	("db.acquire_timeout_ms", "SERVICE_DB_ACQUIRE_TIMEOUT_MS"),
+	("db.migrate_on_start", "SERVICE_DB_MIGRATE_ON_START"),
+	// -- Auth
+	("auth.pwd_key", "SERVICE_PWD_KEY"),
+	("auth.token_key", "SERVICE_TOKEN_KEY"),
+	("auth.token_duration_sec", "SERVICE_TOKEN_DURATION_SEC"),
+	("auth.oidc_providers", "SERVICE_OIDC_PROVIDERS"),
+	// -- Cookies
+	("cookies.secure", "SERVICE_COOKIE_SECURE"),
+	("cookies.same_site", "SERVICE_COOKIE_SAME_SITE"),
+	("cookies.domain", "SERVICE_COOKIE_DOMAIN"),
+	("cookies.max_age_sec", "SERVICE_COOKIE_MAX_AGE_SEC"),
+	// -- CORS
+	("cors.allowed_origins", "SERVICE_CORS_ALLOWED_ORIGINS"),
+	("cors.allow_credentials", "SERVICE_CORS_ALLOW_CREDENTIALS"),
+	("cors.allowed_methods", "SERVICE_CORS_ALLOWED_METHODS"),
+	("cors.max_age_sec", "SERVICE_CORS_MAX_AGE_SEC"),
+	// -- Security headers
+	("security_headers.enabled", "SERVICE_SECURITY_HEADERS_ENABLED"),
+	("security_headers.content_security_policy", "SERVICE_CSP"),
+	("security_headers.frame_ancestors", "SERVICE_FRAME_ANCESTORS"),
+	("security_headers.referrer_policy", "SERVICE_REFERRER_POLICY"),
+	("security_headers.hsts_max_age_sec", "SERVICE_HSTS_MAX_AGE_SEC"),
+	("security_headers.static_files", "SERVICE_SECURITY_HEADERS_STATIC_FILES"),
+	// -- Cache
+	("cache.backend", "SERVICE_CACHE_BACKEND"),
+	("cache.capacity", "SERVICE_CACHE_CAPACITY"),
+	("cache.ttl_sec", "SERVICE_CACHE_TTL_SEC"),
+	("cache.redis_url", "SERVICE_CACHE_REDIS_URL"),
+	// -- Rate limit
+	("rate_limit.enabled", "SERVICE_RATE_LIMIT_ENABLED"),
+	("rate_limit.store", "SERVICE_RATE_LIMIT_STORE"),
+	("rate_limit.redis_url", "SERVICE_RATE_LIMIT_REDIS_URL"),
+	("rate_limit.groups", "SERVICE_RATE_LIMIT_GROUPS"),
+	// -- Media
+	("media.storage", "SERVICE_MEDIA_STORAGE"),
+	("media.local_dir", "SERVICE_MEDIA_LOCAL_DIR"),
+	("media.s3", "SERVICE_MEDIA_S3"),
+	("media.max_size_bytes", "SERVICE_MEDIA_MAX_SIZE_BYTES"),
+	("media.allowed_types", "SERVICE_MEDIA_ALLOWED_TYPES"),
+	("media.process_images", "SERVICE_MEDIA_PROCESS_IMAGES"),
+	("media.renditions", "SERVICE_MEDIA_RENDITIONS"),
+	("media.max_image_pixels", "SERVICE_MEDIA_MAX_IMAGE_PIXELS"),
+	("media.resize_max_dimension", "SERVICE_MEDIA_RESIZE_MAX_DIMENSION"),
+	("media.workers", "SERVICE_MEDIA_WORKERS"),
+	// -- Feeds
+	("feeds.title", "SERVICE_FEEDS_TITLE"),
+	("feeds.description", "SERVICE_FEEDS_DESCRIPTION"),
+	("feeds.max_items", "SERVICE_FEEDS_MAX_ITEMS"),
+	// -- Webhooks
+	("webhooks.enabled", "SERVICE_WEBHOOKS_ENABLED"),
+	("webhooks.timeout_ms", "SERVICE_WEBHOOKS_TIMEOUT_MS"),
+	("webhooks.max_attempts", "SERVICE_WEBHOOKS_MAX_ATTEMPTS"),
+	("webhooks.backoff_base_sec", "SERVICE_WEBHOOKS_BACKOFF_BASE_SEC"),
+	("webhooks.backoff_max_sec", "SERVICE_WEBHOOKS_BACKOFF_MAX_SEC"),
+	("webhooks.poll_interval_sec", "SERVICE_WEBHOOKS_POLL_INTERVAL_SEC"),
+	("webhooks.workers", "SERVICE_WEBHOOKS_WORKERS"),
+	("webhooks.allowed_private_targets", "SERVICE_WEBHOOKS_ALLOWED_PRIVATE_TARGETS"),
+	// -- Robots
+	("robots.disallow", "SERVICE_ROBOTS_DISALLOW"),
+	("robots.content", "SERVICE_ROBOTS_CONTENT"),
+	// -- Logging
+	("logging.sinks", "SERVICE_LOG_SINKS"),
+	("logging.otlp_traces_endpoint", "SERVICE_OTLP_TRACES_ENDPOINT"),
+];
+
+enum RawValue {
+	/// From the TOML file
+	Toml(toml::Value),
+	/// From env or CLI, a string or JSON (e.g., `1800`, `true`, `[{"type": "stdout"}]`)
+	Str(String),
+}
+
+/// The layered raw values, read into typed values with the getters below,
+/// which collect the issues instead of stopping at the first one.
+pub struct Loader {
+	values: BTreeMap<&'static str, (RawValue, ConfigSource)>,
+	pub issues: Vec<ConfigIssue>,
+}
+
+impl Loader {
+	pub fn new(overrides: &ConfigOverrides, env: impl Fn(&str) -> Option<String>) -> Self {
+		let mut loader = Loader {
+			values: BTreeMap::new(),
+			issues: Vec::new(),
+		};
+
+		// -- File
+		let file = overrides.file.clone().or_else(|| env(CONFIG_FILE_ENV).map(PathBuf::from));
+		if let Some(path) = file {
+			loader.add_file(&path);
+		}
+
+		// -- Env (empty is the same as not set)
+		for (key, env_name) in KEYS {
+			if let Some(value) = env(env_name).filter(|value| !value.is_empty()) {
+				loader.values.insert(key, (RawValue::Str(value), ConfigSource::Env(env_name)));
+			}
+		}
+
+		// -- CLI
+		for (key, value) in &overrides.sets {
+			match known_key(key) {
+				Some(key) => {
+					loader.values.insert(key, (RawValue::Str(value.clone()), ConfigSource::Cli));
+				}
+				None => loader.issues.push(ConfigIssue::UnknownKey {
+					key: key.clone(),
+					source: ConfigSource::Cli,
+				}),
+			}
+		}
+
+		loader
+	}
+
+	fn add_file(&mut self, path: &Path) {
+		let content = match fs::read_to_string(path) {
+			Ok(content) => content,
+			Err(ex) => {
+				let cause = ex.to_string();
+				return self.issues.push(ConfigIssue::FileRead { path: path.to_path_buf(), cause });
+			}
+		};
+		let table: toml::Table = match toml::from_str(&content) {
+			Ok(table) => table,
+			Err(ex) => {
+				let cause = ex.message().to_string();
+				return self.issues.push(ConfigIssue::FileParse { path: path.to_path_buf(), cause });
+			}
+		};
+
+		let source = ConfigSource::File(path.to_path_buf());
+		for (section, section_value) in table {
+			let toml::Value::Table(section_table) = section_value else {
+				self.issues.push(ConfigIssue::UnknownKey { key: section, source: source.clone() });
+				continue;
+			};
+
+			for (name, value) in section_table {
+				let key = format!("{section}.{name}");
+				match known_key(&key) {
+					Some(key) => {
+						self.values.insert(key, (RawValue::Toml(value), source.clone()));
+					}
+					None => self.issues.push(ConfigIssue::UnknownKey { key, source: source.clone() }),
+				}
+			}
+		}
+	}
+
+	pub fn sources(&self) -> BTreeMap<&'static str, ConfigSource> {
+		self.values.iter().map(|(key, (_, source))| (*key, source.clone())).collect()
+	}
+
+	/// The value of `key`, `None` when not set (or in the wrong format, recorded as an issue).
+	pub fn opt<T: DeserializeOwned>(&mut self, key: &'static str) -> Option<T> {
+		let (raw, source) = self.values.get(key)?;
+
+		let res = match raw {
+			RawValue::Toml(value) => value.clone().try_into::<T>().map_err(|ex| ex.to_string()),
+			RawValue::Str(value) => toml::Value::String(value.clone())
+				.try_into::<T>()
+				.or_else(|ex| serde_json::from_str::<T>(value).map_err(|_| ex.to_string())),
+		};
+
+		match res {
+			Ok(value) => Some(value),
+			Err(cause) => {
+				let source = source.clone();
+				self.issues.push(ConfigIssue::WrongFormat { key, source, cause });
+				None
+			}
+		}
+	}
+
+	pub fn or<T: DeserializeOwned>(&mut self, key: &'static str, default: T) -> T {
+		self.opt(key).unwrap_or(default)
+	}
+
+	/// The value of `key`, recording an issue when not set (the returned default is then never used).
+	pub fn req<T: DeserializeOwned + Default>(&mut self, key: &'static str) -> T {
+		if !self.values.contains_key(key) {
+			let env = KEYS.iter().find(|(k, _)| *k == key).map(|(_, env)| *env).unwrap_or_default();
+			self.issues.push(ConfigIssue::Missing { key, env });
+		}
+
+		self.opt(key).unwrap_or_default()
+	}
+
+	/// Required base64url value (e.g., keys), decoded.
+	pub fn req_b64u(&mut self, key: &'static str) -> Vec<u8> {
+		let value: String = self.req(key);
+		if value.is_empty() {
+			return Vec::new();
+		}
+
+		base64_url::decode(&value).unwrap_or_else(|ex| {
+			let source = self.values.get(key).map(|(_, source)| source.clone()).unwrap_or(ConfigSource::Cli);
+			self.issues.push(ConfigIssue::WrongFormat { key, source, cause: format!("not base64url - {ex}") });
+			Vec::new()
+		})
+	}
+
+	/// Record `reason` when not `ok`, unless the key already has an issue (e.g., missing).
+	pub fn check(&mut self, key: &'static str, ok: bool, reason: &str) {
+		if !ok && !self.issues.iter().any(|issue| issue.key() == Some(key)) {
+			self.issues.push(ConfigIssue::Invalid { key, reason: reason.to_string() });
+		}
+	}
+}
+
+fn known_key(key: &str) -> Option<&'static str> {
+	KEYS.iter().find(|(k, _)| *k == key).map(|(k, _)| *k)
+}
@@ -16,7 +16,14 @@ pub enum CryptError {
 	TokenCannotDecodeExp,
 	TokenSignatureNotMatching,
 	TokenExpNotIso,
-	TokenExpired
+	TokenExpired,
+
+	// Totp
+	TotpSecretNotBase32,
+	TotpNotMatching,
+
+	// Url signature
+	UrlSignatureNotMatching
 }
 
 // region:    --- Error Boilerplate
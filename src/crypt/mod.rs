@@ -3,6 +3,9 @@
 mod error;
 pub mod pwd;
 pub mod token;
+pub mod totp;
+pub mod url_sig;
+pub mod webhook_sig;
 
 pub use self::error::{CryptError, CryptResult};
 
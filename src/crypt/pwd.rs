@@ -2,9 +2,13 @@ use super::{CryptError, CryptResult};
 use crate::config;
 use crate::crypt::{encrypt_into_b64url, EncryptContent};
 
+/// Salt and reference used by `validate_pwd_dummy` (never matches a real password).
+const DUMMY_SALT: &str = "00000000-0000-0000-0000-000000000000";
+const DUMMY_PWD_REF: &str = "#01#dummy";
+
 /// Encrypt password with default scheme.
 pub fn encrypt_pwd(encrypt_content: &EncryptContent) -> CryptResult<String> {
-	let key = &config().PWD_KEY;
+	let key = &config().auth.pwd_key;
 
 	let encrypted = encrypt_into_b64url(key, encrypt_content)?;
 
@@ -15,11 +19,81 @@ pub fn encrypt_pwd(encrypt_content: &EncryptContent) -> CryptResult<String> {
 pub fn validate_pwd(encrypt_content: &EncryptContent, pwd_ref: &str) -> CryptResult<()> {
 	let pwd = encrypt_pwd(encrypt_content)?;
 
-	if pwd == pwd_ref {
+	if constant_time_eq(pwd.as_bytes(), pwd_ref.as_bytes()) {
 		Ok(())
 	} else {
 		Err(CryptError::PwdNotMatching)
 	}
+}
+
+/// Run a full password validation against a throwaway reference.
+///
+/// Used when there is no password to check against (e.g., unknown email),
+/// so that the login takes the same time whether the author exists or not.
+pub fn validate_pwd_dummy(pwd_clear: &str) {
+	let _ = validate_pwd(
+		&EncryptContent {
+			content: pwd_clear.to_string(),
+			salt: DUMMY_SALT.to_string(),
+		},
+		DUMMY_PWD_REF,
+	);
+}
+
+/// Compare without short-circuiting on the first differing byte.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	if a.len() != b.len() {
+		return false;
+	}
+
+	a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[test]
+	fn test_validate_pwd_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_content = EncryptContent {
+			content: "welcome".to_string(),
+			salt: "some-salt".to_string(),
+		};
+		let fx_pwd_ref = encrypt_pwd(&fx_content)?;
 
+		// -- Exec & Check
+		validate_pwd(&fx_content, &fx_pwd_ref)?;
 
-}
\ No newline at end of file
+		Ok(())
+	}
+
+	#[test]
+	fn test_validate_pwd_err_not_matching() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_pwd_ref = encrypt_pwd(&EncryptContent {
+			content: "welcome".to_string(),
+			salt: "some-salt".to_string(),
+		})?;
+
+		// -- Exec
+		let res = validate_pwd(
+			&EncryptContent {
+				content: "welcome2".to_string(),
+				salt: "some-salt".to_string(),
+			},
+			&fx_pwd_ref,
+		);
+
+		// -- Check
+		assert!(
+			matches!(res, Err(CryptError::PwdNotMatching)),
+			"Should have matched `Err(CryptError::PwdNotMatching)` but was `{res:?}`"
+		);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
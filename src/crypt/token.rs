@@ -59,18 +59,42 @@ impl Display for Token {
 pub fn generate_web_token(user: &str, salt: &str) -> CryptResult<Token> {
 	let config = &config();
 
-	_generate_token(user, config.TOKEN_DURATION_SEC, salt, &config.TOKEN_KEY)
+	_generate_token(user, config.auth.token_duration_sec, salt, &config.auth.token_key)
 }
 
 pub fn validate_web_token(origin_token: &Token, salt: &str) -> CryptResult<()>{
 	let config = &config();
 
-	_validate_token_sign_and_exp(origin_token, salt, &config.TOKEN_KEY)?;
+	_validate_token_sign_and_exp(origin_token, salt, &config.auth.token_key)?;
 
 	Ok(())
 }
 // endregion: --- Web token Gen and Validation
 
+// region:    --- 2FA challenge token Gen and Validation
+
+/// A login pending its second factor must be completed within this duration.
+const TWO_FACTOR_CHALLENGE_DURATION_SEC: f64 = 300.0;
+
+/// Appended to the salt, so a challenge token can never be used as a web token.
+const TWO_FACTOR_CHALLENGE_SALT_SUFFIX: &str = "-2fa-challenge";
+
+pub fn generate_2fa_challenge_token(user: &str, salt: &str) -> CryptResult<Token> {
+	let config = &config();
+	let salt = format!("{salt}{TWO_FACTOR_CHALLENGE_SALT_SUFFIX}");
+
+	_generate_token(user, TWO_FACTOR_CHALLENGE_DURATION_SEC, &salt, &config.auth.token_key)
+}
+
+pub fn validate_2fa_challenge_token(origin_token: &Token, salt: &str) -> CryptResult<()> {
+	let config = &config();
+	let salt = format!("{salt}{TWO_FACTOR_CHALLENGE_SALT_SUFFIX}");
+
+	_validate_token_sign_and_exp(origin_token, &salt, &config.auth.token_key)
+}
+
+// endregion: --- 2FA challenge token Gen and Validation
+
 // region:    --- (private) Token generation an validation
 
 fn _generate_token(
@@ -171,7 +195,7 @@ mod tests {
 		let fx_user = "user_one";
 		let fx_salt = "pepper";
 		let fx_duration_sec = 0.02; //20ms
-		let token_key = &config().TOKEN_KEY;
+		let token_key = &config().auth.token_key;
 
 		let fx_token = _generate_token(fx_user, fx_duration_sec, fx_salt, token_key)?;
 
@@ -179,7 +203,7 @@ mod tests {
 		thread::sleep(Duration::from_millis(10));
 
 		// -- Check
-		let res = validate_web_token(&fx_token, &fx_salt);
+		let res = validate_web_token(&fx_token, fx_salt);
 
 		res?;
 
@@ -193,13 +217,13 @@ mod tests {
 		let fx_user = "user_one";
 		let fx_salt = "pepper";
 		let fx_duration_sec = 0.01; // 10ms
-		let token_key = &config().TOKEN_KEY;
+		let token_key = &config().auth.token_key;
 
 		let fx_token = _generate_token(fx_user, fx_duration_sec, fx_salt, token_key)?;
 
 		// -- Exec
 		thread::sleep(Duration::from_millis(20)); // sleep for 20 ms, 10ms more than token duration
-		let res = validate_web_token(&fx_token, &fx_salt);
+		let res = validate_web_token(&fx_token, fx_salt);
 
 		assert!(
 			matches!(res, Err(CryptError::TokenExpired)),
@@ -208,6 +232,27 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_validate_2fa_challenge_token_err_web_token() -> Result<()> {
+		// -- Setup & fixtures
+		let fx_user = "user_one";
+		let fx_salt = "pepper";
+
+		let fx_web_token = generate_web_token(fx_user, fx_salt)?;
+		let fx_challenge_token = generate_2fa_challenge_token(fx_user, fx_salt)?;
+
+		// -- Exec
+		let res_web_as_challenge = validate_2fa_challenge_token(&fx_web_token, fx_salt);
+		let res_challenge_as_web = validate_web_token(&fx_challenge_token, fx_salt);
+
+		// -- Check
+		validate_2fa_challenge_token(&fx_challenge_token, fx_salt)?;
+		assert!(matches!(res_web_as_challenge, Err(CryptError::TokenSignatureNotMatching)));
+		assert!(matches!(res_challenge_as_web, Err(CryptError::TokenSignatureNotMatching)));
+
+		Ok(())
+	}
 	
 }
 // endregion: --- Tests
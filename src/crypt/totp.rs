@@ -0,0 +1,225 @@
+//! TOTP (RFC 6238) and recovery codes, for two-factor authentication.
+//!
+//! Codes are HMAC-SHA-1 based, 6 digits, with a 30 seconds period,
+//! which is what authenticator apps expect by default.
+
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::{Rng, RngCore};
+use sha1::Sha1;
+
+use super::pwd::constant_time_eq;
+use super::{encrypt_into_b64url, CryptError, CryptResult, EncryptContent};
+use crate::config;
+use crate::utils::now_utc;
+
+pub const TOTP_ISSUER: &str = "axum-cms";
+const TOTP_DIGITS: u32 = 6;
+const TOTP_PERIOD_SEC: i64 = 30;
+/// Number of periods accepted before/after the current one (clock drift).
+const TOTP_SKEW_STEPS: i64 = 1;
+/// 160 bits, as recommended by RFC 4226.
+const TOTP_SECRET_LEN: usize = 20;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_CHARSET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+// region:    --- Secret
+
+/// Generate a new random TOTP secret, base32 encoded (no padding).
+pub fn generate_totp_secret() -> String {
+	let mut secret = [0u8; TOTP_SECRET_LEN];
+	rand::thread_rng().fill_bytes(&mut secret);
+
+	BASE32_NOPAD.encode(&secret)
+}
+
+/// Build the `otpauth://` URI to be rendered as a QR code by the client.
+pub fn totp_otpauth_uri(secret_b32: &str, account: &str) -> String {
+	let issuer = percent_encode(TOTP_ISSUER);
+	let account = percent_encode(account);
+
+	format!(
+		"otpauth://totp/{issuer}:{account}?secret={secret_b32}&issuer={issuer}&algorithm=SHA1&digits={TOTP_DIGITS}&period={TOTP_PERIOD_SEC}"
+	)
+}
+
+// endregion: --- Secret
+
+// region:    --- Validation
+
+/// Validate a TOTP `code` against the base32 `secret_b32`, for the current time.
+///
+/// Returns the matched time step, which must be greater than `last_step`
+/// (the last step successfully used), so a code cannot be replayed.
+pub fn validate_totp(secret_b32: &str, code: &str, last_step: Option<i64>) -> CryptResult<i64> {
+	let now_step = now_utc().unix_timestamp() / TOTP_PERIOD_SEC;
+
+	_validate_totp_at(secret_b32, code, last_step, now_step)
+}
+
+fn _validate_totp_at(
+	secret_b32: &str,
+	code: &str,
+	last_step: Option<i64>,
+	now_step: i64,
+) -> CryptResult<i64> {
+	let secret = BASE32_NOPAD
+		.decode(secret_b32.as_bytes())
+		.map_err(|_| CryptError::TotpSecretNotBase32)?;
+	let code = code.trim();
+
+	for step in (now_step - TOTP_SKEW_STEPS)..=(now_step + TOTP_SKEW_STEPS) {
+		if last_step.is_some_and(|last| step <= last) {
+			continue;
+		}
+
+		if constant_time_eq(_hotp(&secret, step as u64, TOTP_DIGITS)?.as_bytes(), code.as_bytes()) {
+			return Ok(step);
+		}
+	}
+
+	Err(CryptError::TotpNotMatching)
+}
+
+/// HOTP value (RFC 4226) for `counter`, zero-padded to `digits`.
+fn _hotp(secret: &[u8], counter: u64, digits: u32) -> CryptResult<String> {
+	let mut hmac_sha_1 = Hmac::<Sha1>::new_from_slice(secret).map_err(|_| CryptError::KeyFailHmac)?;
+	hmac_sha_1.update(&counter.to_be_bytes());
+	let hash = hmac_sha_1.finalize().into_bytes();
+
+	// -- Dynamic truncation.
+	let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+	let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+	let value = binary % 10u32.pow(digits);
+
+	Ok(format!("{value:0width$}", width = digits as usize))
+}
+
+/// The code of `secret_b32` for the current time step plus `step_offset` (e.g., to log in tests).
+#[cfg(test)]
+pub fn totp_code_now(secret_b32: &str, step_offset: i64) -> CryptResult<String> {
+	let secret = BASE32_NOPAD
+		.decode(secret_b32.as_bytes())
+		.map_err(|_| CryptError::TotpSecretNotBase32)?;
+	let step = now_utc().unix_timestamp() / TOTP_PERIOD_SEC + step_offset;
+
+	_hotp(&secret, step as u64, TOTP_DIGITS)
+}
+
+// endregion: --- Validation
+
+// region:    --- Recovery codes
+
+/// Generate a new set of clear recovery codes (format: `xxxxx-xxxxx`).
+pub fn generate_recovery_codes() -> Vec<String> {
+	let mut rng = rand::thread_rng();
+
+	(0..RECOVERY_CODE_COUNT)
+		.map(|_| {
+			let mut chars = (0..10).map(|_| {
+				RECOVERY_CODE_CHARSET[rng.gen_range(0..RECOVERY_CODE_CHARSET.len())] as char
+			});
+			let head: String = chars.by_ref().take(5).collect();
+			let tail: String = chars.collect();
+			format!("{head}-{tail}")
+		})
+		.collect()
+}
+
+/// Hash a clear recovery code for storage/lookup, with the author password salt.
+pub fn hash_recovery_code(code_clear: &str, salt: &str) -> CryptResult<String> {
+	let key = &config().auth.pwd_key;
+	let content = code_clear.trim().to_lowercase();
+
+	encrypt_into_b64url(key, &EncryptContent { content, salt: salt.to_string() })
+}
+
+// endregion: --- Recovery codes
+
+fn percent_encode(value: &str) -> String {
+	value
+		.bytes()
+		.map(|b| match b {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+			_ => format!("%{b:02X}"),
+		})
+		.collect()
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	// RFC 6238 appendix B secret ("12345678901234567890"), base32 encoded.
+	const FX_SECRET_B32: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+	#[test]
+	fn test_hotp_rfc6238_vectors_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_secret = b"12345678901234567890";
+		let fx_vectors = [(59, "94287082"), (1111111109, "07081804"), (1234567890, "89005924")];
+
+		// -- Exec & Check
+		for (time, code) in fx_vectors {
+			let res = _hotp(fx_secret, time / 30, 8)?;
+			assert_eq!(res, code);
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_validate_totp_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_now_step = 1234567890 / 30;
+		let fx_code = _hotp(&BASE32_NOPAD.decode(FX_SECRET_B32.as_bytes())?, fx_now_step as u64 - 1, 6)?;
+
+		// -- Exec
+		let step = _validate_totp_at(FX_SECRET_B32, &fx_code, None, fx_now_step)?;
+
+		// -- Check
+		assert_eq!(step, fx_now_step - 1);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_validate_totp_err_replay() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_now_step = 1234567890 / 30;
+		let fx_code = _hotp(&BASE32_NOPAD.decode(FX_SECRET_B32.as_bytes())?, fx_now_step as u64, 6)?;
+
+		// -- Exec
+		let res = _validate_totp_at(FX_SECRET_B32, &fx_code, Some(fx_now_step), fx_now_step);
+
+		// -- Check
+		assert!(
+			matches!(res, Err(CryptError::TotpNotMatching)),
+			"Should have matched `Err(CryptError::TotpNotMatching)` but was `{res:?}`"
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_otpauth_uri_ok() {
+		let uri = totp_otpauth_uri("ABC", "e@mail");
+
+		assert_eq!(
+			uri,
+			"otpauth://totp/axum-cms:e%40mail?secret=ABC&issuer=axum-cms&algorithm=SHA1&digits=6&period=30"
+		);
+	}
+
+	#[test]
+	fn test_generate_recovery_codes_ok() {
+		let codes = generate_recovery_codes();
+
+		assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+		assert!(codes.iter().all(|c| c.len() == 11 && c.chars().nth(5) == Some('-')));
+	}
+}
+// endregion: --- Tests
@@ -5,14 +5,22 @@ pub type CoreResult<T> = core::result::Result<T, CoreError>;
 #[derive(Debug)]
 pub enum CoreError {
 	// -- Config
-	ConfigMissingEnv(&'static str),
-	ConfigWrongFormat(&'static str),
+	Config(crate::config::ConfigError),
+
+	// -- Metrics
+	MetricsInit(String),
 
 	// -- Modules
 	Model(models::ModelError)
 }
 
 // region:    --- Froms
+impl From<crate::config::ConfigError> for CoreError {
+	fn from(val: crate::config::ConfigError) -> Self {
+		Self::Config(val)
+	}
+}
+
 impl From<models::ModelError> for CoreError {
 	fn from(val: models::ModelError) -> Self {
 		Self::Model(val)
@@ -0,0 +1,52 @@
+use serde::Serialize;
+
+pub type LogResult<T> = core::result::Result<T, LogError>;
+
+#[derive(Debug, Serialize)]
+pub enum LogError {
+	// -- File sink
+	FileIo(String),
+
+	// -- Http sink
+	HttpFail(String),
+	HttpStatus(u16),
+
+	// -- Tracing
+	OtelInit(String),
+
+	// -- Externals
+	SerdeJson(String),
+}
+
+// region:    --- Froms
+impl From<std::io::Error> for LogError {
+	fn from(val: std::io::Error) -> Self {
+		Self::FileIo(val.to_string())
+	}
+}
+
+impl From<reqwest::Error> for LogError {
+	fn from(val: reqwest::Error) -> Self {
+		Self::HttpFail(val.to_string())
+	}
+}
+
+impl From<serde_json::Error> for LogError {
+	fn from(val: serde_json::Error) -> Self {
+		Self::SerdeJson(val.to_string())
+	}
+}
+// endregion: --- Froms
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for LogError {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for LogError {}
+// endregion: --- Error Boilerplate
@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use time::Date;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+use super::{LogResult, LogSink, RequestLogLine};
+use crate::utils::now_utc;
+
+/// JSON lines files, `{dir}/requests-{date}.log`, then `requests-{date}.{n}.log` once over `max_bytes`.
+///
+/// Files are rotated daily (UTC), and a restart appends to the last file of the day.
+pub struct FileSink {
+	dir: PathBuf,
+	max_bytes: u64,
+	current: Option<CurrentFile>,
+}
+
+struct CurrentFile {
+	date: Date,
+	index: u32,
+	size: u64,
+	file: File,
+}
+
+impl FileSink {
+	pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+		Self {
+			dir: dir.into(),
+			max_bytes,
+			current: None,
+		}
+	}
+
+	fn file_path(&self, date: Date, index: u32) -> PathBuf {
+		let file_name = match index {
+			0 => format!("requests-{date}.log"),
+			index => format!("requests-{date}.{index}.log"),
+		};
+
+		self.dir.join(file_name)
+	}
+
+	/// Open the first file of `date`, from `index`, which can take `len` more bytes (or is empty).
+	async fn open(&self, date: Date, mut index: u32, len: u64) -> LogResult<CurrentFile> {
+		fs::create_dir_all(&self.dir).await?;
+
+		loop {
+			let path = self.file_path(date, index);
+			let size = file_size(&path).await;
+
+			if size == 0 || size + len <= self.max_bytes {
+				let file = OpenOptions::new().create(true).append(true).open(&path).await?;
+				return Ok(CurrentFile { date, index, size, file });
+			}
+
+			index += 1;
+		}
+	}
+}
+
+async fn file_size(path: &Path) -> u64 {
+	fs::metadata(path).await.map(|meta| meta.len()).unwrap_or(0)
+}
+
+#[async_trait]
+impl LogSink for FileSink {
+	fn name(&self) -> &'static str {
+		"file"
+	}
+
+	async fn write(&mut self, log_line: &RequestLogLine) -> LogResult<()> {
+		let mut line = serde_json::to_vec(log_line)?;
+		line.push(b'\n');
+		let len = line.len() as u64;
+
+		// -- Rotate if new day or over size.
+		let today = now_utc().date();
+		let next_index = match &self.current {
+			Some(current) if current.date != today => Some(0),
+			Some(current) if current.size > 0 && current.size + len > self.max_bytes => Some(current.index + 1),
+			Some(_) => None,
+			None => Some(0),
+		};
+		if let Some(index) = next_index {
+			self.current = Some(self.open(today, index, len).await?);
+		}
+
+		// -- Write.
+		let current = self.current.as_mut().expect("current file should be open");
+		if let Err(err) = current.file.write_all(&line).await {
+			// Reopen on next write.
+			self.current = None;
+			return Err(err.into());
+		}
+		current.size += len;
+
+		Ok(())
+	}
+
+	async fn flush(&mut self) -> LogResult<()> {
+		if let Some(current) = self.current.as_mut() {
+			current.file.flush().await?;
+		}
+
+		Ok(())
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::log::tests::fx_log_line;
+	use anyhow::Result;
+
+	#[tokio::test]
+	async fn test_file_sink_rotate_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_dir = std::env::temp_dir().join(format!("axum-cms-log-{}", uuid::Uuid::new_v4()));
+		let fx_line = fx_log_line("fx-uuid-01");
+		let line_len = serde_json::to_vec(&fx_line)?.len() as u64 + 1;
+		// Room for 2 lines per file.
+		let mut sink = FileSink::new(&fx_dir, line_len * 2);
+
+		// -- Exec
+		for _ in 0..5 {
+			sink.write(&fx_line).await?;
+		}
+		sink.flush().await?;
+
+		// -- Check
+		let today = now_utc().date();
+		let sizes = [
+			file_size(&sink.file_path(today, 0)).await,
+			file_size(&sink.file_path(today, 1)).await,
+			file_size(&sink.file_path(today, 2)).await,
+		];
+		assert_eq!(sizes, [line_len * 2, line_len * 2, line_len]);
+
+		let content = fs::read_to_string(sink.file_path(today, 0)).await?;
+		let first: serde_json::Value = serde_json::from_str(content.lines().next().unwrap())?;
+		assert_eq!(first["request_id"], "fx-uuid-01");
+
+		// -- Check - Restart appends to the last file.
+		let mut sink = FileSink::new(&fx_dir, line_len * 2);
+		sink.write(&fx_line).await?;
+		sink.flush().await?;
+		assert_eq!(file_size(&sink.file_path(today, 2)).await, line_len * 2);
+
+		// -- Clean
+		fs::remove_dir_all(&fx_dir).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -1,26 +1,72 @@
+//! Request logging
+//!
+//! Design:
+//!
+//! - One `RequestLogLine` per request, built by `log_request` (called from `main_response_mapper`).
+//! - Lines are handed to the `RequestLogger`, which sends them (bounded channel) to a background task
+//!   writing them to the configured `LogSink`s (see `config().logging.sinks`).
+//! - Logging must never fail (nor slow down) the request: sink errors are only traced,
+//!   and a line is dropped if the channel is full.
+//! - The `request_id` is the one of the request span (see `mw_req_stamp`), which can also be exported
+//!   over OTLP (see `init_tracing`).
+//!
+
+// region:    --- Modules
+mod error;
+mod file_sink;
+mod otel;
+mod otlp_sink;
+mod sink;
+
+pub use self::error::{LogError, LogResult};
+pub use self::file_sink::FileSink;
+pub use self::otel::{init_tracing, shutdown_tracing};
+pub use self::otlp_sink::OtlpHttpSink;
+pub use self::sink::{LogSink, StdoutSink};
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::config::LogSinkConfig;
 use crate::ctx::Ctx;
+use crate::shutdown::Shutdown;
+use crate::utils::{format_time, now_utc};
 use crate::web::{self, ClientError};
-use crate::Result;
-use axum::http::{Method, Uri};
+use axum::http::{Method, StatusCode, Uri};
 use serde::Serialize;
 use serde_json::{json, Value};
 use serde_with::skip_serializing_none;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::debug;
-use uuid::Uuid;
-
-pub async fn log_request(
-	uuid: Uuid,
-	req_method: Method,
-	uri: Uri,
-	ctx: Option<Ctx>,
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+// endregion: --- Modules
+
+/// Max lines waiting to be written, beyond which lines are dropped.
+const CHANNEL_CAPACITY: usize = 10_000;
+
+// region:    --- Log Request
+/// Request side information of the log line.
+pub struct RequestInfo<'a> {
+	pub request_id: &'a str,
+	pub time_in: OffsetDateTime,
+	pub req_method: &'a Method,
+	pub uri: &'a Uri,
+	pub ctx: Option<&'a Ctx>,
+	pub client_ip: Option<IpAddr>,
+	pub user_agent: Option<&'a str>,
+}
+
+pub fn log_request(
+	logger: &RequestLogger,
+	req: RequestInfo,
+	http_status: StatusCode,
+	response_size: Option<u64>,
 	web_error: Option<&web::ServerError>,
-	client_error: Option<ClientError>,
-) -> Result<()> {
-	let timestamp = SystemTime::now()
-		.duration_since(UNIX_EPOCH)
-		.unwrap()
-		.as_millis();
+	client_error: Option<&ClientError>,
+) {
+	let now = now_utc();
+	let latency_ms = (now - req.time_in).as_seconds_f64() * 1000.;
 
 	let error_type = web_error.map(|se| se.as_ref().to_string());
 	let error_data = serde_json::to_value(web_error)
@@ -29,15 +75,20 @@ pub async fn log_request(
 
 	// Create the RequestLogLine
 	let log_line = RequestLogLine {
-		uuid: uuid.to_string(),
-		timestamp: timestamp.to_string(),
+		request_id: req.request_id.to_string(),
+		timestamp: format_time(now),
+		latency_ms,
 
-		http_path: uri.to_string(),
-		http_method: req_method.to_string(),
+		user_id: req.ctx.map(|c| c.user_id()),
+		client_ip: req.client_ip.map(|ip| ip.to_string()),
+		user_agent: req.user_agent.map(str::to_string),
 
-		user_id: ctx.map(|c| c.user_id()),
+		http_path: req.uri.to_string(),
+		http_method: req.req_method.to_string(),
+		http_status: http_status.as_u16(),
+		response_size,
 
-		client_error_type: client_error.map(|e| e.as_ref().to_string()),
+		client_error_type: client_error.map(|e| e.code().to_string()),
 
 		error_type,
 		error_data,
@@ -45,26 +96,239 @@ pub async fn log_request(
 
 	debug!("REQUEST LOG LINE:\n{}", json!(log_line));
 
-	// TODO - Send to cloud-watch.
-
-	Ok(())
+	logger.log(log_line);
 }
 
 #[skip_serializing_none]
-#[derive(Serialize)]
-struct RequestLogLine {
-	uuid: String,      // uuid string formatted
-	timestamp: String, // (should be iso8601)
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestLogLine {
+	pub request_id: String, // X-Request-Id, received or generated (uuid)
+	pub timestamp: String,  // iso8601 (Rfc3339), at response time
+	pub latency_ms: f64,
 
 	// -- User and context attributes.
-	user_id: Option<i64>,
+	pub user_id: Option<i64>,
+	pub client_ip: Option<String>,
+	pub user_agent: Option<String>,
 
 	// -- http request attributes.
-	http_path: String,
-	http_method: String,
+	pub http_path: String,
+	pub http_method: String,
+
+	// -- http response attributes.
+	pub http_status: u16,
+	pub response_size: Option<u64>,
 
 	// -- Errors attributes.
-	client_error_type: Option<String>,
-	error_type: Option<String>,
-	error_data: Option<Value>,
-}
\ No newline at end of file
+	pub client_error_type: Option<String>,
+	pub error_type: Option<String>,
+	pub error_data: Option<Value>,
+}
+// endregion: --- Log Request
+
+// region:    --- RequestLogger
+#[derive(Clone)]
+/// Handle to the background task writing the log lines to the sinks.
+pub struct RequestLogger {
+	/// None when there is no sink.
+	tx: Option<mpsc::Sender<RequestLogLine>>,
+}
+
+impl RequestLogger {
+	/// Start the background task writing to `sinks` (must be called within a tokio runtime).
+	///
+	/// NOTE: The task writes the queued lines, flushes the sinks and ends at shutdown
+	///       (or once all the `RequestLogger` clones are dropped).
+	pub fn new(sinks: Vec<Box<dyn LogSink>>, shutdown: &Shutdown) -> Self {
+		if sinks.is_empty() {
+			return Self { tx: None };
+		}
+
+		let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+		shutdown.spawn(|stop| run_sinks(rx, sinks, stop));
+
+		Self { tx: Some(tx) }
+	}
+
+	pub fn from_config(sink_configs: &[LogSinkConfig], shutdown: &Shutdown) -> Self {
+		let sinks = sink_configs
+			.iter()
+			.map(|sink_config| -> Box<dyn LogSink> {
+				match sink_config {
+					LogSinkConfig::Stdout => Box::new(StdoutSink),
+					LogSinkConfig::File { dir, max_bytes } => Box::new(FileSink::new(dir, *max_bytes)),
+					LogSinkConfig::OtlpHttp { url, batch_size, flush_interval_sec } => Box::new(
+						OtlpHttpSink::new(url, *batch_size, Duration::from_secs(*flush_interval_sec))
+					),
+				}
+			})
+			.collect();
+
+		Self::new(sinks, shutdown)
+	}
+
+	/// Queue the line for the sinks (never blocks, drops the line if the queue is full).
+	pub fn log(&self, log_line: RequestLogLine) {
+		let Some(tx) = &self.tx else {
+			return;
+		};
+
+		if let Err(err) = tx.try_send(log_line) {
+			warn!("{:<12} - request log line dropped - {err}", "LOG");
+		}
+	}
+}
+
+async fn run_sinks(mut rx: mpsc::Receiver<RequestLogLine>, mut sinks: Vec<Box<dyn LogSink>>, stop: CancellationToken) {
+	// -- Flush as often as the most demanding sink needs.
+	let flush_interval = sinks
+		.iter()
+		.filter_map(|sink| sink.flush_interval())
+		.min()
+		.unwrap_or(Duration::from_secs(60));
+	let mut flush_ticker = tokio::time::interval(flush_interval);
+
+	loop {
+		tokio::select! {
+			log_line = rx.recv() => {
+				let Some(log_line) = log_line else {
+					break;
+				};
+
+				write_all(&mut sinks, &log_line).await;
+			},
+			_ = flush_tick(&mut flush_ticker) => flush_all(&mut sinks).await,
+			_ = stop.cancelled() => {
+				// -- Write the lines already queued.
+				rx.close();
+				while let Some(log_line) = rx.recv().await {
+					write_all(&mut sinks, &log_line).await;
+				}
+				break;
+			},
+		}
+	}
+
+	flush_all(&mut sinks).await;
+}
+
+async fn write_all(sinks: &mut [Box<dyn LogSink>], log_line: &RequestLogLine) {
+	for sink in sinks.iter_mut() {
+		if let Err(err) = sink.write(log_line).await {
+			warn!("{:<12} - sink {} write failed - {err}", "LOG", sink.name());
+		}
+	}
+}
+
+async fn flush_tick(ticker: &mut tokio::time::Interval) {
+	ticker.tick().await;
+}
+
+async fn flush_all(sinks: &mut [Box<dyn LogSink>]) {
+	for sink in sinks.iter_mut() {
+		if let Err(err) = sink.flush().await {
+			warn!("{:<12} - sink {} flush failed - {err}", "LOG", sink.name());
+		}
+	}
+}
+// endregion: --- RequestLogger
+
+// region:    --- Tests
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use anyhow::Result;
+	use async_trait::async_trait;
+	use std::sync::{Arc, Mutex};
+
+	pub(crate) fn fx_log_line(request_id: &str) -> RequestLogLine {
+		RequestLogLine {
+			request_id: request_id.to_string(),
+			timestamp: "2026-10-19T10:00:00Z".to_string(),
+			latency_ms: 1.5,
+			user_id: Some(1000),
+			client_ip: Some("127.0.0.1".to_string()),
+			user_agent: Some("fx-agent".to_string()),
+			http_path: "/api/post".to_string(),
+			http_method: "GET".to_string(),
+			http_status: 200,
+			response_size: Some(42),
+			client_error_type: None,
+			error_type: None,
+			error_data: None,
+		}
+	}
+
+	struct FailingSink;
+
+	#[async_trait]
+	impl LogSink for FailingSink {
+		fn name(&self) -> &'static str {
+			"failing"
+		}
+
+		async fn write(&mut self, _log_line: &RequestLogLine) -> LogResult<()> {
+			Err(LogError::HttpStatus(500))
+		}
+	}
+
+	struct CollectSink(Arc<Mutex<Vec<String>>>);
+
+	#[async_trait]
+	impl LogSink for CollectSink {
+		fn name(&self) -> &'static str {
+			"collect"
+		}
+
+		async fn write(&mut self, log_line: &RequestLogLine) -> LogResult<()> {
+			self.0.lock().unwrap().push(log_line.request_id.clone());
+			Ok(())
+		}
+	}
+
+	#[tokio::test]
+	async fn test_logger_sink_failure_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let collected = Arc::new(Mutex::new(Vec::new()));
+		let logger = RequestLogger::new(
+			vec![Box::new(FailingSink), Box::new(CollectSink(collected.clone()))],
+			&Shutdown::default(),
+		);
+
+		// -- Exec
+		logger.log(fx_log_line("fx-uuid-01"));
+		logger.log(fx_log_line("fx-uuid-02"));
+
+		// -- Check
+		for _ in 0..50 {
+			if collected.lock().unwrap().len() == 2 {
+				break;
+			}
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+		assert_eq!(*collected.lock().unwrap(), vec!["fx-uuid-01", "fx-uuid-02"]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_logger_shutdown_writes_queued_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let shutdown = Shutdown::default();
+		let collected = Arc::new(Mutex::new(Vec::new()));
+		let logger = RequestLogger::new(vec![Box::new(CollectSink(collected.clone()))], &shutdown);
+
+		// -- Exec
+		logger.log(fx_log_line("fx-uuid-01"));
+		logger.log(fx_log_line("fx-uuid-02"));
+		let stopped = shutdown.stop_tasks(Duration::from_secs(1)).await;
+
+		// -- Check
+		// (No wait, the lines queued before the shutdown are written before the task ends)
+		assert!(stopped);
+		assert_eq!(*collected.lock().unwrap(), vec!["fx-uuid-01", "fx-uuid-02"]);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,46 @@
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace, Resource};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+use super::{LogError, LogResult};
+
+/// Init the tracing subscriber (console), also exporting the spans over OTLP/HTTP
+/// when `otlp_endpoint` is set (collector base URL, e.g., `http://localhost:4318`).
+pub fn init_tracing(otlp_endpoint: Option<&str>) -> LogResult<()> {
+	let fmt_layer = fmt::layer()
+		.without_time() // For early local development.
+		.with_target(false)
+		.with_writer(std::io::stderr); // stdout is kept for the command outputs (e.g., `token issue`).
+
+	let otel_layer = match otlp_endpoint {
+		Some(endpoint) => {
+			let tracer = opentelemetry_otlp::new_pipeline()
+				.tracing()
+				.with_exporter(opentelemetry_otlp::new_exporter().http().with_endpoint(endpoint))
+				.with_trace_config(trace::config().with_resource(Resource::new(vec![
+					KeyValue::new("service.name", env!("CARGO_PKG_NAME")),
+					KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+				])))
+				.install_batch(runtime::Tokio)
+				.map_err(|ex| LogError::OtelInit(ex.to_string()))?;
+
+			Some(tracing_opentelemetry::layer().with_tracer(tracer))
+		}
+		None => None,
+	};
+
+	tracing_subscriber::registry()
+		.with(EnvFilter::from_default_env())
+		.with(fmt_layer)
+		.with(otel_layer)
+		.try_init()
+		.map_err(|ex| LogError::OtelInit(ex.to_string()))
+}
+
+/// Export the remaining spans (no-op without OTLP export).
+pub fn shutdown_tracing() {
+	opentelemetry::global::shutdown_tracer_provider();
+}
@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+
+use super::{LogError, LogResult, LogSink, RequestLogLine};
+use crate::utils::parse_utc;
+
+/// OTLP severity numbers (see the OpenTelemetry logs data model)
+const SEVERITY_INFO: u8 = 9;
+const SEVERITY_ERROR: u8 = 17;
+
+/// Batched export to an OTLP/HTTP logs endpoint (JSON encoding, e.g., `http://localhost:4318/v1/logs`).
+///
+/// Lines are posted when `batch_size` is reached and on each flush (every `flush_interval`).
+/// NOTE: A failed batch is dropped (not retried), so that a down collector cannot grow the buffer.
+pub struct OtlpHttpSink {
+	http: reqwest::Client,
+	url: String,
+	batch_size: usize,
+	flush_interval: Duration,
+	buffer: Vec<RequestLogLine>,
+}
+
+impl OtlpHttpSink {
+	pub fn new(url: impl Into<String>, batch_size: usize, flush_interval: Duration) -> Self {
+		let http = reqwest::Client::builder()
+			.timeout(Duration::from_secs(10))
+			.build()
+			.unwrap_or_default();
+
+		Self {
+			http,
+			url: url.into(),
+			batch_size: batch_size.max(1),
+			flush_interval,
+			buffer: Vec::new(),
+		}
+	}
+}
+
+#[async_trait]
+impl LogSink for OtlpHttpSink {
+	fn name(&self) -> &'static str {
+		"otlp_http"
+	}
+
+	async fn write(&mut self, log_line: &RequestLogLine) -> LogResult<()> {
+		self.buffer.push(log_line.clone());
+
+		if self.buffer.len() >= self.batch_size {
+			self.flush().await?;
+		}
+
+		Ok(())
+	}
+
+	async fn flush(&mut self) -> LogResult<()> {
+		if self.buffer.is_empty() {
+			return Ok(());
+		}
+
+		let log_lines = std::mem::take(&mut self.buffer);
+		let body = export_logs_request(&log_lines)?;
+
+		let res = self.http.post(&self.url).json(&body).send().await?;
+		if !res.status().is_success() {
+			return Err(LogError::HttpStatus(res.status().as_u16()));
+		}
+
+		Ok(())
+	}
+
+	fn flush_interval(&self) -> Option<Duration> {
+		Some(self.flush_interval)
+	}
+}
+
+// region:    --- OTLP JSON
+/// The OTLP `ExportLogsServiceRequest` (JSON encoding) for the lines.
+fn export_logs_request(log_lines: &[RequestLogLine]) -> LogResult<Value> {
+	let log_records = log_lines.iter().map(log_record).collect::<LogResult<Vec<_>>>()?;
+
+	Ok(json!({
+		"resourceLogs": [{
+			"resource": {
+				"attributes": [
+					{"key": "service.name", "value": {"stringValue": env!("CARGO_PKG_NAME")}},
+					{"key": "service.version", "value": {"stringValue": env!("CARGO_PKG_VERSION")}},
+				]
+			},
+			"scopeLogs": [{
+				"scope": {"name": "request_log"},
+				"logRecords": log_records,
+			}]
+		}]
+	}))
+}
+
+fn log_record(log_line: &RequestLogLine) -> LogResult<Value> {
+	let time_unix_nano = parse_utc(&log_line.timestamp)
+		.map(|time| time.unix_timestamp_nanos().to_string())
+		.unwrap_or_else(|_| "0".to_string());
+
+	let (severity_number, severity_text) = match log_line.error_type {
+		Some(_) => (SEVERITY_ERROR, "ERROR"),
+		None => (SEVERITY_INFO, "INFO"),
+	};
+
+	// -- Every field of the line, as attributes.
+	let Value::Object(fields) = serde_json::to_value(log_line)? else {
+		return Ok(Value::Null);
+	};
+	let attributes: Vec<Value> = fields
+		.into_iter()
+		.map(|(key, value)| json!({"key": key, "value": any_value(value)}))
+		.collect();
+
+	Ok(json!({
+		"timeUnixNano": time_unix_nano,
+		"severityNumber": severity_number,
+		"severityText": severity_text,
+		"body": {"stringValue": format!("{} {} {}", log_line.http_method, log_line.http_path, log_line.http_status)},
+		"attributes": attributes,
+	}))
+}
+
+/// OTLP `AnyValue` (nested objects and arrays are kept as JSON strings).
+fn any_value(value: Value) -> Value {
+	match value {
+		Value::Bool(value) => json!({"boolValue": value}),
+		Value::Number(value) if value.is_f64() => json!({"doubleValue": value}),
+		// (int64 are strings in the OTLP JSON encoding)
+		Value::Number(value) => json!({"intValue": value.to_string()}),
+		Value::String(value) => json!({"stringValue": value}),
+		value => json!({"stringValue": value.to_string()}),
+	}
+}
+// endregion: --- OTLP JSON
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::log::tests::fx_log_line;
+	use anyhow::Result;
+	use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+	use std::net::SocketAddr;
+	use std::sync::{Arc, Mutex};
+
+	type Received = Arc<Mutex<Vec<Value>>>;
+
+	/// Local stand-in for a collector, keeping the received bodies.
+	async fn spawn_collector(status: StatusCode) -> (String, Received) {
+		let received = Received::default();
+
+		let app = Router::new()
+			.route(
+				"/v1/logs",
+				post(move |State(received): State<Received>, Json(body): Json<Value>| async move {
+					received.lock().unwrap().push(body);
+					status
+				}),
+			)
+			.with_state(received.clone());
+
+		let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(app.into_make_service());
+		let url = format!("http://{}/v1/logs", server.local_addr());
+		tokio::spawn(server);
+
+		(url, received)
+	}
+
+	#[tokio::test]
+	async fn test_otlp_sink_batch_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let (url, received) = spawn_collector(StatusCode::OK).await;
+		let mut sink = OtlpHttpSink::new(url, 2, Duration::from_secs(5));
+
+		// -- Exec
+		sink.write(&fx_log_line("fx-uuid-01")).await?;
+		let received_before_batch = received.lock().unwrap().len();
+		sink.write(&fx_log_line("fx-uuid-02")).await?;
+		sink.write(&fx_log_line("fx-uuid-03")).await?;
+		sink.flush().await?;
+
+		// -- Check
+		assert_eq!(received_before_batch, 0);
+		let received = received.lock().unwrap();
+		assert_eq!(received.len(), 2);
+
+		let records = &received[0]["resourceLogs"][0]["scopeLogs"][0]["logRecords"];
+		assert_eq!(records.as_array().map(Vec::len), Some(2));
+		assert_eq!(records[0]["severityText"], "INFO");
+		assert_eq!(records[0]["body"]["stringValue"], "GET /api/post 200");
+		assert_eq!(records[0]["timeUnixNano"], "1792404000000000000");
+		let attributes = records[0]["attributes"].as_array().unwrap();
+		assert!(attributes.contains(&json!({"key": "request_id", "value": {"stringValue": "fx-uuid-01"}})));
+		assert!(attributes.contains(&json!({"key": "http_status", "value": {"intValue": "200"}})));
+		assert!(attributes.contains(&json!({"key": "latency_ms", "value": {"doubleValue": 1.5}})));
+
+		let records = &received[1]["resourceLogs"][0]["scopeLogs"][0]["logRecords"];
+		assert_eq!(records.as_array().map(Vec::len), Some(1));
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_otlp_sink_err_status() -> Result<()> {
+		// -- Setup & Fixtures
+		let (url, _received) = spawn_collector(StatusCode::SERVICE_UNAVAILABLE).await;
+		let mut sink = OtlpHttpSink::new(url, 10, Duration::from_secs(5));
+
+		// -- Exec
+		sink.write(&fx_log_line("fx-uuid-01")).await?;
+		let res = sink.flush().await;
+
+		// -- Check
+		assert!(matches!(res, Err(LogError::HttpStatus(503))), "Should have been a `HttpStatus(503)`, was {res:?}");
+		// The failed batch is dropped.
+		assert!(sink.buffer.is_empty());
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::{LogResult, RequestLogLine};
+
+/// Destination of the request log lines.
+///
+/// Sinks are owned by the logger background task (hence `&mut self`), one line at a time.
+#[async_trait]
+pub trait LogSink: Send {
+	/// For the traces of the sink failures
+	fn name(&self) -> &'static str;
+
+	async fn write(&mut self, log_line: &RequestLogLine) -> LogResult<()>;
+
+	/// Write out the buffered lines, if any.
+	async fn flush(&mut self) -> LogResult<()> {
+		Ok(())
+	}
+
+	/// How often `flush` should be called, `None` if the sink does not buffer.
+	fn flush_interval(&self) -> Option<Duration> {
+		None
+	}
+}
+
+/// One JSON line per request on stdout.
+pub struct StdoutSink;
+
+#[async_trait]
+impl LogSink for StdoutSink {
+	fn name(&self) -> &'static str {
+		"stdout"
+	}
+
+	async fn write(&mut self, log_line: &RequestLogLine) -> LogResult<()> {
+		println!("{}", serde_json::to_string(log_line)?);
+
+		Ok(())
+	}
+}
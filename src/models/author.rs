@@ -3,8 +3,9 @@ use crate::ctx::Ctx;
 use crate::models::AppState;
 use crate::models::{ModelResult, ModelError};
 use crate::models::base::{self, DbBmc};
+use crate::models::post::PostBmc;
 use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString};
-use sea_query::{Expr, Iden, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query::{Condition, Expr, Iden, PostgresQueryBuilder, Query, SimpleExpr};
 use sea_query_binder::SqlxBinder;
 use serde::{Deserialize, Serialize};
 use modql::field::{Fields, HasFields};
@@ -13,6 +14,9 @@ use sqlx::postgres::PgRow;
 use sqlx::FromRow;
 use time::{OffsetDateTime, format_description::well_known::Rfc3339};
 use uuid::Uuid;
+use validator::Validate;
+
+use crate::utils::validation::{validate_not_blank, validate_pwd_strength};
 
 // region:    --- Author Types
 
@@ -24,18 +28,30 @@ pub struct Author {
 	pub name: String,
 	pub email: String,
 	#[serde_as(as = "Rfc3339")]
-	pub created_at: OffsetDateTime
+	pub created_at: OffsetDateTime,
+	#[serde_as(as = "Rfc3339")]
+	pub updated_at: OffsetDateTime
 	// pub password: String
 }
 
-#[derive(Deserialize, Debug, Fields)]
+#[derive(Deserialize, Debug, Fields, Validate)]
 /// Struct holding fields required from client to create an author in the database
 pub struct AuthorForCreate {
+	#[validate(length(max = 128), custom = "validate_not_blank")]
 	pub name: String,
+	#[validate(email, length(max = 128))]
 	pub email: String,
+	#[validate(custom = "validate_pwd_strength")]
 	pub password: String
 }
 
+#[derive(Debug, Fields)]
+/// Struct holding fields required to create an author without a password (e.g., signed up with OpenID Connect)
+pub struct AuthorForCreateNoPwd {
+	pub name: String,
+	pub email: String
+}
+
 #[derive(Deserialize, Debug, Fields)]
 /// Struct holding fields required from client to create an author in the database
 struct AuthorForInsert {
@@ -77,6 +93,23 @@ pub struct AuthorForLogin { //? For login logic
 	pub password: Option<String>, // encrypted, #_scheme_id_#...
 	pub password_salt: Uuid,
 	pub token_salt: Uuid,
+
+	// -- two-factor info
+	pub totp_enabled: bool,
+}
+
+#[derive(Clone, FromRow, Fields, Debug)]
+pub struct AuthorForTotp { //? For two-factor logic
+	pub id: i64,
+	pub email: String,
+
+	pub password_salt: Uuid,
+	pub token_salt: Uuid,
+
+	// -- two-factor info
+	pub totp_secret: Option<String>, // base32, set on enrollment
+	pub totp_enabled: bool, // true once enrollment is confirmed
+	pub totp_last_step: Option<i64>, // last used time step, prevents code replay
 }
 
 #[derive(Clone, FromRow, Fields, Debug)]
@@ -93,6 +126,7 @@ pub trait AuthorBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
 
 impl AuthorBy for Author {}
 impl AuthorBy for AuthorForLogin {}
+impl AuthorBy for AuthorForTotp {}
 impl AuthorBy for AuthorForAuth {}
 // impl AuthorBy for AuthorForResult {}
 
@@ -100,7 +134,13 @@ impl AuthorBy for AuthorForAuth {}
 enum AuthorIden {
 	Id,
 	Email,
-	Password
+	Password,
+	TotpSecret,
+	TotpEnabled,
+	TotpLastStep,
+	Name,
+	IsAdmin,
+	TokenSalt
 }
 
 #[derive(FilterNodes, Deserialize, Default)]
@@ -140,6 +180,13 @@ impl AuthorBmc {
 		base::create_no_auth::<AuthorBmc, _>(app_state, data).await
 	}
 
+	pub async fn create_no_pwd(
+		app_state: &AppState,
+		data: AuthorForCreateNoPwd,
+	) -> ModelResult<i64> {
+		base::create_no_auth::<AuthorBmc, _>(app_state, data).await
+	}
+
 	pub async fn get<E>(
 		ctx: &Ctx,
 		app_state: &AppState,
@@ -222,10 +269,224 @@ impl AuthorBmc {
 		Ok(())
 	}
 
+	pub async fn set_admin(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		id: i64,
+		is_admin: bool
+	) -> ModelResult<()> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.value(AuthorIden::IsAdmin, is_admin)
+			.and_where(Expr::col(AuthorIden::Id).eq(id));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = sqlx::query_with(&sql, values)
+			.execute(db)
+			.await?
+			.rows_affected();
+
+		if count == 0 {
+			return Err(ModelError::EntityNotFound { entity: Self::TABLE, id });
+		}
+
+		Ok(())
+	}
+
+	/// New `token_salt`, invalidating the issued auth tokens (i.e., logging out all the sessions).
+	pub async fn rotate_token_salt(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		id: i64
+	) -> ModelResult<()> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.value(AuthorIden::TokenSalt, Expr::cust("gen_random_uuid()"))
+			.and_where(Expr::col(AuthorIden::Id).eq(id));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = sqlx::query_with(&sql, values)
+			.execute(db)
+			.await?
+			.rows_affected();
+
+		if count == 0 {
+			return Err(ModelError::EntityNotFound { entity: Self::TABLE, id });
+		}
+
+		Ok(())
+	}
+
+	/// Whether an author is an admin (`false` for an unknown author).
+	pub async fn is_admin(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		id: i64
+	) -> ModelResult<bool> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.column(AuthorIden::IsAdmin)
+			.and_where(Expr::col(AuthorIden::Id).eq(id));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let is_admin = sqlx::query_as_with::<_, (bool,), _>(&sql, values)
+			.fetch_optional(db)
+			.await?
+			.is_some_and(|(is_admin,)| is_admin);
+
+		Ok(is_admin)
+	}
+
+	/// Store a new (not yet confirmed) TOTP secret, two-factor stays disabled until `enable_totp`.
+	pub async fn set_totp_pending(
+		ctx: &Ctx,
+		app_state: &AppState,
+		id: i64,
+		totp_secret: &str
+	) -> ModelResult<()> {
+		Self::update_totp(ctx, app_state, id, [
+			(AuthorIden::TotpSecret, SimpleExpr::from(totp_secret)),
+			(AuthorIden::TotpEnabled, SimpleExpr::from(false)),
+			(AuthorIden::TotpLastStep, SimpleExpr::from(Option::<i64>::None)),
+		]).await
+	}
+
+	/// Enable two-factor, `step` being the time step of the confirmation code.
+	pub async fn enable_totp(
+		ctx: &Ctx,
+		app_state: &AppState,
+		id: i64,
+		step: i64
+	) -> ModelResult<()> {
+		Self::update_totp(ctx, app_state, id, [
+			(AuthorIden::TotpEnabled, SimpleExpr::from(true)),
+			(AuthorIden::TotpLastStep, SimpleExpr::from(step)),
+		]).await
+	}
+
+	/// Disable two-factor and forget the TOTP secret.
+	pub async fn disable_totp(
+		ctx: &Ctx,
+		app_state: &AppState,
+		id: i64
+	) -> ModelResult<()> {
+		Self::update_totp(ctx, app_state, id, [
+			(AuthorIden::TotpSecret, SimpleExpr::from(Option::<String>::None)),
+			(AuthorIden::TotpEnabled, SimpleExpr::from(false)),
+			(AuthorIden::TotpLastStep, SimpleExpr::from(Option::<i64>::None)),
+		]).await
+	}
+
+	/// Mark the TOTP time `step` as used.
+	///
+	/// Returns `false` if this step (or a later one) was already used,
+	/// i.e., the code is being replayed (possibly concurrently).
+	pub async fn consume_totp_step(
+		// ctx: &Ctx,
+		app_state: &AppState,
+		id: i64,
+		step: i64
+	) -> ModelResult<bool> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.value(AuthorIden::TotpLastStep, SimpleExpr::from(step))
+			.and_where(Expr::col(AuthorIden::Id).eq(id))
+			.cond_where(
+				Condition::any()
+					.add(Expr::col(AuthorIden::TotpLastStep).is_null())
+					.add(Expr::col(AuthorIden::TotpLastStep).lt(step))
+			);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = sqlx::query_with(&sql, values)
+			.execute(db)
+			.await?
+			.rows_affected();
+
+		Ok(count == 1)
+	}
+
+	async fn update_totp<const N: usize>(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		id: i64,
+		values: [(AuthorIden, SimpleExpr); N]
+	) -> ModelResult<()> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(values)
+			.and_where(Expr::col(AuthorIden::Id).eq(id));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = sqlx::query_with(&sql, values)
+			.execute(db)
+			.await?
+			.rows_affected();
+
+		// -- Check result
+		if count == 0 {
+			Err(ModelError::EntityNotFound { entity: Self::TABLE, id })
+		} else {
+			Ok(())
+		}
+	}
+
 	pub async fn list(app_state: &AppState, filters: Option<AuthorFilter>, list_options: Option<ListOptions>) -> ModelResult<Vec<Author>> {
 		base::list_no_auth::<Self, _, _>(app_state, filters, list_options).await
 	}
 
+	pub async fn count(app_state: &AppState) -> ModelResult<i64> {
+		base::count_no_auth::<Self>(app_state).await
+	}
+
+	/// The `(id, name)` of the authors of `ids`, in a single query (e.g., for the feeds).
+	pub async fn list_names(app_state: &AppState, ids: &[i64]) -> ModelResult<Vec<(i64, String)>> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns([AuthorIden::Id, AuthorIden::Name])
+			.and_where(Expr::col(AuthorIden::Id).is_in(ids.iter().copied()));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let names = base::traced_sql(&sql, sqlx::query_as_with::<_, (i64, String), _>(&sql, values).fetch_all(db)).await?;
+
+		Ok(names)
+	}
+
+	/// The `(id, updated_at)` of the authors by id (e.g., for the sitemap).
+	pub async fn list_versions(app_state: &AppState, offset: i64, limit: i64) -> ModelResult<Vec<(i64, OffsetDateTime)>> {
+		base::list_versions_no_auth::<Self>(app_state, offset, limit).await
+	}
+
 	pub async fn update(ctx: &Ctx, app_state: &AppState, id: i64, author_e: AuthorForEdit) -> ModelResult<()> {
 		base::update::<Self, _>(ctx, app_state, id, author_e).await
 	}
@@ -236,7 +497,11 @@ impl AuthorBmc {
 		app_state: &AppState,
 		id: i64,
 	) -> ModelResult<()> {
-		base::delete::<Self>(ctx, app_state, id).await
+		base::delete::<Self>(ctx, app_state, id).await?;
+		// (The posts of the author are changed by the db, `ON DELETE SET NULL`)
+		PostBmc::invalidate_cache_all(app_state).await;
+
+		Ok(())
 	}
 
 }
@@ -406,5 +671,31 @@ use serial_test::serial;
 			Ok(())
 	
 	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_err_email_taken() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_email = "e@mail";
+
+		// -- Exec
+		let res = AuthorBmc::create_no_pwd(&app_state, AuthorForCreateNoPwd {
+			name: "test_create_err_email_taken".to_string(),
+			email: fx_email.to_string()
+		}).await;
+
+		// -- Check
+		assert!(
+			matches!(
+				&res,
+				Err(ModelError::UniqueViolation { constraint: Some(constraint), .. }) if constraint == "authors_email_key"
+			),
+			"UniqueViolation not matching, was {res:?}"
+		);
+
+		Ok(())
+	}
 }
 // endregion: --- Tests
\ No newline at end of file
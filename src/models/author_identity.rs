@@ -0,0 +1,186 @@
+use modql::field::{Fields, HasFields};
+use sea_query::{Expr, Iden, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use serde_with::serde_as;
+use sqlx::FromRow;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::ctx::Ctx;
+
+use super::{base::{self, DbBmc}, AppState, ModelResult};
+
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+/// Complete "AuthorIdentity" model, as-is in the database (an author's account at an OpenID Connect provider)
+pub struct AuthorIdentity {
+	pub id: i64,
+	pub author_id: i64,
+	pub provider: String,
+	/// The `sub` claim, stable identifier of the account at the provider
+	pub subject: String,
+	pub email: Option<String>,
+	#[serde_as(as = "Rfc3339")]
+	pub created_at: OffsetDateTime
+}
+
+#[derive(Debug, Fields)]
+/// Struct holding fields required to link an identity to an author in the database
+pub struct AuthorIdentityForCreate {
+	pub author_id: i64,
+	pub provider: String,
+	pub subject: String,
+	pub email: Option<String>
+}
+
+#[derive(Iden)]
+enum AuthorIdentityIden {
+	AuthorId,
+	Provider,
+	Subject
+}
+
+pub struct AuthorIdentityBmc;
+
+impl DbBmc for AuthorIdentityBmc {
+	const TABLE: &'static str = "author_identities";
+}
+
+impl AuthorIdentityBmc {
+	pub async fn create(
+		// ctx: &Ctx,
+		app_state: &AppState,
+		data: AuthorIdentityForCreate
+	) -> ModelResult<i64> {
+		base::create_no_auth::<Self, _>(app_state, data).await
+	}
+
+	/// Get the identity for a provider account, if linked to an author.
+	pub async fn first_by_provider_subject(
+		// ctx: &Ctx,
+		app_state: &AppState,
+		provider: &str,
+		subject: &str
+	) -> ModelResult<Option<AuthorIdentity>> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(AuthorIdentity::field_idens())
+			.and_where(Expr::col(AuthorIdentityIden::Provider).eq(provider))
+			.and_where(Expr::col(AuthorIdentityIden::Subject).eq(subject));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let identity = sqlx::query_as_with::<_, AuthorIdentity, _>(&sql, values)
+			.fetch_optional(db)
+			.await?;
+
+		Ok(identity)
+	}
+
+	/// List the identities linked to an author.
+	pub async fn list_for_author(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		author_id: i64
+	) -> ModelResult<Vec<AuthorIdentity>> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(AuthorIdentity::field_idens())
+			.and_where(Expr::col(AuthorIdentityIden::AuthorId).eq(author_id));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let identities = sqlx::query_as_with::<_, AuthorIdentity, _>(&sql, values)
+			.fetch_all(db)
+			.await?;
+
+		Ok(identities)
+	}
+
+	/// Unlink the provider identity of an author.
+	///
+	/// Returns `false` if the author had no identity at this provider.
+	pub async fn delete_for_author_provider(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		author_id: i64,
+		provider: &str
+	) -> ModelResult<bool> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::delete();
+		query
+			.from_table(Self::table_ref())
+			.and_where(Expr::col(AuthorIdentityIden::AuthorId).eq(author_id))
+			.and_where(Expr::col(AuthorIdentityIden::Provider).eq(provider));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = sqlx::query_with(&sql, values)
+			.execute(db)
+			.await?
+			.rows_affected();
+
+		Ok(count > 0)
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	#![allow(unused)]
+	use crate::{_dev_utils, models::author::{AuthorBmc, AuthorForCreateNoPwd, AuthorForLogin}};
+
+	use super::*;
+	use anyhow::{Ok, Result};
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_link_unlink_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_provider = "test_link_unlink_ok-provider";
+		let fx_subject = "test_link_unlink_ok-subject";
+		let author_id = AuthorBmc::create_no_pwd(&app_state, AuthorForCreateNoPwd {
+			name: "test_link_unlink_ok".to_string(),
+			email: "test_link_unlink_ok@mail".to_string()
+		}).await?;
+
+		// -- Exec
+		AuthorIdentityBmc::create(&app_state, AuthorIdentityForCreate {
+			author_id,
+			provider: fx_provider.to_string(),
+			subject: fx_subject.to_string(),
+			email: None
+		}).await?;
+		let found = AuthorIdentityBmc::first_by_provider_subject(&app_state, fx_provider, fx_subject).await?;
+		let identities = AuthorIdentityBmc::list_for_author(&ctx, &app_state, author_id).await?;
+		let unlinked = AuthorIdentityBmc::delete_for_author_provider(&ctx, &app_state, author_id, fx_provider).await?;
+		let not_found = AuthorIdentityBmc::first_by_provider_subject(&app_state, fx_provider, fx_subject).await?;
+
+		// -- Check
+		let author: AuthorForLogin = AuthorBmc::get(&ctx, &app_state, author_id).await?;
+		assert!(author.password.is_none(), "author should have no password");
+		assert_eq!(found.map(|identity| identity.author_id), Some(author_id));
+		assert_eq!(identities.len(), 1);
+		assert!(unlinked);
+		assert!(not_found.is_none());
+
+		// -- Clean
+		AuthorBmc::delete(&ctx, &app_state, author_id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
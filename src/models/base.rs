@@ -1,4 +1,6 @@
 use std::borrow::Borrow;
+use std::future::Future;
+use std::time::Instant;
 use modql::field::HasFields;
 use modql::filter::{FilterGroups, ListOptions};
 use modql::SIden;
@@ -12,6 +14,8 @@ use sqlx::database::HasArguments;
 use sqlx::encode::IsNull;
 use sqlx::postgres::PgRow;
 use sqlx::{Database, Encode, FromRow, Postgres, Type};
+use time::OffsetDateTime;
+use tracing::{field, info_span, Instrument};
 
 /// Trait for Backend Model Controllers that are DB-related
 pub trait DbBmc {
@@ -26,9 +30,22 @@ pub trait DbBmc {
 
 
 
+/// Runs the query in a `sql` span (child of the `request` span),
+/// with the statement (`db.statement`) and its duration (`duration_ms`) as fields.
+pub(in crate::models) async fn traced_sql<F: Future>(sql: &str, query: F) -> F::Output {
+	let span = info_span!("sql", db.statement = sql, duration_ms = field::Empty);
+
+	let start = Instant::now();
+	let res = query.instrument(span.clone()).await;
+	span.record("duration_ms", start.elapsed().as_secs_f64() * 1000.);
+
+	res
+}
+
 #[derive(Iden)]
 pub enum CommonIden {
-	Id
+	Id,
+	UpdatedAt
 }
 
 
@@ -57,9 +74,7 @@ where
 	// -- Execute query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
 
-	let (id,) = sqlx::query_as_with::<_, (i64,), _>(&sql, values)
-		.fetch_one(db)
-		.await?;
+	let (id,) = traced_sql(&sql, sqlx::query_as_with::<_, (i64,), _>(&sql, values).fetch_one(db)).await?;
 
 	Ok(id)
 }
@@ -90,9 +105,7 @@ where
 	// -- Execute query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
 
-	let (id,) = sqlx::query_as_with::<_, (i64,), _>(&sql, values)
-		.fetch_one(db)
-		.await?;
+	let (id,) = traced_sql(&sql, sqlx::query_as_with::<_, (i64,), _>(&sql, values).fetch_one(db)).await?;
 
 	Ok(id)
 }
@@ -128,8 +141,7 @@ where
 
 	// -- Execute query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-	let entity = sqlx::query_as_with::<_, E, _>(&sql, values)
-		.fetch_optional(db)
+	let entity = traced_sql(&sql, sqlx::query_as_with::<_, E, _>(&sql, values).fetch_optional(db))
 		.await?
 		.ok_or(ModelError::EntityNotFound {
 			entity: MC::TABLE,
@@ -184,13 +196,52 @@ where
 	
 	// -- Execute Query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-	let entities = sqlx::query_as_with::<_, E, _>(&sql, values)
-		.fetch_all(db)
-		.await?;
+	let entities = traced_sql(&sql, sqlx::query_as_with::<_, E, _>(&sql, values).fetch_all(db)).await?;
 
 	Ok(entities)
 }
 
+/// Count of the rows (e.g., to page the sitemap).
+pub async fn count_no_auth<MC>(app_state: &AppState) -> ModelResult<i64>
+where
+	MC: DbBmc,
+{
+	let db = app_state.db();
+
+	// -- Build Query
+	let mut query = Query::select();
+	query.from(MC::table_ref()).expr(Expr::col(CommonIden::Id).count());
+
+	// -- Execute Query
+	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+	let (count,) = traced_sql(&sql, sqlx::query_as_with::<_, (i64,), _>(&sql, values).fetch_one(db)).await?;
+
+	Ok(count)
+}
+
+/// The `(id, updated_at)` of the rows by id, from `offset` (e.g., for the sitemap, without loading the rows).
+pub async fn list_versions_no_auth<MC>(app_state: &AppState, offset: i64, limit: i64) -> ModelResult<Vec<(i64, OffsetDateTime)>>
+where
+	MC: DbBmc,
+{
+	let db = app_state.db();
+
+	// -- Build Query
+	let mut query = Query::select();
+	query
+		.from(MC::table_ref())
+		.columns([CommonIden::Id, CommonIden::UpdatedAt])
+		.order_by(CommonIden::Id, sea_query::Order::Asc)
+		.offset(offset as u64)
+		.limit(limit as u64);
+
+	// -- Execute Query
+	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+	let versions = traced_sql(&sql, sqlx::query_as_with::<_, (i64, OffsetDateTime), _>(&sql, values).fetch_all(db)).await?;
+
+	Ok(versions)
+}
+
 
 pub async fn update<MC, E>(
 	_ctx: &Ctx, 
@@ -202,7 +253,35 @@ where
 	MC: DbBmc,
 	E: HasFields,
 {
-	
+	update_where::<MC, E>(app_state, id, data, None).await
+}
+
+/// Update only if the entity is still at the `updated_at` version (optimistic concurrency control),
+/// `EntityModified` otherwise.
+pub async fn update_if_unmodified<MC, E>(
+	_ctx: &Ctx,
+	app_state: &AppState,
+	id: i64,
+	data: E,
+	updated_at: OffsetDateTime
+) -> ModelResult<()>
+where
+	MC: DbBmc,
+	E: HasFields,
+{
+	update_where::<MC, E>(app_state, id, data, Some(updated_at)).await
+}
+
+async fn update_where<MC, E>(
+	app_state: &AppState,
+	id: i64,
+	data: E,
+	updated_at: Option<OffsetDateTime>
+) -> ModelResult<()>
+where
+	MC: DbBmc,
+	E: HasFields,
+{
 	let db = app_state.db();
 
 	// -- Prep data
@@ -215,19 +294,21 @@ where
 		.table(MC::table_ref())
 		.values(fields)
 		.and_where(Expr::col(CommonIden::Id).eq(id));
+	if let Some(updated_at) = updated_at {
+		query.and_where(Expr::col(CommonIden::UpdatedAt).eq(updated_at));
+	}
 
 	// -- Execute query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-	let count = sqlx::query_with(&sql, values)
-		.execute(db)
+	let count = traced_sql(&sql, sqlx::query_with(&sql, values).execute(db))
 		.await?
 		.rows_affected();
 
 	// -- Check result
-	if count == 0 {
-		Err(ModelError::EntityNotFound { entity: MC::TABLE, id })
-	} else {
-		Ok(())
+	match (count, updated_at) {
+		(0, None) => Err(ModelError::EntityNotFound { entity: MC::TABLE, id }),
+		(0, Some(_)) => Err(ModelError::EntityModified { entity: MC::TABLE, id }),
+		_ => Ok(()),
 	}
 }
 
@@ -246,8 +327,7 @@ where
 
 	// Execute query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-	let count = sqlx::query_with(&sql, values)
-		.execute(db)
+	let count = traced_sql(&sql, sqlx::query_with(&sql, values).execute(db))
 		.await?
 		.rows_affected();
 
@@ -0,0 +1,38 @@
+use serde::Serialize;
+
+pub type CacheResult<T> = core::result::Result<T, CacheError>;
+
+#[derive(Debug, Serialize)]
+pub enum CacheError {
+	RedisConnectFail(String),
+	Redis(String),
+	/// A cached value could not be (de)serialized (e.g., after a model change)
+	SerdeJson(String),
+}
+
+// region:    --- Froms
+impl From<redis::RedisError> for CacheError {
+	fn from(val: redis::RedisError) -> Self {
+		Self::Redis(val.to_string())
+	}
+}
+
+impl From<serde_json::Error> for CacheError {
+	fn from(val: serde_json::Error) -> Self {
+		Self::SerdeJson(val.to_string())
+	}
+}
+// endregion: --- Froms
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for CacheError {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for CacheError {}
+// endregion: --- Error Boilerplate
@@ -0,0 +1,130 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use lru::LruCache;
+
+use super::{Cache, CacheResult};
+
+/// In-process cache, the least recently used entries evicted beyond `capacity`.
+///
+/// NOTE: Not shared between instances, i.e., an instance can serve an entry changed through
+///       another one until it expires (use `RedisCache` when running several instances).
+pub struct MemoryCache {
+	entries: Mutex<LruCache<String, Entry>>,
+}
+
+struct Entry {
+	value: Vec<u8>,
+	expires_at: Instant,
+}
+
+impl MemoryCache {
+	pub fn new(capacity: NonZeroUsize) -> Self {
+		Self {
+			entries: Mutex::new(LruCache::new(capacity)),
+		}
+	}
+}
+
+#[async_trait]
+impl Cache for MemoryCache {
+	fn name(&self) -> &'static str {
+		"memory"
+	}
+
+	async fn get(&self, key: &str) -> CacheResult<Option<Vec<u8>>> {
+		let mut entries = self.entries.lock().unwrap();
+
+		match entries.get(key) {
+			Some(entry) if entry.expires_at > Instant::now() => Ok(Some(entry.value.clone())),
+			Some(_) => {
+				entries.pop(key);
+				Ok(None)
+			}
+			None => Ok(None),
+		}
+	}
+
+	async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> CacheResult<()> {
+		let entry = Entry {
+			value,
+			expires_at: Instant::now() + ttl,
+		};
+		self.entries.lock().unwrap().put(key.to_string(), entry);
+
+		Ok(())
+	}
+
+	async fn delete(&self, key: &str) -> CacheResult<()> {
+		self.entries.lock().unwrap().pop(key);
+
+		Ok(())
+	}
+
+	async fn delete_prefix(&self, prefix: &str) -> CacheResult<()> {
+		let mut entries = self.entries.lock().unwrap();
+
+		let keys: Vec<String> = entries
+			.iter()
+			.filter(|(key, _)| key.starts_with(prefix))
+			.map(|(key, _)| key.clone())
+			.collect();
+		for key in keys {
+			entries.pop(&key);
+		}
+
+		Ok(())
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	const FX_TTL: Duration = Duration::from_secs(60);
+
+	#[tokio::test]
+	async fn test_memory_cache_lru_evict_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let cache = MemoryCache::new(NonZeroUsize::new(2).unwrap());
+
+		// -- Exec
+		cache.set("fx-key-01", b"01".to_vec(), FX_TTL).await?;
+		cache.set("fx-key-02", b"02".to_vec(), FX_TTL).await?;
+		cache.get("fx-key-01").await?; // (01 now more recently used than 02)
+		cache.set("fx-key-03", b"03".to_vec(), FX_TTL).await?;
+
+		// -- Check
+		assert_eq!(cache.get("fx-key-01").await?, Some(b"01".to_vec()));
+		assert_eq!(cache.get("fx-key-02").await?, None);
+		assert_eq!(cache.get("fx-key-03").await?, Some(b"03".to_vec()));
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_memory_cache_expire_and_delete_prefix_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let cache = MemoryCache::new(NonZeroUsize::new(10).unwrap());
+		cache.set("fx:expired", b"x".to_vec(), Duration::ZERO).await?;
+		cache.set("fx:list:01", b"x".to_vec(), FX_TTL).await?;
+		cache.set("fx:list:02", b"x".to_vec(), FX_TTL).await?;
+		cache.set("fx:id:01", b"x".to_vec(), FX_TTL).await?;
+
+		// -- Exec
+		cache.delete_prefix("fx:list:").await?;
+
+		// -- Check
+		assert_eq!(cache.get("fx:expired").await?, None);
+		assert_eq!(cache.get("fx:list:01").await?, None);
+		assert_eq!(cache.get("fx:list:02").await?, None);
+		assert!(cache.get("fx:id:01").await?.is_some());
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
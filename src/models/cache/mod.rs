@@ -0,0 +1,219 @@
+//! Cache of the public model data (e.g., posts), read-through in the Bmcs
+//!
+//! Design:
+//!
+//! - `Cache` is a key-value store with TTL, in process (`MemoryCache`, LRU) or shared (`RedisCache`),
+//!   as configured in `cache.backend`.
+//! - Keys are namespaced by entity (e.g., `posts:id:{id}`, `posts:list:{hash}`), so that a change
+//!   can invalidate all the lists of the entity by prefix.
+//! - The cache never fails a request: a failed read falls through to the db, and a failed
+//!   invalidation is logged (the entry then expires with its TTL).
+//!
+
+// region:    --- Modules
+mod error;
+mod memory_cache;
+mod redis_cache;
+
+pub use self::error::{CacheError, CacheResult};
+pub use self::memory_cache::MemoryCache;
+pub use self::redis_cache::RedisCache;
+
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::config::{CacheBackend, CacheConfig};
+use crate::models::ModelResult;
+// endregion: --- Modules
+
+#[async_trait]
+pub trait Cache: Send + Sync {
+	fn name(&self) -> &'static str;
+
+	async fn get(&self, key: &str) -> CacheResult<Option<Vec<u8>>>;
+
+	async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> CacheResult<()>;
+
+	async fn delete(&self, key: &str) -> CacheResult<()>;
+
+	/// Delete all the keys starting with `prefix`.
+	async fn delete_prefix(&self, prefix: &str) -> CacheResult<()>;
+}
+
+/// The cache of the `AppState`, no-op when disabled.
+#[derive(Clone)]
+pub struct ModelCache {
+	backend: Option<Arc<dyn Cache>>,
+	ttl: Duration,
+}
+
+impl ModelCache {
+	pub fn new(backend: Arc<dyn Cache>, ttl: Duration) -> Self {
+		Self {
+			backend: Some(backend),
+			ttl,
+		}
+	}
+
+	pub fn disabled() -> Self {
+		Self {
+			backend: None,
+			ttl: Duration::ZERO,
+		}
+	}
+
+	pub async fn from_config(cache_config: &CacheConfig) -> CacheResult<Self> {
+		let ttl = Duration::from_secs(cache_config.ttl_sec);
+
+		let backend: Arc<dyn Cache> = match &cache_config.backend {
+			CacheBackend::None => return Ok(Self::disabled()),
+			CacheBackend::Memory => {
+				let capacity = NonZeroUsize::new(cache_config.capacity).unwrap_or(NonZeroUsize::MIN);
+				Arc::new(MemoryCache::new(capacity))
+			}
+			CacheBackend::Redis => {
+				let url = cache_config.redis_url.as_deref().map(String::as_str).unwrap_or_default();
+				Arc::new(RedisCache::connect(url).await?)
+			}
+		};
+
+		Ok(Self::new(backend, ttl))
+	}
+
+	/// Read-through: the cached value of `key`, otherwise the `load`ed value (then cached).
+	pub async fn get_or_load<T, Fut>(&self, key: &str, load: impl FnOnce() -> Fut) -> ModelResult<T>
+	where
+		T: Serialize + DeserializeOwned,
+		Fut: Future<Output = ModelResult<T>>,
+	{
+		let Some(backend) = &self.backend else {
+			return load().await;
+		};
+
+		// -- Cached
+		match backend.get(key).await.and_then(|value| {
+			value.map(|value| serde_json::from_slice::<T>(&value)).transpose().map_err(CacheError::from)
+		}) {
+			Ok(Some(value)) => {
+				debug!("{:<12} - hit {key}", "CACHE");
+				return Ok(value);
+			}
+			Ok(None) => debug!("{:<12} - miss {key}", "CACHE"),
+			Err(err) => warn!("{:<12} - {} get {key} failed - {err}", "CACHE", backend.name()),
+		}
+
+		// -- Load, then cache
+		let value = load().await?;
+		let res = match serde_json::to_vec(&value) {
+			Ok(bytes) => backend.set(key, bytes, self.ttl).await,
+			Err(ex) => Err(ex.into()),
+		};
+		if let Err(err) = res {
+			warn!("{:<12} - {} set {key} failed - {err}", "CACHE", backend.name());
+		}
+
+		Ok(value)
+	}
+
+	/// Delete the `keys`, and the keys starting with the `prefixes`.
+	pub async fn invalidate(&self, keys: &[&str], prefixes: &[&str]) {
+		let Some(backend) = &self.backend else {
+			return;
+		};
+
+		for key in keys {
+			if let Err(err) = backend.delete(key).await {
+				warn!("{:<12} - {} delete {key} failed - {err}", "CACHE", backend.name());
+			}
+		}
+		for prefix in prefixes {
+			if let Err(err) = backend.delete_prefix(prefix).await {
+				warn!("{:<12} - {} delete prefix {prefix} failed - {err}", "CACHE", backend.name());
+			}
+		}
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	/// A backend always failing, the cache must then be transparent.
+	struct FailingCache;
+
+	#[async_trait]
+	impl Cache for FailingCache {
+		fn name(&self) -> &'static str {
+			"failing"
+		}
+
+		async fn get(&self, _key: &str) -> CacheResult<Option<Vec<u8>>> {
+			Err(CacheError::Redis("fx-down".to_string()))
+		}
+
+		async fn set(&self, _key: &str, _value: Vec<u8>, _ttl: Duration) -> CacheResult<()> {
+			Err(CacheError::Redis("fx-down".to_string()))
+		}
+
+		async fn delete(&self, _key: &str) -> CacheResult<()> {
+			Err(CacheError::Redis("fx-down".to_string()))
+		}
+
+		async fn delete_prefix(&self, _prefix: &str) -> CacheResult<()> {
+			Err(CacheError::Redis("fx-down".to_string()))
+		}
+	}
+
+	async fn fx_load(loads: &AtomicUsize) -> ModelResult<Vec<String>> {
+		loads.fetch_add(1, Ordering::SeqCst);
+		Ok(vec!["fx-value".to_string()])
+	}
+
+	#[tokio::test]
+	async fn test_model_cache_read_through_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let cache = ModelCache::new(Arc::new(MemoryCache::new(NonZeroUsize::new(10).unwrap())), Duration::from_secs(60));
+		let loads = AtomicUsize::new(0);
+
+		// -- Exec
+		let value_1 = cache.get_or_load("fx:id:01", || fx_load(&loads)).await?;
+		let value_2 = cache.get_or_load("fx:id:01", || fx_load(&loads)).await?;
+		cache.invalidate(&["fx:id:01"], &[]).await;
+		let value_3 = cache.get_or_load("fx:id:01", || fx_load(&loads)).await?;
+
+		// -- Check
+		assert_eq!(value_1, value_2);
+		assert_eq!(value_1, value_3);
+		assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_model_cache_backend_failure_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let cache = ModelCache::new(Arc::new(FailingCache), Duration::from_secs(60));
+		let loads = AtomicUsize::new(0);
+
+		// -- Exec
+		let value = cache.get_or_load("fx:id:01", || fx_load(&loads)).await?;
+		cache.invalidate(&["fx:id:01"], &["fx:list:"]).await;
+
+		// -- Check
+		assert_eq!(value, ["fx-value"]);
+		assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+
+use super::{Cache, CacheError, CacheResult};
+
+/// Namespace of the keys, so that the Redis instance can be shared
+const KEY_NAMESPACE: &str = "axum-cms:";
+/// Keys per `SCAN` round, for `delete_prefix`
+const SCAN_COUNT: usize = 100;
+
+/// Cache shared between instances (the connection is re-established when lost).
+pub struct RedisCache {
+	conn: ConnectionManager,
+}
+
+impl RedisCache {
+	/// Connect to `url`, e.g., `redis://127.0.0.1:6379`.
+	pub async fn connect(url: &str) -> CacheResult<Self> {
+		let client = redis::Client::open(url).map_err(|ex| CacheError::RedisConnectFail(ex.to_string()))?;
+		let conn = ConnectionManager::new(client)
+			.await
+			.map_err(|ex| CacheError::RedisConnectFail(ex.to_string()))?;
+
+		Ok(Self { conn })
+	}
+}
+
+fn namespaced(key: &str) -> String {
+	format!("{KEY_NAMESPACE}{key}")
+}
+
+#[async_trait]
+impl Cache for RedisCache {
+	fn name(&self) -> &'static str {
+		"redis"
+	}
+
+	async fn get(&self, key: &str) -> CacheResult<Option<Vec<u8>>> {
+		let value = redis::cmd("GET")
+			.arg(namespaced(key))
+			.query_async(&mut self.conn.clone())
+			.await?;
+
+		Ok(value)
+	}
+
+	async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> CacheResult<()> {
+		redis::cmd("SET")
+			.arg(namespaced(key))
+			.arg(value)
+			.arg("PX")
+			.arg(ttl.as_millis().max(1) as u64)
+			.query_async::<_, ()>(&mut self.conn.clone())
+			.await?;
+
+		Ok(())
+	}
+
+	async fn delete(&self, key: &str) -> CacheResult<()> {
+		redis::cmd("DEL")
+			.arg(namespaced(key))
+			.query_async::<_, ()>(&mut self.conn.clone())
+			.await?;
+
+		Ok(())
+	}
+
+	/// NOTE: `SCAN`s the keyspace, fine for the few invalidations per write.
+	async fn delete_prefix(&self, prefix: &str) -> CacheResult<()> {
+		let mut conn = self.conn.clone();
+		let pattern = format!("{}*", namespaced(prefix));
+
+		let mut cursor: u64 = 0;
+		loop {
+			let (next_cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+				.arg(cursor)
+				.arg("MATCH")
+				.arg(&pattern)
+				.arg("COUNT")
+				.arg(SCAN_COUNT)
+				.query_async(&mut conn)
+				.await?;
+
+			if !keys.is_empty() {
+				redis::cmd("DEL").arg(keys).query_async::<_, ()>(&mut conn).await?;
+			}
+
+			if next_cursor == 0 {
+				break;
+			}
+			cursor = next_cursor;
+		}
+
+		Ok(())
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	/// Local instance for the tests, `REDIS_TEST_URL` (default `redis://127.0.0.1:6379`), failing when unreachable
+	async fn fx_redis() -> Result<RedisCache> {
+		let url = std::env::var("REDIS_TEST_URL").unwrap_or("redis://127.0.0.1:6379".to_string());
+
+		let cache = tokio::time::timeout(Duration::from_secs(2), RedisCache::connect(&url))
+			.await
+			.map_err(|_| anyhow::anyhow!("no Redis at {url} (timed out)"))??;
+
+		Ok(cache)
+	}
+
+	#[ignore = "needs a Redis, run with `REDIS_TEST_URL=redis://... cargo test -- --ignored redis`"]
+	#[tokio::test]
+	async fn test_redis_cache_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let cache = fx_redis().await?;
+		let fx_ttl = Duration::from_secs(60);
+		cache.set("fx-test:list:01", b"01".to_vec(), fx_ttl).await?;
+		cache.set("fx-test:list:02", b"02".to_vec(), fx_ttl).await?;
+		cache.set("fx-test:id:01", b"id-01".to_vec(), fx_ttl).await?;
+
+		// -- Exec
+		let value = cache.get("fx-test:list:01").await?;
+		cache.delete_prefix("fx-test:list:").await?;
+		cache.delete("fx-test:id:01").await?;
+
+		// -- Check
+		assert_eq!(value, Some(b"01".to_vec()));
+		assert_eq!(cache.get("fx-test:list:02").await?, None);
+		assert_eq!(cache.get("fx-test:id:01").await?, None);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
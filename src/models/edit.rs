@@ -5,8 +5,10 @@ use serde_with::serde_as;
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use serde::{Deserialize, Serialize};
 use sqlx::{prelude::Type, FromRow};
+use validator::Validate;
 
 use crate::ctx::Ctx;
+use crate::utils::validation::validate_not_blank;
 
 use super::{base::{self, DbBmc}, AppState, ModelError, ModelResult};
 
@@ -30,6 +32,7 @@ pub struct Edit {
 // #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "edit_status")]
 #[derive(Clone, Debug, Deserialize, strum_macros::Display, Serialize, sqlx::Type, PartialEq)]
 #[sqlx(type_name = "edit_status")]
+#[allow(clippy::upper_case_acronyms)]
 pub enum EditStatus {
 	PENDING,
 	ACCEPTED,
@@ -57,10 +60,11 @@ pub struct EditForCreate {
 	pub editor_id: i64
 }
 
-#[derive(Deserialize, Debug, Fields)]
+#[derive(Deserialize, Debug, Fields, Validate)]
 /// Struct holding fields required from client to create an edit suggestion in the database
 pub struct EditForCreateRequestBody {
 	pub post_id: i64,
+	#[validate(length(max = 512), custom = "validate_not_blank")] // Replaces the post content once accepted
 	pub new_content: String,
 }
 
@@ -72,9 +76,10 @@ pub struct EditForUpdate {
 	pub status: Option<EditStatus>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Validate)]
 /// Struct holding fields required from client to update an edit's content
 pub struct EditForUpdateClientRequest {
+	#[validate(length(max = 512), custom = "validate_not_blank")]
 	pub new_content: String
 }
 
@@ -96,12 +101,12 @@ pub struct EditFilter {
 	status: Option<OpValsValue>
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Validate)]
 pub struct EditForAccept {
 	pub accept: bool
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Validate)]
 pub struct EditForReject {
 	pub reject: bool
 }
@@ -112,7 +117,8 @@ enum EditIden {
 	PostId,
 	EditorId,
 	NewContent,
-	Status
+	Status,
+	UpdatedAt
 }
 
 
@@ -145,6 +151,21 @@ impl EditBmc {
 	}
 	
 	pub async fn update(ctx: &Ctx, app_state: &AppState, id: i64, edit_e: EditForUpdate) -> ModelResult<()> {
+		Self::update_where(app_state, id, edit_e, None).await
+	}
+
+	/// Update only if the edit is still at the `updated_at` version (see `base::update_if_unmodified`).
+	pub async fn update_if_unmodified(
+		ctx: &Ctx,
+		app_state: &AppState,
+		id: i64,
+		edit_e: EditForUpdate,
+		updated_at: OffsetDateTime
+	) -> ModelResult<()> {
+		Self::update_where(app_state, id, edit_e, Some(updated_at)).await
+	}
+
+	async fn update_where(app_state: &AppState, id: i64, edit_e: EditForUpdate, updated_at: Option<OffsetDateTime>) -> ModelResult<()> {
 		let db = app_state.db();
 
 		let mut query = Query::update();
@@ -160,6 +181,9 @@ impl EditBmc {
 		}
 		
 		query.and_where(Expr::col(EditIden::Id).eq(id));
+		if let Some(updated_at) = updated_at {
+			query.and_where(Expr::col(EditIden::UpdatedAt).eq(updated_at));
+		}
 
 		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
 		let count = sqlx::query_with(&sql, values)
@@ -168,10 +192,10 @@ impl EditBmc {
 			.rows_affected();
 
 		// -- Check result
-		if count == 0 {
-			Err(ModelError::EntityNotFound { entity: Self::TABLE, id })
-		} else {
-			Ok(())
+		match (count, updated_at) {
+			(0, None) => Err(ModelError::EntityNotFound { entity: Self::TABLE, id }),
+			(0, Some(_)) => Err(ModelError::EntityModified { entity: Self::TABLE, id }),
+			_ => Ok(()),
 		}
 	}
 
@@ -186,7 +210,6 @@ impl EditBmc {
 
 // region:    --- Tests
 #[cfg(test)]
-
 mod tests {
 	#![allow(unused)]
 	use crate::{_dev_utils, models::{author::AuthorBmc, post::PostBmc, ModelError}};
@@ -204,13 +227,8 @@ mod tests {
 		let fx_editors = &[("name", "email@mail", "password")];
 		let fx_posts = &[("test_list_ok-post 01", "content 01", 1000)];
 
-		_dev_utils::seed_posts(&ctx, &app_state, fx_posts).await?;
-		_dev_utils::seed_authors(&ctx, &app_state, fx_editors).await?;
-
-		let posts = PostBmc::list(&app_state, None, None).await?;
-		let editors = AuthorBmc::list(&app_state, None, None).await?;
-		let post = &posts[0];
-		let editor = &editors[0];
+		let post = _dev_utils::seed_posts(&ctx, &app_state, fx_posts).await?.remove(0);
+		let editor = _dev_utils::seed_authors(&ctx, &app_state, fx_editors).await?.remove(0);
 
 		let fx_new_content = "Here is a suggestion";
 		let fx_post_id = post.id;
@@ -235,10 +253,44 @@ mod tests {
 
 		// -- Clean
 		EditBmc::delete(&ctx, &app_state, id).await?;
+		PostBmc::delete(&ctx, &app_state, post.id).await?;
+		AuthorBmc::delete(&ctx, &app_state, editor.id).await?;
 		
 		Ok(())
 	}
 
+	#[serial]
+	#[tokio::test]
+	async fn test_create_err_post_not_found() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_editors = &[("test_create_err_post_not_found", "test_create_err_post_not_found@mail", "password")];
+		let editor = _dev_utils::seed_authors(&ctx, &app_state, fx_editors).await?.remove(0);
+
+		// -- Exec
+		let edit_c = EditForCreate {
+			new_content: "Here is a suggestion".to_string(),
+			post_id: 100,
+			editor_id: editor.id
+		};
+		let res = EditBmc::create(&ctx, &app_state, edit_c).await;
+
+		// -- Check
+		assert!(
+			matches!(
+				&res,
+				Err(ModelError::ForeignKeyViolation { constraint: Some(constraint), .. }) if constraint == "edits_post_id_fkey"
+			),
+			"ForeignKeyViolation not matching, was {res:?}"
+		);
+
+		// -- Clean
+		AuthorBmc::delete(&ctx, &app_state, editor.id).await?;
+
+		Ok(())
+	}
+
 	#[serial]
 	#[tokio::test]
 	async fn test_get_err_not_found() -> Result<()> {
@@ -1,4 +1,4 @@
-use crate::{crypt, models::store};
+use crate::{crypt, models::{cache, media, storage, store, webhook}, web::rate_limit};
 use serde::Serialize;
 use serde_with::{serde_as, DisplayFromStr};
 use derive_more::{From, Display};
@@ -9,19 +9,41 @@ pub type ModelResult<T> = core::result::Result<T, ModelError>;
 #[derive(Debug, Serialize, From)]
 pub enum ModelError {
 	EntityNotFound { entity: &'static str, id: i64 },
+	/// Conditional update of a version that is no longer current (updated or deleted since)
+	EntityModified { entity: &'static str, id: i64 },
 
 	EntityAccessRequiresAuth,
 
 	CouldNotParseTextIntoEnum,
 
+	// -- Db (classified Postgres errors, see `From<sqlx::Error>`)
+	/// Unique (or primary key) constraint violation, i.e., the entity already exists
+	UniqueViolation { table: Option<String>, constraint: Option<String> },
+	/// Foreign key constraint violation, i.e., a reference to a missing entity (or a still referenced entity)
+	ForeignKeyViolation { table: Option<String>, constraint: Option<String> },
+	CheckViolation { table: Option<String>, constraint: Option<String> },
+	/// A value is too long for its column
+	StringTooLong,
+	/// Serialization failure or deadlock, the operation can be retried
+	TxConflict,
+
 	// -- Modules
 	#[from]
 	Crypt(crypt::CryptError),
 	#[from]
 	Store(store::StoreError),
+	#[from]
+	Cache(cache::CacheError),
+	#[from]
+	RateLimit(rate_limit::RateLimitError),
+	#[from]
+	Storage(storage::StorageError),
+	#[from]
+	Media(media::MediaError),
+	#[from]
+	Webhook(webhook::WebhookError),
 
 	// -- Externals
-	#[from]
 	Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
 	#[from]
 	SeaQuery(#[serde_as(as = "DisplayFromStr")] sea_query::error::Error),
@@ -29,6 +51,38 @@ pub enum ModelError {
 	ModqlIntoSea(#[serde_as(as = "DisplayFromStr")] modql::filter::IntoSeaError)
 }
 
+// region:    --- Froms
+/// Postgres error codes (SQLSTATE), see https://www.postgresql.org/docs/current/errcodes-appendix.html
+mod pg_code {
+	pub const UNIQUE_VIOLATION: &str = "23505";
+	pub const FOREIGN_KEY_VIOLATION: &str = "23503";
+	pub const CHECK_VIOLATION: &str = "23514";
+	pub const STRING_DATA_RIGHT_TRUNCATION: &str = "22001";
+	pub const SERIALIZATION_FAILURE: &str = "40001";
+	pub const DEADLOCK_DETECTED: &str = "40P01";
+}
+
+impl From<sqlx::Error> for ModelError {
+	fn from(val: sqlx::Error) -> Self {
+		let Some(db_error) = val.as_database_error() else {
+			return Self::Sqlx(val);
+		};
+
+		let table = db_error.table().map(str::to_string);
+		let constraint = db_error.constraint().map(str::to_string);
+
+		match db_error.code().as_deref() {
+			Some(pg_code::UNIQUE_VIOLATION) => Self::UniqueViolation { table, constraint },
+			Some(pg_code::FOREIGN_KEY_VIOLATION) => Self::ForeignKeyViolation { table, constraint },
+			Some(pg_code::CHECK_VIOLATION) => Self::CheckViolation { table, constraint },
+			Some(pg_code::STRING_DATA_RIGHT_TRUNCATION) => Self::StringTooLong,
+			Some(pg_code::SERIALIZATION_FAILURE | pg_code::DEADLOCK_DETECTED) => Self::TxConflict,
+			_ => Self::Sqlx(val),
+		}
+	}
+}
+// endregion: --- Froms
+
 // region:    --- Error Boilerplate
 impl core::fmt::Display for ModelError {
 	fn fmt(
@@ -0,0 +1,40 @@
+use serde::Serialize;
+
+pub type MediaResult<T> = core::result::Result<T, MediaError>;
+
+#[derive(Debug, Serialize)]
+pub enum MediaError {
+	/// The content is not a valid image of its type (e.g., truncated)
+	ImageInvalid(String),
+	/// More pixels than `media.max_image_pixels`
+	ImageTooLarge { width: u32, height: u32 },
+	ImageEncode(String),
+	/// Not a processed image type (e.g., a PDF)
+	ImageTypeNotProcessed(String),
+	/// The processing task panicked or was cancelled
+	ProcessingAborted(String),
+}
+
+// region:    --- Froms
+impl From<image::ImageError> for MediaError {
+	fn from(val: image::ImageError) -> Self {
+		match val {
+			image::ImageError::Encoding(_) => Self::ImageEncode(val.to_string()),
+			_ => Self::ImageInvalid(val.to_string()),
+		}
+	}
+}
+// endregion: --- Froms
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for MediaError {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for MediaError {}
+// endregion: --- Error Boilerplate
@@ -0,0 +1,404 @@
+//! Image operations, CPU bound (see `MediaProcessor::run` to run them off the async runtime)
+//!
+//! - Metadata (EXIF, GPS, XMP, comments, ...) is removed from the container, without re-encoding,
+//!   except when the image has an EXIF orientation: it is then applied and the image re-encoded
+//!   (otherwise the image would show rotated once its EXIF is gone).
+//! - The dimensions are checked against `media.max_image_pixels` before decoding, then the image is
+//!   fully decoded (corrupted or truncated images are refused) before its container is touched.
+//!
+
+use std::io::Cursor;
+
+use bytes::Bytes;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+
+use crate::config::ImageFormatKind;
+
+use super::{MediaError, MediaResult};
+
+const JPEG_QUALITY: u8 = 85;
+
+/// The format of the processed image types (e.g., not PDF).
+pub fn image_format(mime_type: &str) -> Option<ImageFormat> {
+	match mime_type {
+		"image/png" => Some(ImageFormat::Png),
+		"image/jpeg" => Some(ImageFormat::Jpeg),
+		"image/gif" => Some(ImageFormat::Gif),
+		"image/webp" => Some(ImageFormat::WebP),
+		_ => None,
+	}
+}
+
+/// `(width, height)`, from the header only.
+pub fn dimensions(content: &[u8], format: ImageFormat) -> MediaResult<(u32, u32)> {
+	Ok(ImageReader::with_format(Cursor::new(content), format).into_dimensions()?)
+}
+
+/// Decode, checking the pixel count first, with the EXIF orientation applied.
+pub fn decode(content: &[u8], format: ImageFormat, max_pixels: u64) -> MediaResult<DynamicImage> {
+	let (mut image, orientation) = decode_checked(content, format, max_pixels)?;
+	image.apply_orientation(orientation);
+
+	Ok(image)
+}
+
+/// Check the image can be processed: not too large (from its header), then fully decodable.
+pub fn validate(content: &[u8], format: ImageFormat, max_pixels: u64) -> MediaResult<()> {
+	decode_checked(content, format, max_pixels)?;
+
+	Ok(())
+}
+
+/// The content without its metadata (see module doc), once validated (see `validate`).
+pub fn strip_metadata(content: &Bytes, format: ImageFormat, max_pixels: u64) -> MediaResult<Bytes> {
+	let (mut image, orientation) = decode_checked(content, format, max_pixels)?;
+	if orientation != Orientation::NoTransforms {
+		image.apply_orientation(orientation);
+		let kind = match format {
+			ImageFormat::Png => ImageFormatKind::Png,
+			ImageFormat::WebP => ImageFormatKind::Webp,
+			_ => ImageFormatKind::Jpeg,
+		};
+		return encode(&image, kind);
+	}
+
+	let stripped = match format {
+		ImageFormat::Jpeg => strip_jpeg(content)?,
+		ImageFormat::Png => strip_png(content)?,
+		ImageFormat::WebP => strip_webp(content)?,
+		// (GIF has no EXIF, its comments are kept)
+		_ => content.to_vec(),
+	};
+
+	Ok(stripped.into())
+}
+
+/// The whole image and its EXIF orientation (not applied), the pixel count being checked from the header first.
+fn decode_checked(content: &[u8], format: ImageFormat, max_pixels: u64) -> MediaResult<(DynamicImage, Orientation)> {
+	let (width, height) = dimensions(content, format)?;
+	if width as u64 * height as u64 > max_pixels {
+		return Err(MediaError::ImageTooLarge { width, height });
+	}
+
+	// (the JPEG decoder fills in a truncated scan)
+	if format == ImageFormat::Jpeg {
+		check_jpeg_complete(content)?;
+	}
+
+	let mut decoder = ImageReader::with_format(Cursor::new(content), format).into_decoder()?;
+	let orientation = decoder.orientation()?;
+	let image = DynamicImage::from_decoder(decoder)?;
+
+	Ok((image, orientation))
+}
+
+/// Fits `image` within `width` x `height` (never enlarged), or fills it exactly when `crop`,
+/// returning the encoded content and its dimensions.
+pub fn render(
+	image: &DynamicImage,
+	width: Option<u32>,
+	height: Option<u32>,
+	crop: bool,
+	format: ImageFormatKind,
+) -> MediaResult<(Bytes, u32, u32)> {
+	let max_width = width.unwrap_or(image.width());
+	let max_height = height.unwrap_or(image.height());
+
+	let rendered = if crop {
+		image.resize_to_fill(max_width, max_height, FilterType::Lanczos3)
+	} else if image.width() > max_width || image.height() > max_height {
+		image.resize(max_width, max_height, FilterType::Lanczos3)
+	} else {
+		image.clone()
+	};
+
+	Ok((encode(&rendered, format)?, rendered.width(), rendered.height()))
+}
+
+fn encode(image: &DynamicImage, format: ImageFormatKind) -> MediaResult<Bytes> {
+	let mut content = Vec::new();
+	match format {
+		ImageFormatKind::Png => image.write_with_encoder(PngEncoder::new(&mut content))?,
+		// (no alpha in JPEG)
+		ImageFormatKind::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+			.write_with_encoder(JpegEncoder::new_with_quality(&mut content, JPEG_QUALITY))?,
+		ImageFormatKind::Webp => image.write_with_encoder(WebPEncoder::new_lossless(&mut content))?,
+	}
+
+	Ok(content.into())
+}
+
+// region:    --- Containers
+fn invalid(format: &str) -> MediaError {
+	MediaError::ImageInvalid(format!("malformed {format} container"))
+}
+
+/// Drops the APP1 (EXIF, XMP), APP3 to APP12, APP13 (IPTC), APP15 and COM segments,
+/// keeps APP0 (JFIF), APP2 (ICC profile) and APP14 (Adobe color transform).
+fn strip_jpeg(content: &[u8]) -> MediaResult<Vec<u8>> {
+	if !content.starts_with(&[0xFF, 0xD8]) {
+		return Err(invalid("JPEG"));
+	}
+
+	let mut stripped = content[..2].to_vec();
+	let mut pos = 2;
+	loop {
+		let marker = match content.get(pos..pos + 2) {
+			Some([0xFF, marker]) => *marker,
+			_ => return Err(invalid("JPEG")),
+		};
+
+		// -- Start of scan (or end of image), the rest is the image data.
+		if marker == 0xDA || marker == 0xD9 {
+			stripped.extend_from_slice(&content[pos..]);
+			return Ok(stripped);
+		}
+
+		let len = content
+			.get(pos + 2..pos + 4)
+			.map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+			.filter(|len| *len >= 2 && pos + 2 + len <= content.len())
+			.ok_or_else(|| invalid("JPEG"))?;
+		let segment = &content[pos..pos + 2 + len];
+
+		let is_metadata = matches!(marker, 0xE1 | 0xE3..=0xED | 0xEF | 0xFE);
+		if !is_metadata {
+			stripped.extend_from_slice(segment);
+		}
+		pos += segment.len();
+	}
+}
+
+/// Checks the end of image marker follows the (first) start of scan, i.e., the JPEG is not truncated.
+fn check_jpeg_complete(content: &[u8]) -> MediaResult<()> {
+	// -- Skip the segments before the scan (an EXIF thumbnail has its own markers).
+	let mut pos = 2;
+	while let Some([0xFF, marker]) = content.get(pos..pos + 2) {
+		if *marker == 0xDA {
+			return match content[pos..].windows(2).any(|w| w == [0xFF, 0xD9]) {
+				true => Ok(()),
+				false => Err(MediaError::ImageInvalid("truncated JPEG (no end of image)".to_string())),
+			};
+		}
+
+		let len = content
+			.get(pos + 2..pos + 4)
+			.map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+			.filter(|len| *len >= 2)
+			.ok_or_else(|| invalid("JPEG"))?;
+		pos += 2 + len;
+	}
+
+	Err(invalid("JPEG"))
+}
+
+/// Drops the `eXIf`, `tEXt`, `zTXt`, `iTXt` and `tIME` chunks.
+fn strip_png(content: &[u8]) -> MediaResult<Vec<u8>> {
+	const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+	if !content.starts_with(SIGNATURE) {
+		return Err(invalid("PNG"));
+	}
+
+	let mut stripped = SIGNATURE.to_vec();
+	let mut pos = SIGNATURE.len();
+	while pos < content.len() {
+		// (length, type, data, crc)
+		let len = content
+			.get(pos..pos + 4)
+			.map(|len| u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize)
+			.filter(|len| pos + 12 + len <= content.len())
+			.ok_or_else(|| invalid("PNG"))?;
+		let chunk = &content[pos..pos + 12 + len];
+
+		if !matches!(&chunk[4..8], b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME") {
+			stripped.extend_from_slice(chunk);
+		}
+		pos += chunk.len();
+	}
+
+	Ok(stripped)
+}
+
+/// Drops the `EXIF` and `XMP ` chunks, and their `VP8X` flags.
+fn strip_webp(content: &[u8]) -> MediaResult<Vec<u8>> {
+	const VP8X_EXIF_FLAG: u8 = 0x08;
+	const VP8X_XMP_FLAG: u8 = 0x04;
+	if content.len() < 12 || &content[..4] != b"RIFF" || &content[8..12] != b"WEBP" {
+		return Err(invalid("WebP"));
+	}
+
+	let mut stripped = content[..12].to_vec();
+	let mut pos = 12;
+	while pos < content.len() {
+		// (fourcc, little endian size, data padded to even)
+		let len = content
+			.get(pos + 4..pos + 8)
+			.map(|len| u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize)
+			.map(|len| len + len % 2)
+			.filter(|len| pos + 8 + len <= content.len())
+			.ok_or_else(|| invalid("WebP"))?;
+		let chunk = &content[pos..pos + 8 + len];
+
+		match &chunk[..4] {
+			b"EXIF" | b"XMP " => {},
+			b"VP8X" if len > 0 => {
+				let start = stripped.len();
+				stripped.extend_from_slice(chunk);
+				stripped[start + 8] &= !(VP8X_EXIF_FLAG | VP8X_XMP_FLAG);
+			},
+			_ => stripped.extend_from_slice(chunk),
+		}
+		pos += chunk.len();
+	}
+
+	let riff_size = (stripped.len() - 8) as u32;
+	stripped[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+	Ok(stripped)
+}
+// endregion: --- Containers
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use image::RgbImage;
+
+	fn fx_image(width: u32, height: u32) -> DynamicImage {
+		DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| image::Rgb([x as u8, y as u8, 128])))
+	}
+
+	/// A JPEG with an EXIF segment (with a GPS tag, orientation 1) and a comment, after JFIF.
+	fn fx_jpeg_with_exif() -> Result<Vec<u8>> {
+		let (jpeg, _, _) = render(&fx_image(16, 8), None, None, false, ImageFormatKind::Jpeg)?;
+		// (big endian TIFF header, empty IFD, then the fixture "GPS" data)
+		let exif: &[u8] = b"\xFF\xE1\x00\x1CExif\x00\x00MM\x00\x2A\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00GPS-fx";
+		let comment: &[u8] = b"\xFF\xFE\x00\x09fx-note";
+
+		// (JFIF APP0 is the first segment)
+		let app0_end = 4 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+		Ok([&jpeg[..app0_end], exif, comment, &jpeg[app0_end..]].concat())
+	}
+
+	#[test]
+	fn test_strip_metadata_jpeg() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_content = Bytes::from(fx_jpeg_with_exif()?);
+
+		// -- Exec
+		let stripped = strip_metadata(&fx_content, ImageFormat::Jpeg, 1_000_000)?;
+
+		// -- Check
+		let contains = |content: &[u8], part: &[u8]| content.windows(part.len()).any(|w| w == part);
+		assert!(contains(&fx_content, b"GPS-fx") && contains(&fx_content, b"fx-note"));
+		assert!(!contains(&stripped, b"Exif") && !contains(&stripped, b"GPS-fx") && !contains(&stripped, b"fx-note"));
+		assert_eq!(stripped.len(), fx_content.len() - 0x1E - 0x0B);
+		assert_eq!(dimensions(&stripped, ImageFormat::Jpeg)?, (16, 8));
+		decode(&stripped, ImageFormat::Jpeg, 1_000_000)?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_strip_metadata_png_webp() -> Result<()> {
+		// -- Setup & Fixtures
+		let (png, _, _) = render(&fx_image(4, 4), None, None, false, ImageFormatKind::Png)?;
+		// (tEXt chunk, before IEND)
+		let text_chunk: &[u8] = b"\x00\x00\x00\x07tEXtGPS\x00fx1\x00\x00\x00\x00";
+		let fx_png = Bytes::from([&png[..png.len() - 12], text_chunk, &png[png.len() - 12..]].concat());
+		let (webp, _, _) = render(&fx_image(4, 4), None, None, false, ImageFormatKind::Webp)?;
+		let exif_chunk: &[u8] = b"EXIF\x06\x00\x00\x00GPS-fx";
+		let mut fx_webp = [&webp[..], exif_chunk].concat();
+		let riff_size = (fx_webp.len() - 8) as u32;
+		fx_webp[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+		// -- Exec
+		let png_stripped = strip_metadata(&fx_png, ImageFormat::Png, 1_000_000)?;
+		let webp_stripped = strip_metadata(&Bytes::from(fx_webp), ImageFormat::WebP, 1_000_000)?;
+
+		// -- Check
+		assert_eq!(png_stripped, png);
+		assert_eq!(webp_stripped, webp);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_render_fit_and_crop() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_image = fx_image(400, 200);
+
+		// -- Exec
+		let (fit, fit_width, fit_height) = render(&fx_image, Some(100), Some(100), false, ImageFormatKind::Png)?;
+		let (_, crop_width, crop_height) = render(&fx_image, Some(50), Some(50), true, ImageFormatKind::Webp)?;
+		let (_, same_width, same_height) = render(&fx_image, Some(1000), None, false, ImageFormatKind::Jpeg)?;
+
+		// -- Check
+		assert_eq!((fit_width, fit_height), (100, 50));
+		assert_eq!(dimensions(&fit, ImageFormat::Png)?, (100, 50));
+		assert_eq!((crop_width, crop_height), (50, 50));
+		assert_eq!((same_width, same_height), (400, 200));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_decode_err_too_large() -> Result<()> {
+		// -- Setup & Fixtures
+		let (fx_png, _, _) = render(&fx_image(100, 100), None, None, false, ImageFormatKind::Png)?;
+
+		// -- Exec
+		let res = decode(&fx_png, ImageFormat::Png, 9_999);
+
+		// -- Check
+		assert!(matches!(res, Err(MediaError::ImageTooLarge { width: 100, height: 100 })), "{res:?}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_strip_metadata_err_too_large_header() -> Result<()> {
+		// -- Setup & Fixtures
+		// (a JPEG frame header, SOF0, announcing 60000x60000 pixels)
+		let fx_jpeg = fx_jpeg_with_exif()?;
+		let sof = fx_jpeg.windows(2).position(|w| w == [0xFF, 0xC0]).unwrap();
+		let mut fx_large = fx_jpeg.clone();
+		fx_large[sof + 5..sof + 9].copy_from_slice(&[0xEA, 0x60, 0xEA, 0x60]);
+
+		// -- Exec
+		let res = strip_metadata(&Bytes::from(fx_large), ImageFormat::Jpeg, 50_000_000);
+
+		// -- Check
+		assert!(matches!(res, Err(MediaError::ImageTooLarge { width: 60_000, height: 60_000 })), "{res:?}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_validate_err_truncated_jpeg() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_jpeg = fx_jpeg_with_exif()?;
+		// (cut within the image data, after the start of scan)
+		let sos = fx_jpeg.windows(2).position(|w| w == [0xFF, 0xDA]).unwrap();
+		let fx_truncated = Bytes::from(fx_jpeg[..sos + 16].to_vec());
+
+		// -- Exec
+		let res_validate = validate(&fx_truncated, ImageFormat::Jpeg, 1_000_000);
+		let res_strip = strip_metadata(&fx_truncated, ImageFormat::Jpeg, 1_000_000);
+
+		// -- Check
+		// (the header alone is readable)
+		assert_eq!(dimensions(&fx_truncated, ImageFormat::Jpeg)?, (16, 8));
+		assert!(matches!(res_validate, Err(MediaError::ImageInvalid(_))), "{res_validate:?}");
+		assert!(matches!(res_strip, Err(MediaError::ImageInvalid(_))), "{res_strip:?}");
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
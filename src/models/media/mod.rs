@@ -0,0 +1,614 @@
+//! Media library (the content being in the media storage, see `models::storage`)
+//!
+//! Design:
+//!
+//! - Uploaded images (`media.process_images`) are stripped of their metadata before being stored,
+//!   then processed in the background (`processor`): their renditions (`media.renditions`) are generated
+//!   and stored aside the original, `processing_status` going from `pending` to `done` (or `failed`).
+//! - On-demand resizes (`/api/media/:id/resize`) are renditions too, generated once then kept.
+//!
+
+// region:    --- Modules
+mod error;
+pub mod imaging;
+pub mod processor;
+
+pub use self::error::{MediaError, MediaResult};
+pub use self::processor::MediaProcessor;
+
+use bytes::Bytes;
+use data_encoding::HEXLOWER;
+use modql::field::{Fields, HasFields};
+use sea_query::{Expr, Iden, OnConflict, Order, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use serde_with::serde_as;
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config::config;
+use crate::ctx::Ctx;
+
+use super::base::{self, traced_sql, DbBmc};
+use super::storage::StorageError;
+use super::{AppState, ModelError, ModelResult};
+// endregion: --- Modules
+
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+/// Complete "Media" model, as-is in the database (the content being in the media storage)
+pub struct Media {
+	pub id: i64,
+	pub owner_id: i64,
+	/// Key in the media storage (see `models::storage`)
+	#[serde(skip_serializing)]
+	pub storage_key: String,
+	/// As uploaded, only for display (e.g., `Content-Disposition`)
+	pub file_name: String,
+	pub mime_type: String,
+	pub size_bytes: i64,
+	/// Hex SHA-256 of the content
+	pub checksum_sha256: String,
+	/// Of images only
+	pub width: Option<i32>,
+	pub height: Option<i32>,
+	/// See `MediaProcessingStatus`
+	pub processing_status: String,
+	#[serde_as(as = "Rfc3339")]
+	pub created_at: OffsetDateTime
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, strum_macros::AsRefStr)]
+#[strum(serialize_all = "lowercase")]
+/// `media.processing_status`
+pub enum MediaProcessingStatus {
+	/// Image waiting for its renditions
+	Pending,
+	Done,
+	Failed,
+	/// Not processed (not an image, or `media.process_images = false`)
+	Skipped,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+/// A rendition of an image media (see `media.renditions`), or an on-demand resize
+pub struct MediaRendition {
+	pub media_id: i64,
+	pub name: String,
+	#[serde(skip_serializing)]
+	pub storage_key: String,
+	pub mime_type: String,
+	pub width: i32,
+	pub height: i32,
+	pub size_bytes: i64,
+	#[serde_as(as = "Rfc3339")]
+	pub created_at: OffsetDateTime
+}
+
+#[derive(Debug, Fields)]
+/// Struct holding fields required to create a media in the database (see `MediaBmc::upload`)
+struct MediaForCreate {
+	owner_id: i64,
+	storage_key: String,
+	file_name: String,
+	mime_type: String,
+	size_bytes: i64,
+	checksum_sha256: String,
+	width: Option<i32>,
+	height: Option<i32>,
+	processing_status: String
+}
+
+#[derive(Debug, Fields)]
+/// Struct holding fields updated once a media is processed (see `processor`)
+struct MediaForProcessed {
+	processing_status: String
+}
+
+#[derive(Iden)]
+enum MediaIden {
+	#[iden = "media"]
+	Table,
+	Id,
+	OwnerId,
+	ProcessingStatus
+}
+
+#[derive(Iden)]
+enum MediaRenditionIden {
+	#[iden = "media_renditions"]
+	Table,
+	MediaId,
+	Name,
+	StorageKey,
+	MimeType,
+	Width,
+	Height,
+	SizeBytes,
+	CreatedAt
+}
+
+#[derive(Iden)]
+enum PostMediaIden {
+	#[iden = "post_media"]
+	Table,
+	PostId,
+	MediaId,
+	Position
+}
+
+pub struct MediaBmc;
+
+impl DbBmc for MediaBmc {
+	const TABLE: &'static str = "media";
+}
+
+impl MediaBmc {
+	/// Store the content (images validated, and stripped of their metadata), then create its media
+	/// (the content is deleted if the creation fails), images being then queued for processing.
+	pub async fn upload(
+		ctx: &Ctx,
+		app_state: &AppState,
+		file_name: &str,
+		mime_type: &str,
+		content: Bytes
+	) -> ModelResult<i64> {
+		let media_config = &config().media;
+
+		// -- Validate the images (too large or invalid ones are refused), stripping their metadata when processed.
+		let format = imaging::image_format(mime_type);
+		let process_images = media_config.process_images;
+		let (content, dimensions) = match format {
+			Some(format) => {
+				let max_pixels = media_config.max_image_pixels;
+				app_state.media_processor.run(move || {
+					let content = match process_images {
+						true => imaging::strip_metadata(&content, format, max_pixels)?,
+						false => {
+							imaging::validate(&content, format, max_pixels)?;
+							content
+						},
+					};
+					let dimensions = imaging::dimensions(&content, format)?;
+					Ok((content, Some(dimensions)))
+				}).await?
+			},
+			None => (content, None),
+		};
+		let processing_status = match format {
+			Some(_) if process_images => MediaProcessingStatus::Pending,
+			_ => MediaProcessingStatus::Skipped,
+		};
+
+		let storage_key = format!("media/{}", Uuid::new_v4());
+		let data = MediaForCreate {
+			owner_id: ctx.user_id(),
+			storage_key: storage_key.clone(),
+			file_name: file_name.to_string(),
+			mime_type: mime_type.to_string(),
+			size_bytes: content.len() as i64,
+			checksum_sha256: HEXLOWER.encode(&Sha256::digest(&content)),
+			width: dimensions.map(|(width, _)| width as i32),
+			height: dimensions.map(|(_, height)| height as i32),
+			processing_status: processing_status.as_ref().to_string(),
+		};
+
+		app_state.media_storage.put(&storage_key, content, mime_type).await?;
+
+		let res = base::create::<Self, _>(ctx, app_state, data).await;
+		match res {
+			Ok(id) if processing_status == MediaProcessingStatus::Pending => app_state.media_processor.enqueue(id),
+			Ok(_) => {},
+			Err(_) => Self::delete_content(app_state, &storage_key).await,
+		}
+
+		res
+	}
+
+	pub async fn get(
+		ctx: &Ctx,
+		app_state: &AppState,
+		id: i64
+	) -> ModelResult<Media> {
+		base::get::<Self, _>(ctx, app_state, id).await
+	}
+
+	/// The stored content of a media.
+	pub async fn content(
+		// ctx: &Ctx,
+		app_state: &AppState,
+		media: &Media
+	) -> ModelResult<Bytes> {
+		let content = app_state
+			.media_storage
+			.get(&media.storage_key)
+			.await?
+			.ok_or_else(|| StorageError::ContentNotFound(media.storage_key.clone()))?;
+
+		Ok(content)
+	}
+
+	/// List the media of an owner, latest first.
+	pub async fn list_for_owner(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		owner_id: i64
+	) -> ModelResult<Vec<Media>> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(Media::field_column_refs())
+			.and_where(Expr::col(MediaIden::OwnerId).eq(owner_id))
+			.order_by(MediaIden::Id, Order::Desc);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let media = traced_sql(&sql, sqlx::query_as_with::<_, Media, _>(&sql, values).fetch_all(db)).await?;
+
+		Ok(media)
+	}
+
+	/// Delete a media (and its references from posts), then its content and its renditions.
+	pub async fn delete(
+		ctx: &Ctx,
+		app_state: &AppState,
+		media: &Media
+	) -> ModelResult<()> {
+		let renditions = Self::list_renditions(ctx, app_state, media.id).await?;
+
+		base::delete::<Self>(ctx, app_state, media.id).await?;
+		Self::delete_content(app_state, &media.storage_key).await;
+		for rendition in renditions {
+			Self::delete_content(app_state, &rendition.storage_key).await;
+		}
+
+		Ok(())
+	}
+
+	/// The ids of the media still waiting for their processing (e.g., queued at the last shutdown).
+	pub async fn list_pending_ids(app_state: &AppState) -> ModelResult<Vec<i64>> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.column(MediaIden::Id)
+			.and_where(Expr::col(MediaIden::ProcessingStatus).eq(MediaProcessingStatus::Pending.as_ref()))
+			.order_by(MediaIden::Id, Order::Asc);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let ids = traced_sql(&sql, sqlx::query_as_with::<_, (i64,), _>(&sql, values).fetch_all(db)).await?;
+
+		Ok(ids.into_iter().map(|(id,)| id).collect())
+	}
+
+	pub async fn set_processing_status(
+		ctx: &Ctx,
+		app_state: &AppState,
+		id: i64,
+		status: MediaProcessingStatus
+	) -> ModelResult<()> {
+		let data = MediaForProcessed { processing_status: status.as_ref().to_string() };
+
+		base::update::<Self, _>(ctx, app_state, id, data).await
+	}
+
+	/// (A failure leaves an orphan content, logged, the media being gone anyway)
+	async fn delete_content(app_state: &AppState, storage_key: &str) {
+		if let Err(err) = app_state.media_storage.delete(storage_key).await {
+			warn!("{:<12} - {} delete {storage_key} failed - {err}", "STORAGE", app_state.media_storage.name());
+		}
+	}
+}
+
+// region:    --- Renditions
+impl MediaBmc {
+	/// Store a rendition of `media` (replacing the one of the same name).
+	pub async fn save_rendition(
+		ctx: &Ctx,
+		app_state: &AppState,
+		media: &Media,
+		name: &str,
+		mime_type: &str,
+		(content, width, height): (Bytes, u32, u32)
+	) -> ModelResult<MediaRendition> {
+		let storage_key = format!("{}-{name}", media.storage_key);
+		let size_bytes = content.len() as i64;
+		app_state.media_storage.put(&storage_key, content, mime_type).await?;
+
+		// -- Build query
+		let mut query = Query::insert();
+		query
+			.into_table(MediaRenditionIden::Table)
+			.columns([
+				MediaRenditionIden::MediaId,
+				MediaRenditionIden::Name,
+				MediaRenditionIden::StorageKey,
+				MediaRenditionIden::MimeType,
+				MediaRenditionIden::Width,
+				MediaRenditionIden::Height,
+				MediaRenditionIden::SizeBytes,
+			])
+			.values([
+				media.id.into(),
+				name.into(),
+				storage_key.into(),
+				mime_type.into(),
+				(width as i32).into(),
+				(height as i32).into(),
+				size_bytes.into(),
+			])?
+			.on_conflict(
+				OnConflict::columns([MediaRenditionIden::MediaId, MediaRenditionIden::Name])
+					.update_columns([
+						MediaRenditionIden::MimeType,
+						MediaRenditionIden::Width,
+						MediaRenditionIden::Height,
+						MediaRenditionIden::SizeBytes,
+					])
+					.value(MediaRenditionIden::CreatedAt, Expr::current_timestamp())
+					.to_owned(),
+			);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		traced_sql(&sql, sqlx::query_with(&sql, values).execute(app_state.db())).await?;
+
+		Self::get_rendition(ctx, app_state, media.id, name)
+			.await?
+			.ok_or(ModelError::EntityNotFound { entity: "media_renditions", id: media.id })
+	}
+
+	/// The rendition `name` of a media, if generated.
+	pub async fn get_rendition(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		media_id: i64,
+		name: &str
+	) -> ModelResult<Option<MediaRendition>> {
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(MediaRenditionIden::Table)
+			.columns(MediaRendition::field_column_refs())
+			.and_where(Expr::col(MediaRenditionIden::MediaId).eq(media_id))
+			.and_where(Expr::col(MediaRenditionIden::Name).eq(name));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let rendition = traced_sql(
+			&sql,
+			sqlx::query_as_with::<_, MediaRendition, _>(&sql, values).fetch_optional(app_state.db())
+		).await?;
+
+		Ok(rendition)
+	}
+
+	/// The renditions of a media, by name.
+	pub async fn list_renditions(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		media_id: i64
+	) -> ModelResult<Vec<MediaRendition>> {
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(MediaRenditionIden::Table)
+			.columns(MediaRendition::field_column_refs())
+			.and_where(Expr::col(MediaRenditionIden::MediaId).eq(media_id))
+			.order_by(MediaRenditionIden::Name, Order::Asc);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let renditions = traced_sql(
+			&sql,
+			sqlx::query_as_with::<_, MediaRendition, _>(&sql, values).fetch_all(app_state.db())
+		).await?;
+
+		Ok(renditions)
+	}
+
+	/// The stored content of a rendition.
+	pub async fn rendition_content(
+		app_state: &AppState,
+		rendition: &MediaRendition
+	) -> ModelResult<Bytes> {
+		let content = app_state
+			.media_storage
+			.get(&rendition.storage_key)
+			.await?
+			.ok_or_else(|| StorageError::ContentNotFound(rendition.storage_key.clone()))?;
+
+		Ok(content)
+	}
+}
+// endregion: --- Renditions
+
+// region:    --- Post Media
+impl MediaBmc {
+	/// Replace the media referenced by a post, in order.
+	pub async fn set_for_post(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		post_id: i64,
+		media_ids: &[i64]
+	) -> ModelResult<()> {
+		let mut tx = app_state.db().begin().await?;
+
+		// -- Delete the current references
+		let mut query = Query::delete();
+		query
+			.from_table(PostMediaIden::Table)
+			.and_where(Expr::col(PostMediaIden::PostId).eq(post_id));
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		traced_sql(&sql, sqlx::query_with(&sql, values).execute(&mut *tx)).await?;
+
+		// -- Insert the new ones
+		if !media_ids.is_empty() {
+			let mut query = Query::insert();
+			query
+				.into_table(PostMediaIden::Table)
+				.columns([PostMediaIden::PostId, PostMediaIden::MediaId, PostMediaIden::Position]);
+			for (position, media_id) in media_ids.iter().enumerate() {
+				query.values([post_id.into(), (*media_id).into(), (position as i32).into()])?;
+			}
+			let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+			traced_sql(&sql, sqlx::query_with(&sql, values).execute(&mut *tx)).await?;
+		}
+
+		tx.commit().await?;
+
+		Ok(())
+	}
+
+	/// List the media referenced by a post, in order.
+	pub async fn list_for_post(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		post_id: i64
+	) -> ModelResult<Vec<Media>> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(Media::field_column_refs())
+			.inner_join(
+				PostMediaIden::Table,
+				Expr::col((PostMediaIden::Table, PostMediaIden::MediaId)).equals((MediaIden::Table, MediaIden::Id)),
+			)
+			.and_where(Expr::col((PostMediaIden::Table, PostMediaIden::PostId)).eq(post_id))
+			.order_by((PostMediaIden::Table, PostMediaIden::Position), Order::Asc);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let media = traced_sql(&sql, sqlx::query_as_with::<_, Media, _>(&sql, values).fetch_all(db)).await?;
+
+		Ok(media)
+	}
+}
+// endregion: --- Post Media
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use crate::_dev_utils;
+	use crate::config::ImageFormatKind;
+	use crate::models::post::PostBmc;
+
+	use super::*;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_upload_set_for_post_delete_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::new(1000)?; // Seeded user id
+		let fx_posts = _dev_utils::seed_posts(&ctx, &app_state, &[
+			("test_upload_set_for_post_delete_ok title", "test_upload_set_for_post_delete_ok content", 1000),
+		]).await?;
+		let fx_post_id = fx_posts[0].id;
+		let fx_content = Bytes::from_static(b"test_upload_set_for_post_delete_ok content");
+
+		// -- Exec
+		let id_01 = MediaBmc::upload(&ctx, &app_state, "fx-01.txt", "text/plain", fx_content.clone()).await?;
+		let id_02 = MediaBmc::upload(&ctx, &app_state, "fx-02.txt", "text/plain", fx_content.clone()).await?;
+		MediaBmc::set_for_post(&ctx, &app_state, fx_post_id, &[id_02, id_01]).await?;
+
+		// -- Check
+		let media_01 = MediaBmc::get(&ctx, &app_state, id_01).await?;
+		assert_eq!(media_01.size_bytes, fx_content.len() as i64);
+		assert_eq!(MediaBmc::content(&app_state, &media_01).await?, fx_content);
+		let post_media: Vec<i64> = MediaBmc::list_for_post(&ctx, &app_state, fx_post_id).await?.iter().map(|m| m.id).collect();
+		assert_eq!(post_media, [id_02, id_01]);
+
+		// -- Exec & Check (deleted, with its reference)
+		MediaBmc::delete(&ctx, &app_state, &media_01).await?;
+		let post_media: Vec<i64> = MediaBmc::list_for_post(&ctx, &app_state, fx_post_id).await?.iter().map(|m| m.id).collect();
+		assert_eq!(post_media, [id_02]);
+		assert_eq!(app_state.media_storage.get(&media_01.storage_key).await?, None);
+
+		// -- Clean
+		let media_02 = MediaBmc::get(&ctx, &app_state, id_02).await?;
+		MediaBmc::delete(&ctx, &app_state, &media_02).await?;
+		PostBmc::delete(&ctx, &app_state, fx_post_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_upload_image_processed_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::new(1000)?; // Seeded user id
+		let fx_image = image::DynamicImage::ImageRgb8(image::RgbImage::new(600, 300));
+		let mut fx_content = Vec::new();
+		fx_image.write_to(&mut std::io::Cursor::new(&mut fx_content), image::ImageFormat::Png)?;
+
+		// -- Exec
+		let id = MediaBmc::upload(&ctx, &app_state, "fx-01.png", "image/png", fx_content.into()).await?;
+		let pending = MediaBmc::get(&ctx, &app_state, id).await?;
+		processor::process_media(&app_state, id).await?;
+
+		// -- Check
+		assert_eq!(pending.processing_status, "pending");
+		assert_eq!((pending.width, pending.height), (Some(600), Some(300)));
+		let media = MediaBmc::get(&ctx, &app_state, id).await?;
+		assert_eq!(media.processing_status, "done");
+		let renditions = MediaBmc::list_renditions(&ctx, &app_state, id).await?;
+		let sizes: Vec<(&str, i32, i32)> = renditions.iter().map(|r| (r.name.as_str(), r.width, r.height)).collect();
+		assert_eq!(sizes, [("medium", 600, 300), ("original-webp", 600, 300), ("thumbnail", 200, 200)]);
+		let thumbnail = MediaBmc::rendition_content(&app_state, &renditions[2]).await?;
+		assert!(thumbnail.starts_with(b"RIFF"));
+
+		// -- Clean
+		MediaBmc::delete(&ctx, &app_state, &media).await?;
+		assert_eq!(app_state.media_storage.get(&renditions[2].storage_key).await?, None);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_upload_image_err_invalid() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::new(1000)?; // Seeded user id
+		let fx_image = image::DynamicImage::ImageRgb8(image::RgbImage::new(60, 30));
+		let (fx_jpeg, _, _) = imaging::render(&fx_image, None, None, false, ImageFormatKind::Jpeg)?;
+		// (header announcing 60000x60000 pixels, in the SOF0 frame segment)
+		let sof = fx_jpeg.windows(2).position(|w| w == [0xFF, 0xC0]).unwrap();
+		let mut fx_large = fx_jpeg.to_vec();
+		fx_large[sof + 5..sof + 9].copy_from_slice(&[0xEA, 0x60, 0xEA, 0x60]);
+		let fx_truncated = fx_jpeg[..fx_jpeg.len() - 2].to_vec();
+		let media_count = MediaBmc::list_for_owner(&ctx, &app_state, 1000).await?.len();
+
+		// -- Exec
+		let res_large = MediaBmc::upload(&ctx, &app_state, "fx-large.jpg", "image/jpeg", fx_large.into()).await;
+		let res_truncated = MediaBmc::upload(&ctx, &app_state, "fx-truncated.jpg", "image/jpeg", fx_truncated.into()).await;
+
+		// -- Check
+		assert!(
+			matches!(res_large, Err(ModelError::Media(MediaError::ImageTooLarge { width: 60_000, height: 60_000 }))),
+			"{res_large:?}"
+		);
+		assert!(matches!(res_truncated, Err(ModelError::Media(MediaError::ImageInvalid(_)))), "{res_truncated:?}");
+		assert_eq!(MediaBmc::list_for_owner(&ctx, &app_state, 1000).await?.len(), media_count);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
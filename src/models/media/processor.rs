@@ -0,0 +1,159 @@
+//! Background processing of the uploaded images
+//!
+//! - `MediaBmc::upload` queues the `pending` images, the task started by `MediaProcessor::start`
+//!   (`serve` only) generates their renditions, on start also the ones left `pending` (e.g., by a shutdown).
+//! - The CPU bound work (`imaging`, incl. the on-demand resizes) runs on the blocking pool,
+//!   at most `media.workers` at once.
+//!
+
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::config::config;
+use crate::ctx::Ctx;
+use crate::models::{AppState, ModelResult};
+
+use super::{imaging, MediaBmc, MediaError, MediaProcessingStatus, MediaResult};
+
+/// Media queued at once, the others stay `pending` until the next start
+const QUEUE_CAPACITY: usize = 1024;
+
+#[derive(Clone)]
+/// Handle to the media processing (see module doc).
+pub struct MediaProcessor {
+	tx: mpsc::Sender<i64>,
+	/// Taken by `start`
+	rx: Arc<Mutex<Option<mpsc::Receiver<i64>>>>,
+	permits: Arc<Semaphore>,
+}
+
+impl MediaProcessor {
+	pub fn new(workers: usize) -> Self {
+		let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+
+		Self {
+			tx,
+			rx: Arc::new(Mutex::new(Some(rx))),
+			permits: Arc::new(Semaphore::new(workers)),
+		}
+	}
+
+	/// Start the background task (once), ended at shutdown (the media being processed are finished).
+	pub fn start(&self, app_state: &AppState) {
+		let Some(rx) = self.rx.lock().ok().and_then(|mut rx| rx.take()) else {
+			return;
+		};
+
+		let app_state_task = app_state.clone();
+		app_state.shutdown.spawn(|stop| run_processor(app_state_task, rx, stop));
+	}
+
+	/// Queue the processing of a `pending` media (never blocks).
+	pub fn enqueue(&self, media_id: i64) {
+		if let Err(err) = self.tx.try_send(media_id) {
+			warn!("{:<12} - media {media_id} not queued, left pending - {err}", "MEDIA");
+		}
+	}
+
+	/// Run the CPU bound `work` on the blocking pool, when one of the `media.workers` is free.
+	pub async fn run<T, F>(&self, work: F) -> MediaResult<T>
+	where
+		T: Send + 'static,
+		F: FnOnce() -> MediaResult<T> + Send + 'static,
+	{
+		let _permit = self
+			.permits
+			.acquire()
+			.await
+			.map_err(|ex| MediaError::ProcessingAborted(ex.to_string()))?;
+
+		tokio::task::spawn_blocking(work)
+			.await
+			.map_err(|ex| MediaError::ProcessingAborted(ex.to_string()))?
+	}
+}
+
+async fn run_processor(app_state: AppState, mut rx: mpsc::Receiver<i64>, stop: CancellationToken) {
+	let mut processing = JoinSet::new();
+
+	// -- The media left pending.
+	match MediaBmc::list_pending_ids(&app_state).await {
+		Ok(ids) => {
+			for id in ids {
+				let app_state = app_state.clone();
+				processing.spawn(async move { log_processed(id, process_media(&app_state, id).await) });
+			}
+		},
+		Err(err) => warn!("{:<12} - pending media not listed - {err}", "MEDIA"),
+	}
+
+	loop {
+		tokio::select! {
+			id = rx.recv() => {
+				let Some(id) = id else {
+					break;
+				};
+
+				let app_state = app_state.clone();
+				processing.spawn(async move { log_processed(id, process_media(&app_state, id).await) });
+			},
+			// (reap the finished ones)
+			Some(_) = processing.join_next(), if !processing.is_empty() => {},
+			_ = stop.cancelled() => break,
+		}
+	}
+
+	while processing.join_next().await.is_some() {}
+}
+
+fn log_processed(id: i64, res: ModelResult<()>) {
+	match res {
+		Ok(()) => debug!("{:<12} - media {id} processed", "MEDIA"),
+		Err(err) => warn!("{:<12} - media {id} processing failed - {err}", "MEDIA"),
+	}
+}
+
+/// Generate the renditions of a `pending` image, then mark it `done` (`failed` if it could not be decoded).
+pub async fn process_media(app_state: &AppState, id: i64) -> ModelResult<()> {
+	let ctx = Ctx::root_ctx();
+	let media = MediaBmc::get(&ctx, app_state, id).await?;
+
+	let is_pending = media.processing_status == MediaProcessingStatus::Pending.as_ref();
+	let Some(format) = imaging::image_format(&media.mime_type).filter(|_| is_pending) else {
+		return Ok(());
+	};
+
+	// -- Render all the renditions from a single decode.
+	let content = MediaBmc::content(app_state, &media).await?;
+	let media_config = &config().media;
+	let renditions = media_config.renditions.clone();
+	let max_pixels = media_config.max_image_pixels;
+	let rendered = app_state.media_processor.run(move || {
+		let image = imaging::decode(&content, format, max_pixels)?;
+
+		renditions
+			.into_iter()
+			.map(|(name, rendition)| {
+				let rendered = imaging::render(&image, rendition.width, rendition.height, rendition.crop, rendition.format)?;
+				Ok((name, rendition.format.mime_type(), rendered))
+			})
+			.collect::<MediaResult<Vec<_>>>()
+	}).await;
+
+	let rendered = match rendered {
+		Ok(rendered) => rendered,
+		Err(err) => {
+			MediaBmc::set_processing_status(&ctx, app_state, id, MediaProcessingStatus::Failed).await?;
+			return Err(err.into());
+		},
+	};
+
+	for (name, mime_type, rendered) in rendered {
+		MediaBmc::save_rendition(&ctx, app_state, &media, &name, mime_type, rendered).await?;
+	}
+	MediaBmc::set_processing_status(&ctx, app_state, id, MediaProcessingStatus::Done).await
+}
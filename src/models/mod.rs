@@ -7,7 +7,7 @@
 //! - All application code data access must go through the Model layer.
 //! - The `ModelManager` holds the internal states/resources
 //!   needed by ModelControllers to access data.
-//!   (e.g., db_pool, S3 client, redis client).
+//!   (e.g., db_pool, S3 client, cache).
 //! - Model Controllers (e.g., `TaskBmc`, `ProjectBmc`) implement
 //!   CRUD and other data access methods on a given "entity"
 //!   (e.g., `Task`, `Project`).
@@ -21,30 +21,93 @@
 mod base;
 mod error;
 mod store;
+pub mod cache;
+pub mod storage;
 pub mod author;
+pub mod author_identity;
 pub mod post;
 pub mod edit;
+pub mod recovery_code;
+pub mod media;
+pub mod webhook;
 
 use serde::Serialize;
 
 pub use self::error::{ModelError, ModelResult};
+pub use self::store::migrations::MigrationStatus;
 
-use crate::models::store::{new_db_pool, Db};
+use crate::models::store::{db_ping, migrations, new_db_pool, Db};
+use crate::oidc::OidcClient;
+use crate::config::config;
+use crate::models::cache::ModelCache;
+use crate::models::media::MediaProcessor;
+use crate::models::storage::MediaStorage;
+use crate::models::webhook::WebhookDispatcher;
+use crate::shutdown::Shutdown;
+use crate::web::login_guard::LoginGuard;
+use crate::web::rate_limit::RateLimiter;
 
 // endregion: --- Modules
 
 #[derive(Clone)]
 /// Struct holding the application state
 pub struct AppState {
-	pub pool: Db
+	pub pool: Db,
+	pub login_guard: LoginGuard,
+	pub rate_limiter: RateLimiter,
+	pub oidc: OidcClient,
+	pub shutdown: Shutdown,
+	pub cache: ModelCache,
+	pub media_storage: MediaStorage,
+	pub media_processor: MediaProcessor,
+	pub webhook_dispatcher: WebhookDispatcher
 }
 
 impl AppState {
 	/// Constructor
 	pub async fn new() -> ModelResult<Self> {
 		let pool = new_db_pool().await?;
+		let cache = ModelCache::from_config(&config().cache).await?;
+		let rate_limiter = RateLimiter::from_config(&config().rate_limit).await?;
+		let media_storage = MediaStorage::from_config(&config().media)?;
+		let webhook_dispatcher = WebhookDispatcher::new(config().webhooks.timeout_ms)?;
 
-		Ok(AppState { pool })
+		Ok(AppState {
+			pool,
+			login_guard: LoginGuard::default(),
+			rate_limiter,
+			oidc: OidcClient::default(),
+			shutdown: Shutdown::default(),
+			cache,
+			media_storage,
+			media_processor: MediaProcessor::new(config().media.workers),
+			webhook_dispatcher
+		})
+	}
+
+	/// Checks the db answers (`SELECT 1`), for the readiness probe.
+	pub async fn db_ping(&self) -> ModelResult<()> {
+		Ok(db_ping(self.db()).await?)
+	}
+
+	/// Checks the db schema is at the version this build expects (see `store::migrations`).
+	pub async fn db_schema_check(&self) -> ModelResult<()> {
+		Ok(migrations::check_version(self.db()).await?)
+	}
+
+	/// Applies the pending schema migrations, returning their versions.
+	pub async fn db_migrate_up(&self) -> ModelResult<Vec<i64>> {
+		Ok(migrations::migrate_up(self.db()).await?)
+	}
+
+	/// All the schema migrations, applied or not.
+	pub async fn db_migration_status(&self) -> ModelResult<Vec<MigrationStatus>> {
+		Ok(migrations::status(self.db()).await?)
+	}
+
+	/// Reverts the schema migrations above `target_version`, returning their versions.
+	pub async fn db_migrate_down(&self, target_version: i64) -> ModelResult<Vec<i64>> {
+		Ok(migrations::migrate_down(self.db(), target_version).await?)
 	}
 
 	/// Returns the sqlx db pool reference.
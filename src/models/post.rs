@@ -4,9 +4,13 @@ use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use sqlx::FromRow;
 use serde::{Deserialize, Serialize};
 use time::PrimitiveDateTime;
+use validator::Validate;
 // use serial_test::*;
 
 use crate::ctx::Ctx;
+use crate::utils::validation::validate_not_blank;
+use data_encoding::HEXLOWER;
+use sha2::{Digest, Sha256};
 
 use super::{base::{self, DbBmc}, AppState, ModelResult};
 
@@ -26,21 +30,25 @@ pub struct Post {
 	pub updated_at: OffsetDateTime
 }
 
-#[derive(Deserialize, Debug, Fields)]
+#[derive(Deserialize, Debug, Fields, Validate)]
 /// Struct holding fields required from client to create a post in the database
 pub struct PostForCreate {
+	#[validate(length(max = 256), custom = "validate_not_blank")]
 	pub title: String,
+	#[validate(length(max = 512), custom = "validate_not_blank")]
 	pub content: String,
 	pub author_id: i64
 }
 
-#[derive(Deserialize, Debug, Fields)]
+#[derive(Deserialize, Debug, Fields, Validate)]
 /// Struct holding fields required from client to edit a post
 pub struct PostForUpdate {
+	#[validate(length(max = 256), custom = "validate_not_blank")]
 	pub title: Option<String>,
+	#[validate(length(max = 512), custom = "validate_not_blank")]
 	pub content: Option<String>
 }
-#[derive(FilterNodes, Deserialize, Default)]
+#[derive(FilterNodes, Deserialize, Default, Debug)]
 pub struct PostFilter {
 	id: Option<OpValsInt64>,
 
@@ -51,6 +59,24 @@ pub struct PostFilter {
 
 pub struct PostBmc;
 
+// region:    --- Cache Keys
+// Posts are public (same for any ctx), so they are cached (see `models::cache`).
+const CACHE_PREFIX: &str = "posts:";
+const CACHE_LIST_PREFIX: &str = "posts:list:";
+
+fn cache_key_id(id: i64) -> String {
+	format!("{CACHE_PREFIX}id:{id}")
+}
+
+/// (The list arguments hashed, as they have no canonical serialization)
+fn cache_key_list(filters: &Option<Vec<PostFilter>>, list_options: &Option<ListOptions>) -> String {
+	let args = format!("{filters:?}|{list_options:?}");
+	let hash = HEXLOWER.encode(&Sha256::digest(args.as_bytes()));
+
+	format!("{CACHE_LIST_PREFIX}{hash}")
+}
+// endregion: --- Cache Keys
+
 impl DbBmc for PostBmc {
 	const TABLE: &'static str = "posts";
 }
@@ -61,8 +87,10 @@ impl PostBmc {
 		app_state: &AppState,
 		data: PostForCreate,
 	) -> ModelResult<i64> {
-		let db = app_state.db();
-		base::create::<PostBmc, _>(ctx, app_state, data).await
+		let id = base::create::<PostBmc, _>(ctx, app_state, data).await?;
+		app_state.cache.invalidate(&[], &[CACHE_LIST_PREFIX]).await;
+
+		Ok(id)
 	}
 
 	pub async fn get(
@@ -70,7 +98,10 @@ impl PostBmc {
 		app_state: &AppState,
 		id: i64,
 	) -> ModelResult<Post> {
-		base::get::<Self, _>(ctx, app_state, id).await // Underscore on the second generic parameter because we return a model of author, the compiler can infer
+		app_state
+			.cache
+			.get_or_load(&cache_key_id(id), || base::get::<Self, _>(ctx, app_state, id))
+			.await
 	}
 
 	pub async fn list(
@@ -78,12 +109,56 @@ impl PostBmc {
 		filters: Option<Vec<PostFilter>>,
 		list_options: Option<ListOptions>
 	) -> ModelResult<Vec<Post>> {
-		base::list_no_auth::<Self, _, _>(app_state, filters, list_options).await
+		let key = cache_key_list(&filters, &list_options);
+
+		app_state
+			.cache
+			.get_or_load(&key, || base::list_no_auth::<Self, _, _>(app_state, filters, list_options))
+			.await
+	}
+
+	/// The latest created posts, of an author or of all (e.g., for the feeds).
+	pub async fn list_latest(
+		app_state: &AppState,
+		author_id: Option<i64>,
+		limit: i64
+	) -> ModelResult<Vec<Post>> {
+		let filters = author_id.map(|author_id| vec![PostFilter { author_id: Some(author_id.into()), ..Default::default() }]);
+		let list_options = ListOptions { limit: Some(limit), offset: None, order_bys: Some("!created_at".into()) };
+
+		Self::list(app_state, filters, Some(list_options)).await
+	}
+
+	pub async fn count(app_state: &AppState) -> ModelResult<i64> {
+		base::count_no_auth::<Self>(app_state).await
+	}
+
+	/// The `(id, updated_at)` of the posts by id (e.g., for the sitemap).
+	pub async fn list_versions(app_state: &AppState, offset: i64, limit: i64) -> ModelResult<Vec<(i64, OffsetDateTime)>> {
+		base::list_versions_no_auth::<Self>(app_state, offset, limit).await
 	}
 
 	
 	pub async fn update(ctx: &Ctx, app_state: &AppState, id: i64, post_e: PostForUpdate) -> ModelResult<()> {
-		base::update::<Self, _>(ctx, app_state, id, post_e).await
+		base::update::<Self, _>(ctx, app_state, id, post_e).await?;
+		app_state.cache.invalidate(&[&cache_key_id(id)], &[CACHE_LIST_PREFIX]).await;
+
+		Ok(())
+	}
+
+	/// Update only if the post is still at the `updated_at` version (see `base::update_if_unmodified`).
+	pub async fn update_if_unmodified(
+		ctx: &Ctx,
+		app_state: &AppState,
+		id: i64,
+		post_e: PostForUpdate,
+		updated_at: OffsetDateTime,
+	) -> ModelResult<()> {
+		let res = base::update_if_unmodified::<Self, _>(ctx, app_state, id, post_e, updated_at).await;
+		// (also when modified, as the cached version may be the stale one)
+		app_state.cache.invalidate(&[&cache_key_id(id)], &[CACHE_LIST_PREFIX]).await;
+
+		res
 	}
 
 
@@ -92,7 +167,15 @@ impl PostBmc {
 		app_state: &AppState,
 		id: i64,
 	) -> ModelResult<()> {
-		base::delete::<Self>(ctx, app_state, id).await
+		base::delete::<Self>(ctx, app_state, id).await?;
+		app_state.cache.invalidate(&[&cache_key_id(id)], &[CACHE_LIST_PREFIX]).await;
+
+		Ok(())
+	}
+
+	/// For changes made to the posts outside of `PostBmc` (e.g., an author deleted).
+	pub async fn invalidate_cache_all(app_state: &AppState) {
+		app_state.cache.invalidate(&[], &[CACHE_PREFIX]).await;
 	}
 
 }
@@ -251,6 +334,79 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_if_unmodified_err_modified() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_post = _dev_utils::seed_posts(&ctx, &app_state, &[("test_update_if_unmodified_err_modified - post 01", "content 01", 1000)])
+			.await?
+			.remove(0);
+		let fx_post_u = || PostForUpdate {
+			title: None,
+			content: Some("content 01 - new".to_string())
+		};
+
+		// -- Exec
+		// (first editor, at the current version)
+		PostBmc::update_if_unmodified(&ctx, &app_state, fx_post.id, fx_post_u(), fx_post.updated_at).await?;
+		// (second editor, at the same, now stale, version)
+		let res = PostBmc::update_if_unmodified(&ctx, &app_state, fx_post.id, fx_post_u(), fx_post.updated_at).await;
+
+		// -- Check
+		assert!(
+			matches!(res, Err(ModelError::EntityModified { entity: "posts", id }) if id == fx_post.id),
+			"EntityModified not matching, was {res:?}"
+		);
+		let post = PostBmc::get(&ctx, &app_state, fx_post.id).await?;
+		assert!(post.updated_at > fx_post.updated_at);
+
+		// -- Clean
+		PostBmc::delete(&ctx, &app_state, fx_post.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_cache_invalidation_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_title_new = "test_cache_invalidation_ok - post 01 - new";
+		let fx_post = _dev_utils::seed_posts(&ctx, &app_state, &[("test_cache_invalidation_ok - post 01", "content 01", 1000)])
+			.await?
+			.remove(0);
+		// (cached)
+		PostBmc::get(&ctx, &app_state, fx_post.id).await?;
+		PostBmc::list(&app_state, None, None).await?;
+
+		// -- Exec
+		PostBmc::update(
+			&ctx,
+			&app_state,
+			fx_post.id,
+			PostForUpdate {
+				title: Some(fx_title_new.to_string()),
+				content: None
+			}
+		).await?;
+
+		// -- Check
+		let post = PostBmc::get(&ctx, &app_state, fx_post.id).await?;
+		assert_eq!(post.title, fx_title_new);
+		let posts = PostBmc::list(&app_state, None, None).await?;
+		assert!(posts.iter().any(|p| p.id == fx_post.id && p.title == fx_title_new));
+
+		// -- Clean
+		PostBmc::delete(&ctx, &app_state, fx_post.id).await?;
+		let posts = PostBmc::list(&app_state, None, None).await?;
+		assert!(!posts.iter().any(|p| p.id == fx_post.id));
+
+		Ok(())
+	}
 }
 // endregion: --- Tests
 
@@ -0,0 +1,153 @@
+use modql::field::Fields;
+use sea_query::{Expr, Iden, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use serde_with::serde_as;
+use sqlx::FromRow;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::ctx::Ctx;
+
+use super::{base::{self, DbBmc}, AppState, ModelResult};
+
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+/// Complete "RecoveryCode" model, as-is in the database (only the hash of the code is stored)
+pub struct RecoveryCode {
+	pub id: i64,
+	pub author_id: i64,
+	pub code_hash: String,
+	#[serde_as(as = "Option<Rfc3339>")]
+	pub used_at: Option<OffsetDateTime>,
+	#[serde_as(as = "Rfc3339")]
+	pub created_at: OffsetDateTime
+}
+
+#[derive(Debug, Fields)]
+/// Struct holding fields required to create a recovery code in the database
+struct RecoveryCodeForCreate {
+	author_id: i64,
+	code_hash: String
+}
+
+#[derive(Iden)]
+enum RecoveryCodeIden {
+	AuthorId,
+	CodeHash,
+	UsedAt
+}
+
+pub struct RecoveryCodeBmc;
+
+impl DbBmc for RecoveryCodeBmc {
+	const TABLE: &'static str = "recovery_codes";
+}
+
+impl RecoveryCodeBmc {
+	/// Replace all the recovery codes of an author with the given (already hashed) ones.
+	pub async fn replace_for_author(
+		ctx: &Ctx,
+		app_state: &AppState,
+		author_id: i64,
+		code_hashes: Vec<String>
+	) -> ModelResult<()> {
+		Self::delete_for_author(ctx, app_state, author_id).await?;
+
+		for code_hash in code_hashes {
+			base::create::<Self, _>(ctx, app_state, RecoveryCodeForCreate { author_id, code_hash }).await?;
+		}
+
+		Ok(())
+	}
+
+	/// Delete all the recovery codes of an author.
+	pub async fn delete_for_author(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		author_id: i64
+	) -> ModelResult<()> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::delete();
+		query
+			.from_table(Self::table_ref())
+			.and_where(Expr::col(RecoveryCodeIden::AuthorId).eq(author_id));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		sqlx::query_with(&sql, values).execute(db).await?;
+
+		Ok(())
+	}
+
+	/// Mark the unused recovery code matching `code_hash` as used.
+	///
+	/// Returns `false` if there is no such unused code (each code can only be used once).
+	pub async fn consume(
+		// ctx: &Ctx,
+		app_state: &AppState,
+		author_id: i64,
+		code_hash: &str
+	) -> ModelResult<bool> {
+		let db = app_state.db();
+
+		// -- Build query
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.value(RecoveryCodeIden::UsedAt, Expr::current_timestamp())
+			.and_where(Expr::col(RecoveryCodeIden::AuthorId).eq(author_id))
+			.and_where(Expr::col(RecoveryCodeIden::CodeHash).eq(code_hash))
+			.and_where(Expr::col(RecoveryCodeIden::UsedAt).is_null());
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = sqlx::query_with(&sql, values)
+			.execute(db)
+			.await?
+			.rows_affected();
+
+		Ok(count > 0)
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	#![allow(unused)]
+	use crate::{_dev_utils, models::author::AuthorBmc};
+
+	use super::*;
+	use anyhow::{Ok, Result};
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_consume_once_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_authors = &[("test_consume_once_ok", "test_consume_once_ok@mail", "password")];
+		let author = _dev_utils::seed_authors(&ctx, &app_state, fx_authors).await?.remove(0);
+		let fx_hashes = vec!["hash-01".to_string(), "hash-02".to_string()];
+
+		RecoveryCodeBmc::replace_for_author(&ctx, &app_state, author.id, fx_hashes).await?;
+
+		// -- Exec
+		let first = RecoveryCodeBmc::consume(&app_state, author.id, "hash-01").await?;
+		let second = RecoveryCodeBmc::consume(&app_state, author.id, "hash-01").await?;
+		let unknown = RecoveryCodeBmc::consume(&app_state, author.id, "hash-03").await?;
+
+		// -- Check
+		assert!(first, "first use should succeed");
+		assert!(!second, "second use should fail");
+		assert!(!unknown, "unknown code should fail");
+
+		// -- Clean
+		AuthorBmc::delete(&ctx, &app_state, author.id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,44 @@
+use serde::Serialize;
+
+pub type StorageResult<T> = core::result::Result<T, StorageError>;
+
+#[derive(Debug, Serialize)]
+pub enum StorageError {
+	/// A key not made of `[a-zA-Z0-9_-]` segments separated by `/` (never from the client)
+	KeyInvalid(String),
+	/// The content of a media is missing (e.g., deleted from the bucket)
+	ContentNotFound(String),
+	Io(String),
+
+	// -- S3
+	S3Request(String),
+	/// S3Status(`status`, `body`) of an unexpected response
+	S3Status(u16, String),
+}
+
+// region:    --- Froms
+impl From<std::io::Error> for StorageError {
+	fn from(val: std::io::Error) -> Self {
+		Self::Io(val.to_string())
+	}
+}
+
+impl From<reqwest::Error> for StorageError {
+	fn from(val: reqwest::Error) -> Self {
+		Self::S3Request(val.to_string())
+	}
+}
+// endregion: --- Froms
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for StorageError {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for StorageError {}
+// endregion: --- Error Boilerplate
@@ -0,0 +1,94 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::fs;
+
+use super::{check_key, Storage, StorageResult};
+
+/// Objects as files under a directory (`media.local_dir`).
+pub struct LocalStorage {
+	dir: PathBuf,
+}
+
+impl LocalStorage {
+	pub fn new(dir: impl Into<PathBuf>) -> Self {
+		Self { dir: dir.into() }
+	}
+
+	fn path(&self, key: &str) -> StorageResult<PathBuf> {
+		check_key(key)?;
+
+		Ok(self.dir.join(key))
+	}
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+	fn name(&self) -> &'static str {
+		"local"
+	}
+
+	async fn put(&self, key: &str, content: Bytes, _mime_type: &str) -> StorageResult<()> {
+		let path = self.path(key)?;
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent).await?;
+		}
+
+		// (written aside then renamed, so that a partial file is never read)
+		let tmp_path = path.with_extension("tmp");
+		fs::write(&tmp_path, &content).await?;
+		fs::rename(&tmp_path, &path).await?;
+
+		Ok(())
+	}
+
+	async fn get(&self, key: &str) -> StorageResult<Option<Bytes>> {
+		match fs::read(self.path(key)?).await {
+			Ok(content) => Ok(Some(content.into())),
+			Err(ex) if ex.kind() == ErrorKind::NotFound => Ok(None),
+			Err(ex) => Err(ex.into()),
+		}
+	}
+
+	async fn delete(&self, key: &str) -> StorageResult<()> {
+		match fs::remove_file(self.path(key)?).await {
+			Err(ex) if ex.kind() != ErrorKind::NotFound => Err(ex.into()),
+			_ => Ok(()),
+		}
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[tokio::test]
+	async fn test_local_storage_put_get_delete() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_dir = std::env::temp_dir().join(format!("fx-media-{}", uuid::Uuid::new_v4()));
+		let storage = LocalStorage::new(&fx_dir);
+		let fx_key = "media/fx-01";
+		let fx_content = Bytes::from_static(b"fx content 01");
+
+		// -- Exec
+		storage.put(fx_key, fx_content.clone(), "text/plain").await?;
+		let content = storage.get(fx_key).await?;
+		storage.delete(fx_key).await?;
+
+		// -- Check
+		assert_eq!(content, Some(fx_content));
+		assert_eq!(storage.get(fx_key).await?, None);
+		// (deleting a missing key is not an error)
+		storage.delete(fx_key).await?;
+
+		// -- Clean
+		fs::remove_dir_all(&fx_dir).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
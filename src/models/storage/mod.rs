@@ -0,0 +1,102 @@
+//! Storage of the media content (see `models::media` for their metadata)
+//!
+//! Design:
+//!
+//! - `Storage` is a blob store by key, on the local filesystem (`LocalStorage`) or in an S3 compatible
+//!   bucket (`S3Storage`), as configured in `media.storage`.
+//! - Keys are generated by `MediaBmc` (never from the client), e.g., `media/{uuid}`.
+//! - The content is put before its `media` row is created, and deleted after it is deleted,
+//!   so that a row always has its content (a failure may leave an orphan object, never a dangling row).
+//!
+
+// region:    --- Modules
+mod error;
+mod local_storage;
+mod s3_storage;
+
+pub use self::error::{StorageError, StorageResult};
+pub use self::local_storage::LocalStorage;
+pub use self::s3_storage::S3Storage;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::config::{MediaConfig, MediaStorageKind};
+// endregion: --- Modules
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+	fn name(&self) -> &'static str;
+
+	async fn put(&self, key: &str, content: Bytes, mime_type: &str) -> StorageResult<()>;
+
+	/// The content of `key`, `None` if not found.
+	async fn get(&self, key: &str) -> StorageResult<Option<Bytes>>;
+
+	/// Delete `key`, if found.
+	async fn delete(&self, key: &str) -> StorageResult<()>;
+}
+
+/// The media storage of the `AppState`.
+#[derive(Clone)]
+pub struct MediaStorage {
+	backend: Arc<dyn Storage>,
+}
+
+impl MediaStorage {
+	pub fn new(backend: Arc<dyn Storage>) -> Self {
+		Self { backend }
+	}
+
+	pub fn from_config(media_config: &MediaConfig) -> StorageResult<Self> {
+		let backend: Arc<dyn Storage> = match (&media_config.storage, &media_config.s3) {
+			(MediaStorageKind::S3, Some(s3_config)) => Arc::new(S3Storage::new(s3_config.clone())?),
+			// (`s3` required by the config load)
+			_ => Arc::new(LocalStorage::new(&media_config.local_dir)),
+		};
+
+		Ok(Self::new(backend))
+	}
+}
+
+impl std::ops::Deref for MediaStorage {
+	type Target = dyn Storage;
+
+	fn deref(&self) -> &Self::Target {
+		self.backend.as_ref()
+	}
+}
+
+/// Keys are relative paths made of `[a-zA-Z0-9_-]` segments (e.g., no `..`), for both the backends.
+fn check_key(key: &str) -> StorageResult<()> {
+	let is_valid = key.split('/').all(|segment| {
+		!segment.is_empty() && segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+	});
+
+	if is_valid {
+		Ok(())
+	} else {
+		Err(StorageError::KeyInvalid(key.to_string()))
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[test]
+	fn test_check_key() -> Result<()> {
+		// -- Exec & Check
+		assert!(check_key("media/0b4e6c2e-fx").is_ok());
+		for fx_key in ["", "media/", "/media/fx", "media/../fx", "media/fx.png", "media//fx"] {
+			assert!(check_key(fx_key).is_err(), "{fx_key:?} should be invalid");
+		}
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,281 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use data_encoding::HEXLOWER;
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Method, RequestBuilder, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+use super::{check_key, Storage, StorageError, StorageResult};
+use crate::config::S3Config;
+use crate::utils::now_utc;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Objects in an S3 compatible bucket (path-style URLs, AWS Signature Version 4).
+pub struct S3Storage {
+	config: S3Config,
+	client: Client,
+}
+
+impl S3Storage {
+	pub fn new(config: S3Config) -> StorageResult<Self> {
+		let client = Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+
+		Ok(Self { config, client })
+	}
+
+	/// The signed request for `key`, with `payload` as body.
+	fn request(&self, method: Method, key: &str, payload: Bytes) -> StorageResult<RequestBuilder> {
+		check_key(key)?;
+
+		let S3Config { endpoint, bucket, .. } = &self.config;
+		let url = Url::parse(&format!("{}/{bucket}/{key}", endpoint.trim_end_matches('/')))
+			.map_err(|ex| StorageError::S3Request(ex.to_string()))?;
+
+		let payload_hash = HEXLOWER.encode(&Sha256::digest(&payload));
+		let signed = sign(&self.config, method.as_str(), &url, &payload_hash, now_utc());
+
+		Ok(self
+			.client
+			.request(method, url)
+			.header("x-amz-date", signed.amz_date)
+			.header("x-amz-content-sha256", payload_hash)
+			.header("authorization", signed.authorization)
+			.body(payload))
+	}
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+	fn name(&self) -> &'static str {
+		"s3"
+	}
+
+	async fn put(&self, key: &str, content: Bytes, mime_type: &str) -> StorageResult<()> {
+		let res = self.request(Method::PUT, key, content)?.header(CONTENT_TYPE, mime_type).send().await?;
+
+		match res.status() {
+			status if status.is_success() => Ok(()),
+			status => Err(StorageError::S3Status(status.as_u16(), res.text().await.unwrap_or_default())),
+		}
+	}
+
+	async fn get(&self, key: &str) -> StorageResult<Option<Bytes>> {
+		let res = self.request(Method::GET, key, Bytes::new())?.send().await?;
+
+		match res.status() {
+			StatusCode::NOT_FOUND => Ok(None),
+			status if status.is_success() => Ok(Some(res.bytes().await?)),
+			status => Err(StorageError::S3Status(status.as_u16(), res.text().await.unwrap_or_default())),
+		}
+	}
+
+	async fn delete(&self, key: &str) -> StorageResult<()> {
+		let res = self.request(Method::DELETE, key, Bytes::new())?.send().await?;
+
+		match res.status() {
+			// (S3 answers 204 whether the key existed or not)
+			status if status.is_success() || status == StatusCode::NOT_FOUND => Ok(()),
+			status => Err(StorageError::S3Status(status.as_u16(), res.text().await.unwrap_or_default())),
+		}
+	}
+}
+
+// region:    --- Signature V4
+struct Signed {
+	/// `x-amz-date` header, e.g., `20260119T120000Z`
+	amz_date: String,
+	authorization: String,
+}
+
+/// Signs a request without query, with the `host`, `x-amz-content-sha256` and `x-amz-date` headers
+/// (see https://docs.aws.amazon.com/AmazonS3/latest/API/sig-v4-header-based-auth.html).
+fn sign(config: &S3Config, method: &str, url: &Url, payload_hash: &str, now: OffsetDateTime) -> Signed {
+	let date = format!("{:04}{:02}{:02}", now.year(), u8::from(now.month()), now.day());
+	let amz_date = format!("{date}T{:02}{:02}{:02}Z", now.hour(), now.minute(), now.second());
+	let host = match url.port() {
+		Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+		None => url.host_str().unwrap_or_default().to_string(),
+	};
+
+	// -- Canonical request (keys only have unreserved characters, see `check_key`)
+	let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+	let canonical_request = format!(
+		"{method}\n{path}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{signed_headers}\n{payload_hash}",
+		path = url.path(),
+	);
+
+	// -- String to sign
+	let scope = format!("{date}/{}/s3/aws4_request", config.region);
+	let string_to_sign = format!(
+		"AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+		HEXLOWER.encode(&Sha256::digest(canonical_request.as_bytes()))
+	);
+
+	// -- Signature
+	let signing_key = signing_key(&config.secret_access_key, &date, &config.region, "s3");
+	let signature = HEXLOWER.encode(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+	Signed {
+		authorization: format!(
+			"AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+			config.access_key_id
+		),
+		amz_date,
+	}
+}
+
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+	let key = hmac_sha256(format!("AWS4{secret_access_key}").as_bytes(), date.as_bytes());
+	let key = hmac_sha256(&key, region.as_bytes());
+	let key = hmac_sha256(&key, service.as_bytes());
+
+	hmac_sha256(&key, b"aws4_request")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+	// (any key size is valid for HMAC)
+	let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key size");
+	mac.update(data);
+
+	mac.finalize().into_bytes().to_vec()
+}
+// endregion: --- Signature V4
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use axum::extract::{Path, State};
+	use axum::http::{HeaderMap, StatusCode};
+	use axum::routing::put;
+	use axum::Router;
+	use std::collections::HashMap;
+	use std::net::TcpListener;
+	use std::sync::{Arc, Mutex};
+
+	const FX_ACCESS_KEY_ID: &str = "fx-access-key-id";
+
+	// region:    --- Mock S3
+	/// Local stand-in of an S3 bucket, checking the payload hash and the credential of the signature.
+	#[derive(Clone, Default)]
+	struct MockS3 {
+		objects: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+	}
+
+	fn mock_check(headers: &HeaderMap, body: &[u8]) -> Result<(), StatusCode> {
+		let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+
+		let payload_hash = HEXLOWER.encode(&Sha256::digest(body));
+		let credential = format!("AWS4-HMAC-SHA256 Credential={FX_ACCESS_KEY_ID}/");
+		if header("x-amz-content-sha256") != payload_hash || !header("authorization").starts_with(&credential) {
+			return Err(StatusCode::FORBIDDEN);
+		}
+
+		Ok(())
+	}
+
+	async fn mock_put(
+		State(mock): State<MockS3>,
+		Path((bucket, key)): Path<(String, String)>,
+		headers: HeaderMap,
+		body: Bytes,
+	) -> StatusCode {
+		if let Err(status) = mock_check(&headers, &body) {
+			return status;
+		}
+		mock.objects.lock().unwrap().insert(format!("{bucket}/{key}"), body.to_vec());
+
+		StatusCode::OK
+	}
+
+	async fn mock_get(
+		State(mock): State<MockS3>,
+		Path((bucket, key)): Path<(String, String)>,
+		headers: HeaderMap,
+	) -> Result<Vec<u8>, StatusCode> {
+		mock_check(&headers, b"")?;
+
+		mock.objects.lock().unwrap().get(&format!("{bucket}/{key}")).cloned().ok_or(StatusCode::NOT_FOUND)
+	}
+
+	async fn mock_delete(
+		State(mock): State<MockS3>,
+		Path((bucket, key)): Path<(String, String)>,
+		headers: HeaderMap,
+	) -> StatusCode {
+		if let Err(status) = mock_check(&headers, b"") {
+			return status;
+		}
+		mock.objects.lock().unwrap().remove(&format!("{bucket}/{key}"));
+
+		StatusCode::NO_CONTENT
+	}
+
+	async fn spawn_mock_s3() -> Result<(MockS3, String)> {
+		let listener = TcpListener::bind("127.0.0.1:0")?;
+		listener.set_nonblocking(true)?;
+		let endpoint = format!("http://{}", listener.local_addr()?);
+		let mock = MockS3::default();
+
+		let routes = Router::new()
+			.route("/:bucket/*key", put(mock_put).get(mock_get).delete(mock_delete))
+			.with_state(mock.clone());
+
+		let server = axum::Server::from_tcp(listener)?.serve(routes.into_make_service());
+		tokio::spawn(server);
+
+		Ok((mock, endpoint))
+	}
+
+	fn fx_config(endpoint: &str) -> S3Config {
+		S3Config {
+			endpoint: endpoint.to_string(),
+			region: "us-east-1".to_string(),
+			bucket: "fx-bucket".to_string(),
+			access_key_id: FX_ACCESS_KEY_ID.to_string(),
+			secret_access_key: "fx-secret-access-key".to_string().into(),
+		}
+	}
+	// endregion: --- Mock S3
+
+	#[test]
+	fn test_signing_key() -> Result<()> {
+		// -- Exec (example of the AWS documentation)
+		let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+
+		// -- Check
+		assert_eq!(HEXLOWER.encode(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_s3_storage_put_get_delete() -> Result<()> {
+		// -- Setup & Fixtures
+		let (mock, endpoint) = spawn_mock_s3().await?;
+		let storage = S3Storage::new(fx_config(&endpoint))?;
+		let fx_key = "media/fx-01";
+		let fx_content = Bytes::from_static(b"fx content 01");
+
+		// -- Exec
+		storage.put(fx_key, fx_content.clone(), "text/plain").await?;
+		let content = storage.get(fx_key).await?;
+
+		// -- Check
+		assert_eq!(content, Some(fx_content));
+		assert!(mock.objects.lock().unwrap().contains_key("fx-bucket/media/fx-01"));
+
+		// -- Exec & Check (deleted)
+		storage.delete(fx_key).await?;
+		assert_eq!(storage.get(fx_key).await?, None);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
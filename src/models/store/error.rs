@@ -4,7 +4,20 @@ pub type StoreResult<T> = core::result::Result<T, StoreError>;
 
 #[derive(Debug, Serialize)] // This is very flexible for logging into t a new-line JSON format
 pub enum StoreError{
-	FailToCreatePool(String)
+	FailToCreatePool(String),
+
+	// -- Health checks
+	DbPingFail(String),
+
+	// -- Migrations (see `store::migrations`)
+	/// The db has migrations the binary does not know of (i.e., deployed by a newer binary)
+	MigrationDbAhead { db_version: i64, binary_version: i64 },
+	/// The db has not applied all the binary migrations
+	MigrationPending { db_version: i64, binary_version: i64 },
+	/// An applied migration was changed afterward
+	MigrationChecksumMismatch { version: i64 },
+	MigrationFail { version: i64, cause: String },
+	MigrationSqlx(String)
 }
 
 // region: --Error Boilerplate
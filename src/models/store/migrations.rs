@@ -0,0 +1,406 @@
+//! Versioned schema migrations
+//!
+//! Design:
+//!
+//! - Migrations are embedded in the binary (`sql/migrations/{version}_{name}.{up|down}.sql`, listed in `MIGRATIONS`).
+//! - Applied migrations are recorded in `schema_migrations`, with the checksum of their up SQL,
+//!   so that an applied migration edited afterward is detected (and refused).
+//! - A db with migrations unknown to the binary (i.e., ahead of it) is refused.
+//! - Each migration runs in its own transaction, under an advisory lock (one migrating instance at a time).
+//!
+
+use data_encoding::HEXLOWER;
+use sha2::{Digest, Sha256};
+use sqlx::{Acquire, Executor, PgConnection};
+use time::OffsetDateTime;
+use tracing::info;
+
+use super::{Db, StoreError, StoreResult};
+
+/// `pg_advisory_lock` key of the migrations
+const MIGRATIONS_LOCK_KEY: i64 = 0x0061_7875_6d63_6d73; // "axumcms"
+/// SQLSTATE of a missing relation (e.g., `schema_migrations` before the first migration)
+const PG_UNDEFINED_TABLE: &str = "42P01";
+
+pub struct Migration {
+	pub version: i64,
+	pub name: &'static str,
+	pub up: &'static str,
+	pub down: &'static str,
+}
+
+macro_rules! migration {
+	($version:literal, $file_stem:literal, $name:literal) => {
+		Migration {
+			version: $version,
+			name: $name,
+			up: include_str!(concat!("../../../sql/migrations/", $file_stem, ".up.sql")),
+			down: include_str!(concat!("../../../sql/migrations/", $file_stem, ".down.sql")),
+		}
+	};
+}
+
+/// All the migrations, in version order.
+pub const MIGRATIONS: &[Migration] = &[
+	migration!(1, "0001_initial_schema", "initial_schema"),
+	migration!(2, "0002_author_admin", "author_admin"),
+	migration!(3, "0003_author_updated_at", "author_updated_at"),
+	migration!(4, "0004_media", "media"),
+	migration!(5, "0005_media_renditions", "media_renditions"),
+	migration!(6, "0006_webhooks", "webhooks"),
+];
+
+impl Migration {
+	pub fn checksum(&self) -> String {
+		HEXLOWER.encode(&Sha256::digest(self.up.as_bytes()))
+	}
+}
+
+/// Version the binary expects the db to be at.
+pub fn latest_version() -> i64 {
+	MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+// region:    --- Applied Migrations
+#[derive(Debug, sqlx::FromRow)]
+struct AppliedMigration {
+	version: i64,
+	name: String,
+	checksum: String,
+	applied_at: OffsetDateTime,
+}
+
+/// A migration of the binary, or of the db only (`known` false, i.e., db ahead)
+#[derive(Debug)]
+pub struct MigrationStatus {
+	pub version: i64,
+	pub name: String,
+	pub known: bool,
+	pub applied_at: Option<OffsetDateTime>,
+	pub checksum_ok: bool,
+}
+
+async fn ensure_migrations_table(conn: &mut PgConnection) -> StoreResult<()> {
+	conn.execute(
+		r#"CREATE TABLE IF NOT EXISTS "schema_migrations" (
+			version BIGINT PRIMARY KEY,
+			name varchar(128) NOT NULL,
+			checksum varchar(64) NOT NULL,
+			applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+		)"#,
+	)
+	.await
+	.map_err(|ex| StoreError::MigrationSqlx(ex.to_string()))?;
+
+	Ok(())
+}
+
+/// The applied migrations, none when `schema_migrations` does not exist yet (read only, for the checks).
+async fn applied_migrations(conn: &mut PgConnection) -> StoreResult<Vec<AppliedMigration>> {
+	let res = sqlx::query_as(r#"SELECT version, name, checksum, applied_at FROM "schema_migrations" ORDER BY version"#)
+		.fetch_all(conn)
+		.await;
+
+	match res {
+		Ok(applied) => Ok(applied),
+		Err(sqlx::Error::Database(ex)) if ex.code().as_deref() == Some(PG_UNDEFINED_TABLE) => Ok(Vec::new()),
+		Err(ex) => Err(StoreError::MigrationSqlx(ex.to_string())),
+	}
+}
+
+/// Check the applied migrations are known to the binary, and unchanged.
+fn verify_applied(applied: &[AppliedMigration]) -> StoreResult<()> {
+	for applied in applied {
+		let Some(migration) = MIGRATIONS.iter().find(|m| m.version == applied.version) else {
+			return Err(StoreError::MigrationDbAhead {
+				db_version: applied.version,
+				binary_version: latest_version(),
+			});
+		};
+
+		if migration.checksum() != applied.checksum {
+			return Err(StoreError::MigrationChecksumMismatch { version: migration.version });
+		}
+	}
+
+	Ok(())
+}
+// endregion: --- Applied Migrations
+
+// region:    --- Migrate
+/// Apply the pending migrations, returning their versions.
+pub async fn migrate_up(db: &Db) -> StoreResult<Vec<i64>> {
+	let mut conn = db.acquire().await.map_err(|ex| StoreError::MigrationSqlx(ex.to_string()))?;
+
+	lock(&mut conn).await?;
+	let res = migrate_up_locked(&mut conn).await;
+	unlock(&mut conn).await;
+
+	res
+}
+
+async fn migrate_up_locked(conn: &mut PgConnection) -> StoreResult<Vec<i64>> {
+	ensure_migrations_table(conn).await?;
+	let applied = applied_migrations(conn).await?;
+	verify_applied(&applied)?;
+
+	let mut versions = Vec::new();
+	for migration in MIGRATIONS.iter().filter(|m| !applied.iter().any(|a| a.version == m.version)) {
+		info!("{:<12} - up {} {}", "MIGRATION", migration.version, migration.name);
+		run_migration(conn, migration, Direction::Up).await?;
+		versions.push(migration.version);
+	}
+
+	Ok(versions)
+}
+
+/// Revert the applied migrations above `target_version` (latest first), returning their versions.
+pub async fn migrate_down(db: &Db, target_version: i64) -> StoreResult<Vec<i64>> {
+	let mut conn = db.acquire().await.map_err(|ex| StoreError::MigrationSqlx(ex.to_string()))?;
+
+	lock(&mut conn).await?;
+	let res = migrate_down_locked(&mut conn, target_version).await;
+	unlock(&mut conn).await;
+
+	res
+}
+
+async fn migrate_down_locked(conn: &mut PgConnection, target_version: i64) -> StoreResult<Vec<i64>> {
+	ensure_migrations_table(conn).await?;
+	let applied = applied_migrations(conn).await?;
+	verify_applied(&applied)?;
+
+	let mut versions = Vec::new();
+	for migration in MIGRATIONS
+		.iter()
+		.rev()
+		.filter(|m| m.version > target_version && applied.iter().any(|a| a.version == m.version))
+	{
+		info!("{:<12} - down {} {}", "MIGRATION", migration.version, migration.name);
+		run_migration(conn, migration, Direction::Down).await?;
+		versions.push(migration.version);
+	}
+
+	Ok(versions)
+}
+
+/// Check the db is at the version the binary expects (read only, run by the readiness probe).
+pub async fn check_version(db: &Db) -> StoreResult<()> {
+	let mut conn = db.acquire().await.map_err(|ex| StoreError::MigrationSqlx(ex.to_string()))?;
+
+	let applied = applied_migrations(&mut conn).await?;
+	verify_applied(&applied)?;
+
+	let db_version = applied.last().map(|a| a.version).unwrap_or(0);
+	if applied.len() != MIGRATIONS.len() {
+		return Err(StoreError::MigrationPending {
+			db_version,
+			binary_version: latest_version(),
+		});
+	}
+
+	Ok(())
+}
+
+/// All the migrations, of the binary and the db, in version order.
+pub async fn status(db: &Db) -> StoreResult<Vec<MigrationStatus>> {
+	let mut conn = db.acquire().await.map_err(|ex| StoreError::MigrationSqlx(ex.to_string()))?;
+	let applied = applied_migrations(&mut conn).await?;
+
+	let mut statuses: Vec<MigrationStatus> = MIGRATIONS
+		.iter()
+		.map(|m| {
+			let applied = applied.iter().find(|a| a.version == m.version);
+			MigrationStatus {
+				version: m.version,
+				name: m.name.to_string(),
+				known: true,
+				applied_at: applied.map(|a| a.applied_at),
+				checksum_ok: applied.map(|a| a.checksum == m.checksum()).unwrap_or(true),
+			}
+		})
+		.collect();
+
+	for applied in applied.iter().filter(|a| !MIGRATIONS.iter().any(|m| m.version == a.version)) {
+		statuses.push(MigrationStatus {
+			version: applied.version,
+			name: applied.name.clone(),
+			known: false,
+			applied_at: Some(applied.applied_at),
+			checksum_ok: true,
+		});
+	}
+	statuses.sort_by_key(|s| s.version);
+
+	Ok(statuses)
+}
+
+enum Direction {
+	Up,
+	Down,
+}
+
+/// Run the migration SQL (possibly several statements) and record it, in a transaction.
+async fn run_migration(conn: &mut PgConnection, migration: &Migration, direction: Direction) -> StoreResult<()> {
+	let fail = |ex: sqlx::Error| StoreError::MigrationFail {
+		version: migration.version,
+		cause: ex.to_string(),
+	};
+
+	let mut tx = conn.begin().await.map_err(fail)?;
+
+	// (A `&str` is run as a simple query, so it can hold several statements)
+	match direction {
+		Direction::Up => {
+			tx.execute(migration.up).await.map_err(fail)?;
+			sqlx::query(r#"INSERT INTO "schema_migrations" (version, name, checksum) VALUES ($1, $2, $3)"#)
+				.bind(migration.version)
+				.bind(migration.name)
+				.bind(migration.checksum())
+				.execute(&mut *tx)
+				.await
+				.map_err(fail)?;
+		}
+		Direction::Down => {
+			tx.execute(migration.down).await.map_err(fail)?;
+			sqlx::query(r#"DELETE FROM "schema_migrations" WHERE version = $1"#)
+				.bind(migration.version)
+				.execute(&mut *tx)
+				.await
+				.map_err(fail)?;
+		}
+	}
+
+	tx.commit().await.map_err(fail)?;
+
+	Ok(())
+}
+
+/// Take the migrations advisory lock (waits for another migrating instance).
+async fn lock(conn: &mut PgConnection) -> StoreResult<()> {
+	sqlx::query("SELECT pg_advisory_lock($1)")
+		.bind(MIGRATIONS_LOCK_KEY)
+		.execute(conn)
+		.await
+		.map_err(|ex| StoreError::MigrationSqlx(ex.to_string()))?;
+
+	Ok(())
+}
+
+/// (Also released when the connection closes)
+async fn unlock(conn: &mut PgConnection) {
+	let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+		.bind(MIGRATIONS_LOCK_KEY)
+		.execute(conn)
+		.await;
+}
+// endregion: --- Migrate
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_migrate_up_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let db = &app_state.pool;
+
+		// -- Exec
+		let versions = migrate_up(db).await?;
+
+		// -- Check
+		// (Applied by the dev db init)
+		assert!(versions.is_empty());
+		check_version(db).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_migrate_err_checksum_mismatch() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let db = &app_state.pool;
+		sqlx::query(r#"UPDATE "schema_migrations" SET checksum = 'fx-checksum' WHERE version = 1"#)
+			.execute(db)
+			.await?;
+
+		// -- Exec
+		let res = migrate_up(db).await;
+
+		// -- Check
+		assert!(
+			matches!(res, Err(StoreError::MigrationChecksumMismatch { version: 1 })),
+			"Should have been a `MigrationChecksumMismatch`, was {res:?}"
+		);
+
+		// -- Clean
+		sqlx::query(r#"UPDATE "schema_migrations" SET checksum = $1 WHERE version = 1"#)
+			.bind(MIGRATIONS[0].checksum())
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_migrate_err_db_ahead() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let db = &app_state.pool;
+		let fx_version = latest_version() + 100;
+		sqlx::query(r#"INSERT INTO "schema_migrations" (version, name, checksum) VALUES ($1, 'fx_future', 'fx')"#)
+			.bind(fx_version)
+			.execute(db)
+			.await?;
+
+		// -- Exec
+		let res_up = migrate_up(db).await;
+		let res_check = check_version(db).await;
+
+		// -- Check
+		for res in [res_up.map(|_| ()), res_check] {
+			assert!(
+				matches!(res, Err(StoreError::MigrationDbAhead { db_version, .. }) if db_version == fx_version),
+				"Should have been a `MigrationDbAhead`, was {res:?}"
+			);
+		}
+
+		// -- Clean
+		sqlx::query(r#"DELETE FROM "schema_migrations" WHERE version = $1"#)
+			.bind(fx_version)
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_migrate_applied_no_table() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let mut tx = app_state.pool.begin().await?;
+		sqlx::query(r#"DROP TABLE "schema_migrations""#).execute(&mut *tx).await?;
+
+		// -- Exec
+		let applied = applied_migrations(&mut tx).await?;
+
+		// -- Check
+		assert!(applied.is_empty());
+
+		// -- Clean
+		tx.rollback().await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
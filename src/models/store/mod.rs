@@ -1,10 +1,12 @@
 // region:    --- Modules
 
 mod error;
+pub mod migrations;
 
 pub use self::error::{StoreError, StoreResult};
 use crate::config;
 use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
 use sqlx::{Pool, Postgres};
 
 // endregion: --- Modules
@@ -12,9 +14,21 @@ use sqlx::{Pool, Postgres};
 pub type Db = Pool<Postgres>;
 
 pub async fn new_db_pool() -> StoreResult<Db> {
+	let db_config = &config().db;
+
 	PgPoolOptions::new()
-		.max_connections(5)
-		.connect(&config().DB_URL)
+		.max_connections(db_config.max_connections)
+		.acquire_timeout(Duration::from_millis(db_config.acquire_timeout_ms))
+		.connect(&db_config.url)
 		.await
 		.map_err(|ex| StoreError::FailToCreatePool(ex.to_string()))
 }
+
+pub async fn db_ping(db: &Db) -> StoreResult<()> {
+	sqlx::query("SELECT 1")
+		.execute(db)
+		.await
+		.map_err(|ex| StoreError::DbPingFail(ex.to_string()))?;
+
+	Ok(())
+}
@@ -0,0 +1,305 @@
+//! Delivery of the queued webhook events
+//!
+//! - The task started by `WebhookDispatcher::start` (`serve` only) takes the due deliveries, woken on
+//!   the events of this instance, and every `webhooks.poll_interval_sec` (retries, events of other instances).
+//! - At most `webhooks.workers` deliveries in flight, the ones in flight being finished at shutdown.
+//! - A delivery is a `POST` of its payload, delivered on a `2xx` (redirects are not followed),
+//!   else retried after `webhooks.backoff_base_sec`, doubled on each retry (up to `webhooks.backoff_max_sec`).
+//! - Only to the allowed addresses (see `target`), the response body not kept (e.g., of an internal service).
+//!
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use reqwest::header;
+use reqwest::redirect::Policy;
+use time::OffsetDateTime;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::config::config;
+use crate::crypt::webhook_sig::sign_webhook_payload;
+use crate::ctx::Ctx;
+use crate::models::{AppState, ModelResult};
+
+use super::target::{check_target, TargetResolver};
+use super::{
+	WebhookAttemptForCreate, WebhookBmc, WebhookDelivery, WebhookDeliveryBmc, WebhookDeliveryStatus, WebhookError,
+	WebhookResult, WebhookSubscription,
+};
+
+/// Lease of the taken deliveries, beyond the request timeout
+const LEASE_MARGIN_SEC: u64 = 60;
+
+#[derive(Clone)]
+/// Handle to the webhook deliveries (see module doc).
+pub struct WebhookDispatcher {
+	wake: Arc<Notify>,
+	http: reqwest::Client,
+	started: Arc<AtomicBool>,
+}
+
+impl WebhookDispatcher {
+	pub fn new(timeout_ms: u64) -> WebhookResult<Self> {
+		let http = reqwest::Client::builder()
+			.timeout(Duration::from_millis(timeout_ms))
+			.redirect(Policy::none())
+			// (connections to the allowed addresses only)
+			.dns_resolver(Arc::new(TargetResolver))
+			.no_proxy()
+			.user_agent(concat!("axum-cms-webhooks/", env!("CARGO_PKG_VERSION")))
+			.build()
+			.map_err(|ex| WebhookError::HttpClient(ex.to_string()))?;
+
+		Ok(Self {
+			wake: Arc::new(Notify::new()),
+			http,
+			started: Arc::new(AtomicBool::new(false)),
+		})
+	}
+
+	/// Start the background task (once, unless `webhooks.enabled = false`), ended at shutdown.
+	pub fn start(&self, app_state: &AppState) {
+		if !config().webhooks.enabled || self.started.swap(true, Ordering::SeqCst) {
+			return;
+		}
+
+		let app_state_task = app_state.clone();
+		app_state.shutdown.spawn(|stop| run_dispatcher(app_state_task, stop));
+	}
+
+	/// Take the due deliveries now (e.g., just queued).
+	pub fn wake(&self) {
+		self.wake.notify_one();
+	}
+}
+
+async fn run_dispatcher(app_state: AppState, stop: CancellationToken) {
+	let webhooks_config = &config().webhooks;
+	let dispatcher = app_state.webhook_dispatcher.clone();
+	let poll_interval = Duration::from_secs(webhooks_config.poll_interval_sec);
+	let lease_sec = webhooks_config.timeout_ms / 1000 + LEASE_MARGIN_SEC;
+
+	let mut in_flight = JoinSet::new();
+	loop {
+		// -- Take the due ones, up to the free workers.
+		let free = webhooks_config.workers.saturating_sub(in_flight.len());
+		if free > 0 {
+			match WebhookDeliveryBmc::claim_due(&app_state, free as u64, lease_sec).await {
+				Ok(deliveries) => {
+					for delivery in deliveries {
+						let app_state = app_state.clone();
+						in_flight.spawn(async move {
+							let id = delivery.id;
+							log_delivered(id, deliver(&app_state, delivery).await)
+						});
+					}
+				},
+				Err(err) => warn!("{:<12} - due deliveries not taken - {err}", "WEBHOOK"),
+			}
+		}
+
+		tokio::select! {
+			_ = dispatcher.wake.notified() => {},
+			_ = tokio::time::sleep(poll_interval) => {},
+			// (a worker is free)
+			Some(_) = in_flight.join_next(), if !in_flight.is_empty() => {},
+			_ = stop.cancelled() => break,
+		}
+	}
+
+	while in_flight.join_next().await.is_some() {}
+}
+
+fn log_delivered(id: i64, res: ModelResult<WebhookDeliveryStatus>) {
+	match res {
+		Ok(status) => debug!("{:<12} - delivery {id} attempted, {}", "WEBHOOK", status.as_ref()),
+		Err(err) => warn!("{:<12} - delivery {id} not recorded - {err}", "WEBHOOK"),
+	}
+}
+
+/// Attempt a delivery, and record it (its status after it).
+async fn deliver(app_state: &AppState, delivery: WebhookDelivery) -> ModelResult<WebhookDeliveryStatus> {
+	let webhooks_config = &config().webhooks;
+	let subscription = WebhookBmc::get(&Ctx::root_ctx(), app_state, delivery.subscription_id).await?;
+	let attempt = delivery.attempts + 1;
+
+	// -- Send.
+	let start = Instant::now();
+	let res = match subscription.active {
+		true => send(&app_state.webhook_dispatcher.http, &subscription, &delivery).await,
+		false => Err((None, "Subscription inactive".to_string())),
+	};
+	let duration_ms = start.elapsed().as_millis() as i32;
+
+	// -- Record, and schedule the retry.
+	let (status, next_attempt_at) = match &res {
+		Ok(_) => (WebhookDeliveryStatus::Delivered, delivery.next_attempt_at),
+		Err(_) if !subscription.active || attempt as u32 >= webhooks_config.max_attempts => {
+			(WebhookDeliveryStatus::Failed, delivery.next_attempt_at)
+		},
+		Err(_) => {
+			let delay = retry_delay(attempt as u32, webhooks_config.backoff_base_sec, webhooks_config.backoff_max_sec);
+			(WebhookDeliveryStatus::Pending, OffsetDateTime::now_utc() + delay)
+		},
+	};
+	let (response_status, error) = match res {
+		Ok(response_status) => (Some(response_status), None),
+		Err((response_status, error)) => (response_status, Some(error)),
+	};
+
+	let attempt = WebhookAttemptForCreate {
+		delivery_id: delivery.id,
+		attempt,
+		response_status: response_status.map(i32::from),
+		error,
+		duration_ms,
+	};
+	WebhookDeliveryBmc::record_attempt(app_state, attempt, status, next_attempt_at).await?;
+
+	Ok(status)
+}
+
+/// `POST` the payload, the `2xx` response status, or the failure (with the response status, if any).
+async fn send(
+	http: &reqwest::Client,
+	subscription: &WebhookSubscription,
+	delivery: &WebhookDelivery,
+) -> Result<u16, (Option<u16>, String)> {
+	// -- Check the target (the resolved addresses are, by the `TargetResolver`).
+	let url = reqwest::Url::parse(&subscription.url).map_err(|ex| (None, ex.to_string()))?;
+	check_target(&url).map_err(|_| (None, "Target address not allowed (see `webhooks.allowed_private_targets`)".to_string()))?;
+
+	let timestamp = OffsetDateTime::now_utc().unix_timestamp();
+	let signature =
+		sign_webhook_payload(&subscription.secret, timestamp, &delivery.payload).map_err(|ex| (None, ex.to_string()))?;
+
+	let res = http
+		.post(url)
+		.header(header::CONTENT_TYPE, "application/json")
+		.header("X-Webhook-Id", &delivery.event_id)
+		.header("X-Webhook-Delivery", delivery.id.to_string())
+		.header("X-Webhook-Event", &delivery.event_type)
+		.header("X-Webhook-Timestamp", timestamp.to_string())
+		.header("X-Webhook-Signature", signature)
+		.body(delivery.payload.clone())
+		.send()
+		.await
+		.map_err(|ex| (None, ex.to_string()))?;
+
+	let status = res.status();
+	if status.is_success() {
+		return Ok(status.as_u16());
+	}
+
+	// (not the body, e.g., of an internal service)
+	Err((Some(status.as_u16()), format!("HTTP {status}")))
+}
+
+/// Wait before the retry following `attempt` (from 1): `base_sec` doubled on each retry, up to `max_sec`.
+pub fn retry_delay(attempt: u32, base_sec: u64, max_sec: u64) -> Duration {
+	let factor = 1u64.checked_shl(attempt.saturating_sub(1)).unwrap_or(u64::MAX);
+
+	Duration::from_secs(base_sec.saturating_mul(factor).min(max_sec))
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::models::webhook::tests::fx_subscription;
+	use crate::models::webhook::WebhookEventType;
+	use anyhow::Result;
+	use serde_json::json;
+	use serial_test::serial;
+
+	#[test]
+	fn test_retry_delay() -> Result<()> {
+		// -- Check
+		assert_eq!(retry_delay(1, 30, 3600), Duration::from_secs(30));
+		assert_eq!(retry_delay(2, 30, 3600), Duration::from_secs(60));
+		assert_eq!(retry_delay(4, 30, 3600), Duration::from_secs(240));
+		assert_eq!(retry_delay(8, 30, 3600), Duration::from_secs(3600));
+		// (no overflow)
+		assert_eq!(retry_delay(100, 30, 3600), Duration::from_secs(3600));
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_deliver_ok_retry_scheduled() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let webhooks_config = &config().webhooks;
+		let fx_id = fx_subscription(&app_state, &[WebhookEventType::PostCreated]).await?;
+		WebhookBmc::enqueue(&app_state, WebhookEventType::PostCreated, &json!({})).await?;
+		let delivery = WebhookDeliveryBmc::claim_due(&app_state, 10, 60).await?.remove(0);
+
+		// -- Exec
+		let status = deliver(&app_state, delivery).await?;
+
+		// -- Check
+		assert_eq!(status, WebhookDeliveryStatus::Pending);
+		let delivery = &WebhookDeliveryBmc::list_for_subscription(&ctx, &app_state, fx_id).await?[0];
+		assert_eq!(delivery.attempts, 1);
+		let delay = retry_delay(1, webhooks_config.backoff_base_sec, webhooks_config.backoff_max_sec);
+		let delay_sec = (delivery.next_attempt_at - OffsetDateTime::now_utc()).whole_seconds();
+		assert!((delay.as_secs() as i64 - 5..=delay.as_secs() as i64).contains(&delay_sec), "{delay_sec}");
+		let attempts = WebhookDeliveryBmc::list_attempts(&ctx, &app_state, delivery.id).await?;
+		assert_eq!(attempts.len(), 1);
+		assert_eq!(attempts[0].attempt, 1);
+		assert_eq!(attempts[0].response_status, None);
+		assert!(attempts[0].error.as_deref().is_some_and(|error| error.contains("not allowed")), "{attempts:?}");
+
+		// -- Clean
+		WebhookBmc::delete(&ctx, &app_state, fx_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_deliver_ok_failed_after_max_attempts() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let max_attempts = config().webhooks.max_attempts as i32;
+		let fx_id = fx_subscription(&app_state, &[WebhookEventType::PostCreated]).await?;
+		WebhookBmc::enqueue(&app_state, WebhookEventType::PostCreated, &json!({})).await?;
+		let delivery = WebhookDeliveryBmc::claim_due(&app_state, 10, 60).await?.remove(0);
+		// (the attempts before the last one, the retry due)
+		let fx_attempt = WebhookAttemptForCreate {
+			delivery_id: delivery.id,
+			attempt: max_attempts - 1,
+			response_status: Some(503),
+			error: Some("HTTP 503 Service Unavailable".to_string()),
+			duration_ms: 12,
+		};
+		let fx_now = OffsetDateTime::now_utc();
+		WebhookDeliveryBmc::record_attempt(&app_state, fx_attempt, WebhookDeliveryStatus::Pending, fx_now).await?;
+		let delivery = WebhookDeliveryBmc::claim_due(&app_state, 10, 60).await?.remove(0);
+
+		// -- Exec
+		let status = deliver(&app_state, delivery).await?;
+
+		// -- Check
+		assert_eq!(status, WebhookDeliveryStatus::Failed);
+		let delivery = &WebhookDeliveryBmc::list_for_subscription(&ctx, &app_state, fx_id).await?[0];
+		assert_eq!(delivery.status, "failed");
+		assert_eq!(delivery.attempts, max_attempts);
+		// (not taken again)
+		assert!(WebhookDeliveryBmc::claim_due(&app_state, 10, 0).await?.is_empty());
+
+		// -- Clean
+		WebhookBmc::delete(&ctx, &app_state, fx_id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
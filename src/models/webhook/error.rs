@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+pub type WebhookResult<T> = core::result::Result<T, WebhookError>;
+
+#[derive(Debug, Serialize)]
+pub enum WebhookError {
+	/// The event data could not be serialized into the payload
+	PayloadSerialize(String),
+	/// The HTTP client of the deliveries could not be built
+	HttpClient(String),
+	/// The subscription URL is to a private, loopback or link-local IP (see `target`)
+	TargetNotAllowed { url: String },
+	/// Not redelivered, being attempted or to be retried (`pending`, not due yet)
+	DeliveryPending { id: i64 },
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for WebhookError {
+	fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for WebhookError {}
+// endregion: --- Error Boilerplate
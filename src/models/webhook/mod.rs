@@ -0,0 +1,754 @@
+//! Outgoing webhooks, for the downstream systems (e.g., search index, static site builder)
+//!
+//! Design:
+//!
+//! - Subscriptions (admin only, see `web::handlers::webhook`) get the events of their `event_types`,
+//!   for all the content, POSTed as JSON to their `url`, signed with their `secret` (see `crypt::webhook_sig`).
+//! - `WebhookBmc::emit` queues an event in the db (`webhook_deliveries`, one delivery per subscription),
+//!   after the change is made (a failure to queue is only logged, the change being committed already).
+//! - The `dispatcher` delivers the due ones (from any instance), retried with an exponential backoff
+//!   until `webhooks.max_attempts`, each attempt logged (`webhook_delivery_attempts`).
+//! - The targets are not on the internal network, unless allowed (see `target`).
+//! - A delivery can be redelivered manually (once not pending), its attempts starting over.
+//!
+
+// region:    --- Modules
+mod error;
+pub mod dispatcher;
+pub mod target;
+
+pub use self::error::{WebhookError, WebhookResult};
+pub use self::dispatcher::WebhookDispatcher;
+
+use modql::field::{Fields, HasFields};
+use sea_query::{Expr, Iden, LockBehavior, LockType, Order, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::FromRow;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::config::config;
+use crate::ctx::Ctx;
+
+use super::base::{self, traced_sql, CommonIden, DbBmc};
+use super::{AppState, ModelError, ModelResult};
+// endregion: --- Modules
+
+/// Deliveries listed at most (latest first)
+const DELIVERIES_LIST_LIMIT: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, strum_macros::AsRefStr)]
+/// The events subscriptions get (`type` of the payload)
+pub enum WebhookEventType {
+	#[serde(rename = "post.created")]
+	#[strum(serialize = "post.created")]
+	PostCreated,
+	#[serde(rename = "post.updated")]
+	#[strum(serialize = "post.updated")]
+	PostUpdated,
+	#[serde(rename = "post.deleted")]
+	#[strum(serialize = "post.deleted")]
+	PostDeleted,
+	#[serde(rename = "edit.created")]
+	#[strum(serialize = "edit.created")]
+	EditCreated,
+	#[serde(rename = "edit.accepted")]
+	#[strum(serialize = "edit.accepted")]
+	EditAccepted,
+	#[serde(rename = "edit.rejected")]
+	#[strum(serialize = "edit.rejected")]
+	EditRejected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, strum_macros::AsRefStr)]
+#[strum(serialize_all = "lowercase")]
+/// `webhook_deliveries.status`
+pub enum WebhookDeliveryStatus {
+	/// Not yet attempted, or to be retried at `next_attempt_at`
+	Pending,
+	Delivered,
+	/// Out of attempts (`webhooks.max_attempts`), or the subscription is inactive
+	Failed,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+/// Complete "Webhook subscription" model, as-is in the database
+pub struct WebhookSubscription {
+	pub id: i64,
+	pub owner_id: i64,
+	pub url: String,
+	/// Only sent back at creation
+	#[serde(skip_serializing)]
+	pub secret: String,
+	/// See `WebhookEventType`
+	pub event_types: Vec<String>,
+	pub active: bool,
+	#[serde_as(as = "Rfc3339")]
+	pub created_at: OffsetDateTime,
+	#[serde_as(as = "Rfc3339")]
+	pub updated_at: OffsetDateTime
+}
+
+#[derive(Debug, Fields)]
+/// Struct holding fields required to create a subscription in the database
+pub struct WebhookForCreate {
+	pub owner_id: i64,
+	pub url: String,
+	pub secret: String,
+	pub event_types: Vec<String>
+}
+
+#[derive(Debug, Default, Fields)]
+/// Struct holding the fields of a subscription that can be updated (the ones set)
+pub struct WebhookForUpdate {
+	pub url: Option<String>,
+	pub event_types: Option<Vec<String>>,
+	pub active: Option<bool>
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+/// Complete "Webhook delivery" model, an event to a subscription
+pub struct WebhookDelivery {
+	pub id: i64,
+	pub subscription_id: i64,
+	/// Same for all the deliveries of an event
+	pub event_id: String,
+	pub event_type: String,
+	/// The JSON body, as signed
+	pub payload: String,
+	/// See `WebhookDeliveryStatus`
+	pub status: String,
+	pub attempts: i32,
+	#[serde_as(as = "Rfc3339")]
+	pub next_attempt_at: OffsetDateTime,
+	#[serde_as(as = "Rfc3339")]
+	pub created_at: OffsetDateTime,
+	#[serde_as(as = "Rfc3339")]
+	pub updated_at: OffsetDateTime
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+/// An attempt of a delivery (the delivery log)
+pub struct WebhookDeliveryAttempt {
+	pub id: i64,
+	pub delivery_id: i64,
+	/// From 1
+	pub attempt: i32,
+	/// None when no response (e.g., connection refused, timeout)
+	pub response_status: Option<i32>,
+	pub error: Option<String>,
+	pub duration_ms: i32,
+	#[serde_as(as = "Rfc3339")]
+	pub created_at: OffsetDateTime
+}
+
+#[derive(Debug, Fields)]
+/// Struct holding fields required to log an attempt (see `WebhookDeliveryBmc::record_attempt`)
+pub struct WebhookAttemptForCreate {
+	pub delivery_id: i64,
+	pub attempt: i32,
+	pub response_status: Option<i32>,
+	pub error: Option<String>,
+	pub duration_ms: i32
+}
+
+#[derive(Debug, Fields)]
+/// Struct holding fields updated after an attempt, or to redeliver
+struct WebhookDeliveryForUpdate {
+	status: String,
+	attempts: i32,
+	next_attempt_at: OffsetDateTime
+}
+
+#[derive(Serialize)]
+/// The body sent
+struct WebhookPayload<'a, T: Serialize> {
+	id: &'a str,
+	#[serde(rename = "type")]
+	event_type: &'a str,
+	created_at: String,
+	data: &'a T,
+}
+
+#[derive(Iden)]
+enum WebhookDeliveryIden {
+	#[iden = "webhook_deliveries"]
+	Table,
+	Id,
+	SubscriptionId,
+	EventId,
+	EventType,
+	Payload,
+	Status,
+	NextAttemptAt
+}
+
+#[derive(Iden)]
+enum WebhookDeliveryAttemptIden {
+	DeliveryId,
+	Attempt
+}
+
+pub struct WebhookBmc;
+
+impl DbBmc for WebhookBmc {
+	const TABLE: &'static str = "webhook_subscriptions";
+}
+
+pub struct WebhookDeliveryBmc;
+
+impl DbBmc for WebhookDeliveryBmc {
+	const TABLE: &'static str = "webhook_deliveries";
+}
+
+struct WebhookDeliveryAttemptBmc;
+
+impl DbBmc for WebhookDeliveryAttemptBmc {
+	const TABLE: &'static str = "webhook_delivery_attempts";
+}
+
+// region:    --- Subscriptions
+impl WebhookBmc {
+	pub async fn create(
+		ctx: &Ctx,
+		app_state: &AppState,
+		data: WebhookForCreate
+	) -> ModelResult<i64> {
+		check_target_url(&data.url)?;
+
+		base::create::<Self, _>(ctx, app_state, data).await
+	}
+
+	pub async fn get(
+		ctx: &Ctx,
+		app_state: &AppState,
+		id: i64
+	) -> ModelResult<WebhookSubscription> {
+		base::get::<Self, _>(ctx, app_state, id).await
+	}
+
+	pub async fn list(
+		_ctx: &Ctx,
+		app_state: &AppState
+	) -> ModelResult<Vec<WebhookSubscription>> {
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(WebhookSubscription::field_column_refs())
+			.order_by(CommonIden::Id, Order::Asc);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let subscriptions = traced_sql(
+			&sql,
+			sqlx::query_as_with::<_, WebhookSubscription, _>(&sql, values).fetch_all(app_state.db())
+		).await?;
+
+		Ok(subscriptions)
+	}
+
+	pub async fn update(
+		ctx: &Ctx,
+		app_state: &AppState,
+		id: i64,
+		data: WebhookForUpdate
+	) -> ModelResult<()> {
+		if let Some(url) = &data.url {
+			check_target_url(url)?;
+		}
+
+		base::update::<Self, _>(ctx, app_state, id, data).await
+	}
+
+	/// Delete a subscription, and its deliveries.
+	pub async fn delete(
+		ctx: &Ctx,
+		app_state: &AppState,
+		id: i64
+	) -> ModelResult<()> {
+		base::delete::<Self>(ctx, app_state, id).await
+	}
+
+	/// Queue an event for the active subscriptions to its type, then wake the dispatcher.
+	/// (A failure is only logged, the change being committed already, for the request not to be retried)
+	pub async fn emit(app_state: &AppState, event_type: WebhookEventType, data: &impl Serialize) {
+		if !config().webhooks.enabled {
+			return;
+		}
+
+		match Self::enqueue(app_state, event_type, data).await {
+			Ok(0) => {},
+			Ok(count) => {
+				debug!("{:<12} - {} queued for {count} subscriptions", "WEBHOOK", event_type.as_ref());
+				app_state.webhook_dispatcher.wake();
+			},
+			Err(err) => warn!("{:<12} - {} not queued - {err}", "WEBHOOK", event_type.as_ref()),
+		}
+	}
+
+	/// The deliveries created, one per subscription.
+	async fn enqueue(app_state: &AppState, event_type: WebhookEventType, data: &impl Serialize) -> ModelResult<usize> {
+		let subscription_ids: Vec<i64> = Self::list(&Ctx::root_ctx(), app_state)
+			.await?
+			.into_iter()
+			.filter(|subscription| subscription.active && subscription.event_types.iter().any(|t| t == event_type.as_ref()))
+			.map(|subscription| subscription.id)
+			.collect();
+		if subscription_ids.is_empty() {
+			return Ok(0);
+		}
+
+		let event_id = Uuid::new_v4().to_string();
+		let payload = WebhookPayload {
+			id: &event_id,
+			event_type: event_type.as_ref(),
+			created_at: OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default(),
+			data,
+		};
+		let payload = serde_json::to_string(&payload).map_err(|ex| WebhookError::PayloadSerialize(ex.to_string()))?;
+
+		// -- Build query
+		let mut query = Query::insert();
+		query.into_table(WebhookDeliveryIden::Table).columns([
+			WebhookDeliveryIden::SubscriptionId,
+			WebhookDeliveryIden::EventId,
+			WebhookDeliveryIden::EventType,
+			WebhookDeliveryIden::Payload,
+		]);
+		for subscription_id in &subscription_ids {
+			query.values([
+				(*subscription_id).into(),
+				event_id.clone().into(),
+				event_type.as_ref().into(),
+				payload.clone().into(),
+			])?;
+		}
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		traced_sql(&sql, sqlx::query_with(&sql, values).execute(app_state.db())).await?;
+
+		Ok(subscription_ids.len())
+	}
+}
+// endregion: --- Subscriptions
+
+/// See `target::check_target` (the URL syntax being validated with the payload).
+fn check_target_url(url: &str) -> WebhookResult<()> {
+	match reqwest::Url::parse(url) {
+		Ok(url) => target::check_target(&url),
+		Err(_) => Ok(()),
+	}
+}
+
+// region:    --- Deliveries
+impl WebhookDeliveryBmc {
+	/// A delivery of a subscription (`EntityNotFound` if of another one).
+	pub async fn get(
+		ctx: &Ctx,
+		app_state: &AppState,
+		subscription_id: i64,
+		id: i64
+	) -> ModelResult<WebhookDelivery> {
+		let delivery: WebhookDelivery = base::get::<Self, _>(ctx, app_state, id).await?;
+		if delivery.subscription_id != subscription_id {
+			return Err(ModelError::EntityNotFound { entity: Self::TABLE, id });
+		}
+
+		Ok(delivery)
+	}
+
+	/// The latest deliveries of a subscription.
+	pub async fn list_for_subscription(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		subscription_id: i64
+	) -> ModelResult<Vec<WebhookDelivery>> {
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(WebhookDelivery::field_column_refs())
+			.and_where(Expr::col(WebhookDeliveryIden::SubscriptionId).eq(subscription_id))
+			.order_by(WebhookDeliveryIden::Id, Order::Desc)
+			.limit(DELIVERIES_LIST_LIMIT);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let deliveries = traced_sql(
+			&sql,
+			sqlx::query_as_with::<_, WebhookDelivery, _>(&sql, values).fetch_all(app_state.db())
+		).await?;
+
+		Ok(deliveries)
+	}
+
+	/// The attempts of a delivery, in order.
+	pub async fn list_attempts(
+		_ctx: &Ctx,
+		app_state: &AppState,
+		delivery_id: i64
+	) -> ModelResult<Vec<WebhookDeliveryAttempt>> {
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(WebhookDeliveryAttemptBmc::table_ref())
+			.columns(WebhookDeliveryAttempt::field_column_refs())
+			.and_where(Expr::col(WebhookDeliveryAttemptIden::DeliveryId).eq(delivery_id))
+			.order_by(WebhookDeliveryAttemptIden::Attempt, Order::Asc);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let attempts = traced_sql(
+			&sql,
+			sqlx::query_as_with::<_, WebhookDeliveryAttempt, _>(&sql, values).fetch_all(app_state.db())
+		).await?;
+
+		Ok(attempts)
+	}
+
+	/// Queue a delivery again (delivered or failed), its attempts starting over, then wake the dispatcher.
+	/// (`DeliveryPending` while pending, its attempt in flight or its retry scheduled)
+	pub async fn redeliver(
+		ctx: &Ctx,
+		app_state: &AppState,
+		subscription_id: i64,
+		id: i64
+	) -> ModelResult<()> {
+		Self::get(ctx, app_state, subscription_id, id).await?;
+
+		// -- Build query (not while pending, e.g., leased by `claim_due`, its attempt in flight)
+		let data = WebhookDeliveryForUpdate {
+			status: WebhookDeliveryStatus::Pending.as_ref().to_string(),
+			attempts: 0,
+			next_attempt_at: OffsetDateTime::now_utc(),
+		};
+		let is_pending = Expr::col(WebhookDeliveryIden::Status)
+			.eq(WebhookDeliveryStatus::Pending.as_ref())
+			.and(Expr::col(WebhookDeliveryIden::NextAttemptAt).gt(Expr::current_timestamp()));
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(data.not_none_fields().for_sea_update())
+			.and_where(Expr::col(CommonIden::Id).eq(id))
+			.and_where(is_pending.not());
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = traced_sql(&sql, sqlx::query_with(&sql, values).execute(app_state.db()))
+			.await?
+			.rows_affected();
+		if count == 0 {
+			return Err(WebhookError::DeliveryPending { id }.into());
+		}
+
+		app_state.webhook_dispatcher.wake();
+
+		Ok(())
+	}
+
+	/// Take up to `limit` due deliveries, leased for `lease_sec` (not taken again meanwhile, by any instance,
+	/// then retried if the attempt was never recorded, e.g., on a crash).
+	pub async fn claim_due(app_state: &AppState, limit: u64, lease_sec: u64) -> ModelResult<Vec<WebhookDelivery>> {
+		// -- Build query
+		let mut due = Query::select();
+		due
+			.from(Self::table_ref())
+			.column(WebhookDeliveryIden::Id)
+			.and_where(Expr::col(WebhookDeliveryIden::Status).eq(WebhookDeliveryStatus::Pending.as_ref()))
+			.and_where(Expr::col(WebhookDeliveryIden::NextAttemptAt).lte(Expr::current_timestamp()))
+			.order_by(WebhookDeliveryIden::NextAttemptAt, Order::Asc)
+			.limit(limit)
+			.lock_with_behavior(LockType::Update, LockBehavior::SkipLocked);
+
+		let leased_until = OffsetDateTime::now_utc() + time::Duration::seconds(lease_sec as i64);
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.value(WebhookDeliveryIden::NextAttemptAt, leased_until)
+			.and_where(Expr::col(WebhookDeliveryIden::Id).in_subquery(due))
+			.returning(Query::returning().columns(WebhookDelivery::field_column_refs()));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let deliveries = traced_sql(
+			&sql,
+			sqlx::query_as_with::<_, WebhookDelivery, _>(&sql, values).fetch_all(app_state.db())
+		).await?;
+
+		Ok(deliveries)
+	}
+
+	/// Log an attempt, and set the delivery `status` (`next_attempt_at` for a retry).
+	pub async fn record_attempt(
+		app_state: &AppState,
+		attempt: WebhookAttemptForCreate,
+		status: WebhookDeliveryStatus,
+		next_attempt_at: OffsetDateTime
+	) -> ModelResult<()> {
+		let ctx = Ctx::root_ctx();
+		let delivery_id = attempt.delivery_id;
+		let data = WebhookDeliveryForUpdate {
+			status: status.as_ref().to_string(),
+			attempts: attempt.attempt,
+			next_attempt_at,
+		};
+
+		base::create::<WebhookDeliveryAttemptBmc, _>(&ctx, app_state, attempt).await?;
+		base::update::<Self, _>(&ctx, app_state, delivery_id, data).await
+	}
+}
+// endregion: --- Deliveries
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use serde_json::json;
+	use serial_test::serial;
+
+	const FX_LOOPBACK_URL: &str = "http://127.0.0.1:9/hooks";
+
+	/// A subscription of the seeded author, to `event_types`
+	/// (inserted as is, its target not allowed, so its deliveries fail without a request).
+	pub(super) async fn fx_subscription(app_state: &AppState, event_types: &[WebhookEventType]) -> ModelResult<i64> {
+		let data = WebhookForCreate {
+			owner_id: 1000,
+			url: FX_LOOPBACK_URL.to_string(),
+			secret: "fx-secret-0123456789".to_string(),
+			event_types: event_types.iter().map(|event_type| event_type.as_ref().to_string()).collect(),
+		};
+
+		base::create::<WebhookBmc, _>(&Ctx::root_ctx(), app_state, data).await
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_err_target_not_allowed() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+
+		// -- Exec & Check
+		for fx_url in [FX_LOOPBACK_URL, "http://169.254.169.254/latest/meta-data", "http://[fd00::1]/hooks"] {
+			let data = WebhookForCreate {
+				owner_id: 1000,
+				url: fx_url.to_string(),
+				secret: "fx-secret-0123456789".to_string(),
+				event_types: vec![WebhookEventType::PostCreated.as_ref().to_string()],
+			};
+			let res = WebhookBmc::create(&ctx, &app_state, data).await;
+			assert!(matches!(res, Err(ModelError::Webhook(WebhookError::TargetNotAllowed { .. }))), "{fx_url} {res:?}");
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_err_target_not_allowed() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_id = fx_subscription(&app_state, &[WebhookEventType::PostCreated]).await?;
+
+		// -- Exec
+		let data = WebhookForUpdate { url: Some("http://10.0.0.7/hooks".to_string()), ..Default::default() };
+		let res = WebhookBmc::update(&ctx, &app_state, fx_id, data).await;
+		let data = WebhookForUpdate { url: Some("https://search.example.com/hooks".to_string()), ..Default::default() };
+		WebhookBmc::update(&ctx, &app_state, fx_id, data).await?;
+
+		// -- Check
+		assert!(matches!(res, Err(ModelError::Webhook(WebhookError::TargetNotAllowed { .. }))), "{res:?}");
+		let subscription = WebhookBmc::get(&ctx, &app_state, fx_id).await?;
+		assert_eq!(subscription.url, "https://search.example.com/hooks");
+
+		// -- Clean
+		WebhookBmc::delete(&ctx, &app_state, fx_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_enqueue_ok_active_and_event_type_only() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_subscribed = fx_subscription(&app_state, &[WebhookEventType::PostCreated]).await?;
+		let fx_other_type = fx_subscription(&app_state, &[WebhookEventType::EditCreated]).await?;
+		let fx_inactive = fx_subscription(&app_state, &[WebhookEventType::PostCreated]).await?;
+		let data = WebhookForUpdate { active: Some(false), ..Default::default() };
+		WebhookBmc::update(&ctx, &app_state, fx_inactive, data).await?;
+
+		// -- Exec
+		let count = WebhookBmc::enqueue(&app_state, WebhookEventType::PostCreated, &json!({"id": 1})).await?;
+
+		// -- Check
+		assert_eq!(count, 1);
+		let deliveries = WebhookDeliveryBmc::list_for_subscription(&ctx, &app_state, fx_subscribed).await?;
+		assert_eq!(deliveries.len(), 1);
+		assert_eq!(deliveries[0].event_type, "post.created");
+		assert_eq!(deliveries[0].status, "pending");
+		assert_eq!(deliveries[0].attempts, 0);
+		let payload: serde_json::Value = serde_json::from_str(&deliveries[0].payload)?;
+		assert_eq!(payload["id"].as_str(), Some(deliveries[0].event_id.as_str()));
+		assert_eq!(payload["type"], "post.created");
+		assert_eq!(payload["data"], json!({"id": 1}));
+		for id in [fx_other_type, fx_inactive] {
+			assert!(WebhookDeliveryBmc::list_for_subscription(&ctx, &app_state, id).await?.is_empty());
+		}
+
+		// -- Clean
+		for id in [fx_subscribed, fx_other_type, fx_inactive] {
+			WebhookBmc::delete(&ctx, &app_state, id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_claim_due_ok_leased() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_id = fx_subscription(&app_state, &[WebhookEventType::PostCreated]).await?;
+		for _ in 0..3 {
+			WebhookBmc::enqueue(&app_state, WebhookEventType::PostCreated, &json!({})).await?;
+		}
+
+		// -- Exec
+		let claimed = WebhookDeliveryBmc::claim_due(&app_state, 2, 60).await?;
+		let claimed_rest = WebhookDeliveryBmc::claim_due(&app_state, 10, 60).await?;
+		let claimed_again = WebhookDeliveryBmc::claim_due(&app_state, 10, 60).await?;
+
+		// -- Check
+		assert_eq!(claimed.len(), 2);
+		assert_eq!(claimed_rest.len(), 1);
+		assert!(!claimed.iter().any(|delivery| delivery.id == claimed_rest[0].id));
+		// (leased, still pending)
+		assert!(claimed_again.is_empty());
+		let lease_sec = (claimed[0].next_attempt_at - OffsetDateTime::now_utc()).whole_seconds();
+		assert!((55..=60).contains(&lease_sec), "{lease_sec}");
+		assert_eq!(claimed[0].status, "pending");
+
+		// -- Clean
+		WebhookBmc::delete(&ctx, &app_state, fx_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_claim_due_ok_skip_locked() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_id = fx_subscription(&app_state, &[WebhookEventType::PostCreated]).await?;
+		for _ in 0..3 {
+			WebhookBmc::enqueue(&app_state, WebhookEventType::PostCreated, &json!({})).await?;
+		}
+		let fx_locked_id = WebhookDeliveryBmc::list_for_subscription(&ctx, &app_state, fx_id).await?[0].id;
+
+		// (being claimed by another instance)
+		let mut tx = app_state.db().begin().await?;
+		sqlx::query(r#"SELECT id FROM "webhook_deliveries" WHERE id = $1 FOR UPDATE"#)
+			.bind(fx_locked_id)
+			.execute(&mut *tx)
+			.await?;
+
+		// -- Exec (not waiting on the lock)
+		let claim = WebhookDeliveryBmc::claim_due(&app_state, 10, 60);
+		let claimed = tokio::time::timeout(std::time::Duration::from_secs(5), claim).await??;
+		tx.rollback().await?;
+		let claimed_after = WebhookDeliveryBmc::claim_due(&app_state, 10, 60).await?;
+
+		// -- Check
+		assert_eq!(claimed.len(), 2);
+		assert!(!claimed.iter().any(|delivery| delivery.id == fx_locked_id));
+		assert_eq!(claimed_after.len(), 1);
+		assert_eq!(claimed_after[0].id, fx_locked_id);
+
+		// -- Clean
+		WebhookBmc::delete(&ctx, &app_state, fx_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_redeliver_ok_attempts_reset() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_id = fx_subscription(&app_state, &[WebhookEventType::PostCreated]).await?;
+		WebhookBmc::enqueue(&app_state, WebhookEventType::PostCreated, &json!({})).await?;
+		let delivery = WebhookDeliveryBmc::claim_due(&app_state, 10, 60).await?.remove(0);
+		let fx_attempt = WebhookAttemptForCreate {
+			delivery_id: delivery.id,
+			attempt: 8,
+			response_status: Some(500),
+			error: Some("HTTP 500 Internal Server Error".to_string()),
+			duration_ms: 12,
+		};
+		WebhookDeliveryBmc::record_attempt(&app_state, fx_attempt, WebhookDeliveryStatus::Failed, delivery.next_attempt_at).await?;
+
+		// -- Exec
+		WebhookDeliveryBmc::redeliver(&ctx, &app_state, fx_id, delivery.id).await?;
+
+		// -- Check
+		let delivery = WebhookDeliveryBmc::get(&ctx, &app_state, fx_id, delivery.id).await?;
+		assert_eq!(delivery.status, "pending");
+		assert_eq!(delivery.attempts, 0);
+		assert!(delivery.next_attempt_at <= OffsetDateTime::now_utc());
+		// (the log kept)
+		assert_eq!(WebhookDeliveryBmc::list_attempts(&ctx, &app_state, delivery.id).await?.len(), 1);
+		let claimed = WebhookDeliveryBmc::claim_due(&app_state, 10, 60).await?;
+		assert_eq!(claimed.len(), 1);
+		assert_eq!(claimed[0].id, delivery.id);
+
+		// -- Clean
+		WebhookBmc::delete(&ctx, &app_state, fx_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_redeliver_err_pending() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_id = fx_subscription(&app_state, &[WebhookEventType::PostCreated]).await?;
+		WebhookBmc::enqueue(&app_state, WebhookEventType::PostCreated, &json!({})).await?;
+		let delivery = WebhookDeliveryBmc::claim_due(&app_state, 10, 60).await?.remove(0);
+
+		// -- Exec
+		let res = WebhookDeliveryBmc::redeliver(&ctx, &app_state, fx_id, delivery.id).await;
+
+		// -- Check
+		assert!(
+			matches!(res, Err(ModelError::Webhook(WebhookError::DeliveryPending { id })) if id == delivery.id),
+			"{res:?}"
+		);
+		// (the lease kept)
+		let delivery_after = WebhookDeliveryBmc::get(&ctx, &app_state, fx_id, delivery.id).await?;
+		assert_eq!(delivery_after.next_attempt_at, delivery.next_attempt_at);
+
+		// -- Clean
+		WebhookBmc::delete(&ctx, &app_state, fx_id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,135 @@
+//! Webhook targets, not to the internal network (SSRF)
+//!
+//! - Private, loopback, link-local (and unspecified, shared, broadcast) addresses are refused,
+//!   unless in `webhooks.allowed_private_targets` (e.g., a receiver on the same network).
+//! - Checked on the URL of the subscriptions (IP hosts, `check_target`), and on the resolved addresses at delivery,
+//!   the connection being made to the checked addresses only (`TargetResolver`).
+//!
+
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+
+use crate::config::{config, IpNetwork};
+
+use super::{WebhookError, WebhookResult};
+
+/// Whether a delivery can be made to `ip`, with the configured `webhooks.allowed_private_targets`.
+fn is_allowed_target(ip: IpAddr) -> bool {
+	is_allowed_target_in(ip, &config().webhooks.allowed_private_targets)
+}
+
+/// `TargetNotAllowed` if the URL host is an IP not allowed (the names being checked at delivery).
+pub fn check_target(url: &reqwest::Url) -> WebhookResult<()> {
+	match host_ip(url) {
+		Some(ip) if !is_allowed_target(ip) => Err(WebhookError::TargetNotAllowed { url: url.to_string() }),
+		_ => Ok(()),
+	}
+}
+
+/// The IP of an URL host, when not a name (e.g., `http://127.0.0.1:8080`, `http://[::1]`).
+fn host_ip(url: &reqwest::Url) -> Option<IpAddr> {
+	url.host_str()?.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+fn is_allowed_target_in(ip: IpAddr, allowed_private_targets: &[IpNetwork]) -> bool {
+	!is_internal(ip) || allowed_private_targets.iter().any(|network| network.contains(ip))
+}
+
+fn is_internal(ip: IpAddr) -> bool {
+	// (e.g., `::ffff:127.0.0.1`)
+	match ip.to_canonical() {
+		IpAddr::V4(ip) => {
+			let [a, b, ..] = ip.octets();
+			ip.is_unspecified()
+				|| ip.is_loopback()
+				|| ip.is_private()
+				|| ip.is_link_local()
+				|| ip.is_broadcast()
+				// "this network" 0.0.0.0/8, shared (CGNAT) 100.64.0.0/10
+				|| a == 0
+				|| (a == 100 && (64..128).contains(&b))
+		}
+		IpAddr::V6(ip) => ip.is_unspecified() || ip.is_loopback() || ip.is_unique_local() || ip.is_unicast_link_local(),
+	}
+}
+
+/// DNS resolver of the delivery client, the addresses not allowed (see `is_allowed_target`) filtered out,
+/// a host with none of them failing the delivery.
+pub struct TargetResolver;
+
+impl Resolve for TargetResolver {
+	fn resolve(&self, name: Name) -> Resolving {
+		Box::pin(async move {
+			let host = name.as_str().to_string();
+			let addrs: Vec<SocketAddr> =
+				tokio::task::spawn_blocking(move || (host.as_str(), 0).to_socket_addrs().map(Iterator::collect)).await??;
+
+			let allowed: Vec<SocketAddr> = addrs.into_iter().filter(|addr| is_allowed_target(addr.ip())).collect();
+			if allowed.is_empty() {
+				return Err(format!("`{}` resolves to no allowed address (see `webhooks.allowed_private_targets`)", name.as_str()).into());
+			}
+
+			Ok(Box::new(allowed.into_iter()) as Addrs)
+		})
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	fn fx_ip(ip: &str) -> IpAddr {
+		ip.parse().unwrap()
+	}
+
+	#[test]
+	fn test_is_allowed_target_internal_err() -> Result<()> {
+		// -- Check
+		for ip in [
+			"127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "0.0.0.0", "100.64.0.1",
+			"255.255.255.255", "::1", "::", "fd00::1", "fe80::1", "::ffff:127.0.0.1",
+		] {
+			assert!(!is_allowed_target_in(fx_ip(ip), &[]), "{ip}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_is_allowed_target_public_ok() -> Result<()> {
+		// -- Check
+		for ip in ["93.184.215.14", "8.8.8.8", "172.32.0.1", "100.128.0.1", "2606:4700::1111"] {
+			assert!(is_allowed_target_in(fx_ip(ip), &[]), "{ip}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_host_ip() -> Result<()> {
+		// -- Check
+		let fx_host_ip = |url: &str| host_ip(&reqwest::Url::parse(url).unwrap());
+		assert_eq!(fx_host_ip("http://127.0.0.1:8080/hooks"), Some(fx_ip("127.0.0.1")));
+		assert_eq!(fx_host_ip("http://[::1]:8080"), Some(fx_ip("::1")));
+		assert_eq!(fx_host_ip("https://search.example.com"), None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_is_allowed_target_allowlist_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_allowed = [IpNetwork::try_from("10.0.0.0/8".to_string()).unwrap()];
+
+		// -- Check
+		assert!(is_allowed_target_in(fx_ip("10.1.2.3"), &fx_allowed));
+		assert!(!is_allowed_target_in(fx_ip("192.168.1.1"), &fx_allowed));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
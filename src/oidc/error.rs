@@ -0,0 +1,64 @@
+use serde::Serialize;
+
+use crate::crypt;
+
+pub type OidcResult<T> = core::result::Result<T, OidcError>;
+
+#[derive(Debug, Serialize)]
+pub enum OidcError {
+	// -- Config
+	ProviderNotConfigured(String),
+
+	// -- Provider
+	HttpFail(String),
+	DiscoveryIssuerMismatch { expected: String, found: String },
+	DiscoveryInvalidEndpoint(String),
+	TokenExchangeFail { status: u16 },
+
+	// -- Flow (state, nonce & PKCE verifier kept client side)
+	FlowInvalidFormat,
+	FlowSignatureNotMatching,
+	FlowExpired,
+
+	// -- Id token
+	IdTokenInvalid(String),
+	IdTokenUnsupportedAlg(String),
+	IdTokenNonceNotMatching,
+	JwkNotFound,
+
+	// -- Modules
+	Crypt(crypt::CryptError),
+}
+
+// region:    --- Froms
+impl From<crypt::CryptError> for OidcError {
+	fn from(val: crypt::CryptError) -> Self {
+		Self::Crypt(val)
+	}
+}
+
+impl From<reqwest::Error> for OidcError {
+	fn from(val: reqwest::Error) -> Self {
+		Self::HttpFail(val.to_string())
+	}
+}
+
+impl From<jsonwebtoken::errors::Error> for OidcError {
+	fn from(val: jsonwebtoken::errors::Error) -> Self {
+		Self::IdTokenInvalid(val.to_string())
+	}
+}
+// endregion: --- Froms
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for OidcError {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for OidcError {}
+// endregion: --- Error Boilerplate
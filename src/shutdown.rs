@@ -0,0 +1,144 @@
+//! Graceful shutdown
+//!
+//! On SIGTERM/SIGINT (see `cli::serve`):
+//!
+//! 1. Draining starts, the readiness probe reports `draining` (503).
+//! 2. After `server.shutdown_delay_sec` (for the load balancer to see it), new connections are refused,
+//!    and the in-flight requests have `server.drain_timeout_sec` to finish (dropped after).
+//! 3. The background tasks (spawned with `Shutdown::spawn`) are cancelled and awaited (e.g., log sinks flushed).
+//! 4. The db pool is closed.
+//!
+
+use std::future::Future;
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{info, warn};
+
+#[derive(Clone, Default)]
+pub struct Shutdown {
+	/// Cancelled when draining starts
+	drain: CancellationToken,
+	/// Cancelled once the server is done, for the background tasks
+	stop: CancellationToken,
+	tasks: TaskTracker,
+}
+
+impl Shutdown {
+	pub fn start_draining(&self) {
+		self.drain.cancel();
+	}
+
+	pub fn is_draining(&self) -> bool {
+		self.drain.is_cancelled()
+	}
+
+	/// Resolves when draining starts.
+	pub async fn draining(&self) {
+		self.drain.cancelled().await
+	}
+
+	/// Start draining on SIGTERM or SIGINT (must be called within a tokio runtime).
+	pub fn listen_signals(&self) {
+		let shutdown = self.clone();
+		tokio::spawn(async move {
+			signal().await;
+			info!("{:<12} - draining", "SHUTDOWN");
+			shutdown.start_draining();
+		});
+	}
+
+	/// Spawn a background task, given a token cancelled at shutdown (the task should then finish its work and end).
+	pub fn spawn<F>(&self, task: impl FnOnce(CancellationToken) -> F)
+	where
+		F: Future<Output = ()> + Send + 'static,
+	{
+		self.tasks.spawn(task(self.stop.clone()));
+	}
+
+	/// Cancel the background tasks, and wait for them up to `timeout`, `false` if some did not end.
+	pub async fn stop_tasks(&self, timeout: Duration) -> bool {
+		self.stop.cancel();
+		self.tasks.close();
+
+		let stopped = tokio::time::timeout(timeout, self.tasks.wait()).await.is_ok();
+		if !stopped {
+			warn!("{:<12} - {} background task(s) not stopped after {timeout:?}", "SHUTDOWN", self.tasks.len());
+		}
+
+		stopped
+	}
+}
+
+async fn signal() {
+	let ctrl_c = async {
+		tokio::signal::ctrl_c().await.expect("Could not listen to SIGINT");
+	};
+
+	#[cfg(unix)]
+	let terminate = async {
+		tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+			.expect("Could not listen to SIGTERM")
+			.recv()
+			.await;
+	};
+
+	#[cfg(not(unix))]
+	let terminate = std::future::pending::<()>();
+
+	tokio::select! {
+		_ = ctrl_c => {},
+		_ = terminate => {},
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::sync::Arc;
+
+	#[tokio::test]
+	async fn test_shutdown_stop_tasks_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let shutdown = Shutdown::default();
+		let fx_done = Arc::new(AtomicBool::new(false));
+		let done = fx_done.clone();
+		shutdown.spawn(|stop| async move {
+			stop.cancelled().await;
+			tokio::time::sleep(Duration::from_millis(20)).await; // e.g., flushing
+			done.store(true, Ordering::SeqCst);
+		});
+
+		// -- Exec
+		assert!(!shutdown.is_draining());
+		shutdown.start_draining();
+		let stopped = shutdown.stop_tasks(Duration::from_secs(1)).await;
+
+		// -- Check
+		assert!(shutdown.is_draining());
+		assert!(stopped);
+		assert!(fx_done.load(Ordering::SeqCst));
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_shutdown_stop_tasks_timeout() -> Result<()> {
+		// -- Setup & Fixtures
+		let shutdown = Shutdown::default();
+		shutdown.spawn(|_stop| std::future::pending());
+
+		// -- Exec
+		let stopped = shutdown.stop_tasks(Duration::from_millis(20)).await;
+
+		// -- Check
+		assert!(!stopped);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
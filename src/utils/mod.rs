@@ -16,8 +16,8 @@ use crate::web::ServerError;
 use crate::log::log_request;
 
 // pub mod auth;
-// pub mod cache;
 pub mod error;
+pub mod validation;
 
 pub use self::error::{UtilError, UtilResult};
 use time::format_description::well_known::Rfc3339;
@@ -0,0 +1,90 @@
+//! Custom validation rules, for use on payload structs with `#[validate(custom = "...")]`
+//! (the built-in rules, e.g., `length`, `email`, `range`, come with the `validator` crate).
+
+use std::borrow::Cow;
+
+use validator::ValidationError;
+
+pub const PWD_MIN_LEN: usize = 8;
+pub const PWD_MAX_LEN: usize = 128;
+
+/// Reject empty or whitespace only strings.
+pub fn validate_not_blank(value: &str) -> Result<(), ValidationError> {
+	if value.trim().is_empty() {
+		return Err(new_error("blank", "must not be blank"));
+	}
+
+	Ok(())
+}
+
+/// Password strength: `PWD_MIN_LEN` to `PWD_MAX_LEN` characters, with at least a letter and a digit.
+pub fn validate_pwd_strength(pwd: &str) -> Result<(), ValidationError> {
+	let len = pwd.chars().count();
+
+	if !(PWD_MIN_LEN..=PWD_MAX_LEN).contains(&len) {
+		let message = format!("must be between {PWD_MIN_LEN} and {PWD_MAX_LEN} characters");
+		return Err(new_error("pwd_length", message));
+	}
+
+	if !pwd.chars().any(char::is_alphabetic) || !pwd.chars().any(|c| c.is_ascii_digit()) {
+		return Err(new_error("pwd_weak", "must contain at least a letter and a digit"));
+	}
+
+	Ok(())
+}
+
+/// An absolute `http` or `https` URL (e.g., of a webhook).
+pub fn validate_http_url(value: &str) -> Result<(), ValidationError> {
+	let is_http_url = reqwest::Url::parse(value)
+		.is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host());
+	if !is_http_url {
+		return Err(new_error("http_url", "must be an http or https URL"));
+	}
+
+	Ok(())
+}
+
+fn new_error(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+	let mut error = ValidationError::new(code);
+	error.message = Some(message.into());
+
+	error
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[test]
+	fn test_validate_not_blank() -> Result<()> {
+		assert!(validate_not_blank("title").is_ok());
+		assert!(validate_not_blank("").is_err());
+		assert!(validate_not_blank(" \t\n").is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_validate_pwd_strength() -> Result<()> {
+		assert!(validate_pwd_strength("welcome123").is_ok());
+		assert_eq!(validate_pwd_strength("abc123").unwrap_err().code, "pwd_length");
+		assert_eq!(validate_pwd_strength(&"a1".repeat(65)).unwrap_err().code, "pwd_length");
+		assert_eq!(validate_pwd_strength("password").unwrap_err().code, "pwd_weak");
+		assert_eq!(validate_pwd_strength("12345678").unwrap_err().code, "pwd_weak");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_validate_http_url() -> Result<()> {
+		assert!(validate_http_url("https://search.example.com/hooks/cms").is_ok());
+		assert!(validate_http_url("http://93.184.215.14:8080").is_ok());
+		assert!(validate_http_url("ftp://example.com").is_err());
+		assert!(validate_http_url("example.com/hooks").is_err());
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
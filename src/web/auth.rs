@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
 use crate::models::author::AuthorForCreate;
+use crate::utils::validation::{validate_not_blank, validate_pwd_strength};
 
 #[derive(Debug, Deserialize)]
 pub struct LoginPayload {
@@ -8,10 +10,13 @@ pub struct LoginPayload {
 	pub password: String
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct SignupPayload {
+	#[validate(length(max = 128), custom = "validate_not_blank")]
 	pub name: String,
+	#[validate(email, length(max = 128))]
 	pub email: String,
+	#[validate(custom = "validate_pwd_strength")]
 	pub password: String
 }
 
@@ -25,6 +30,38 @@ impl From<SignupPayload> for AuthorForCreate {
 	}
 }
 
+#[derive(Debug, Deserialize, Validate)]
+/// Second step of a login, when two-factor is enabled (either `code` or `recovery_code`)
+pub struct LoginTwoFactorPayload {
+	#[validate(custom = "validate_not_blank")]
+	pub challenge_token: String,
+	#[validate(length(max = 32))]
+	pub code: Option<String>,
+	#[validate(length(max = 32))]
+	pub recovery_code: Option<String>
+}
+
+#[derive(Debug, Serialize)]
+/// Sent back by login when a second factor is required
+pub struct LoginChallenge {
+	pub two_factor_required: bool,
+	pub challenge_token: String
+}
+
+#[derive(Debug, Deserialize, Validate)]
+/// A TOTP code from an authenticator app (or a recovery code, where accepted)
+pub struct TwoFactorCodePayload {
+	#[validate(length(min = 1, max = 32))]
+	pub code: String
+}
+
+#[derive(Debug, Serialize)]
+/// Sent back on two-factor enrollment, to be set up in an authenticator app
+pub struct TwoFactorEnrollment {
+	pub secret: String,
+	pub otpauth_uri: String
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Claims { // For JWT creation
 	pub email: String,
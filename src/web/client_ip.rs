@@ -0,0 +1,150 @@
+//! Client IP of the requests, for the rate limits, the login guard and the request log
+//!
+//! - The peer address, unless it is one of `server.trusted_proxies`.
+//! - Behind trusted proxies, the `server.forwarded_header` (e.g., `X-Forwarded-For: client, proxy1, proxy2`)
+//!   is read from the right, skipping the trusted proxies: the first address not trusted is the client
+//!   (the entries on its left are set by the client, so never trusted).
+//! - An entry that is not an IP stops the walk, the last trusted hop being the client then.
+//!
+
+use std::net::{IpAddr, SocketAddr};
+
+use async_trait::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use tracing::debug;
+
+use crate::config::{config, IpNetwork};
+use crate::web::{ServerError, ServerResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Client IP extractor (see module doc), requires the server to be served with the connect info
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+	type Rejection = ServerError;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> ServerResult<Self> {
+		debug!("{:<12} - ClientIp", "EXTRACTOR");
+
+		let ConnectInfo(peer) = parts
+			.extensions
+			.get::<ConnectInfo<SocketAddr>>()
+			.copied()
+			.ok_or(ServerError::ClientAddrNotInReqExt)?;
+
+		Ok(ClientIp(client_ip(peer.ip(), &parts.headers)))
+	}
+}
+
+/// The client IP of a request from `peer`, with the `server` config (see module doc).
+pub fn client_ip(peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+	let server_config = &config().server;
+
+	resolve_client_ip(peer, headers, &server_config.trusted_proxies, &server_config.forwarded_header)
+}
+
+fn resolve_client_ip(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpNetwork], forwarded_header: &str) -> IpAddr {
+	let is_trusted = |ip: IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+
+	let mut client = peer;
+	if !is_trusted(client) {
+		return client;
+	}
+
+	// (all the header lines, in order, as if joined with commas)
+	let entries: Vec<&str> = headers
+		.get_all(forwarded_header)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.flat_map(|value| value.split(','))
+		.collect();
+
+	for entry in entries.into_iter().rev() {
+		let Some(ip) = parse_entry(entry) else {
+			break;
+		};
+		client = ip;
+		if !is_trusted(ip) {
+			break;
+		}
+	}
+
+	client
+}
+
+/// `1.2.3.4`, `1.2.3.4:5678`, `::1` or `[::1]:5678`
+fn parse_entry(entry: &str) -> Option<IpAddr> {
+	let entry = entry.trim();
+
+	entry
+		.parse::<IpAddr>()
+		.ok()
+		.or_else(|| entry.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use axum::http::HeaderValue;
+
+	const FX_HEADER: &str = "x-forwarded-for";
+
+	fn fx_proxies() -> Vec<IpNetwork> {
+		vec![IpNetwork::try_from("10.0.0.0/8".to_string()).unwrap()]
+	}
+
+	fn fx_headers(values: &[&str]) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		for value in values {
+			headers.append(FX_HEADER, HeaderValue::from_str(value).unwrap());
+		}
+
+		headers
+	}
+
+	fn ip(value: &str) -> IpAddr {
+		value.parse().unwrap()
+	}
+
+	#[test]
+	fn test_client_ip_untrusted_peer() -> Result<()> {
+		// -- Exec
+		let client = resolve_client_ip(ip("203.0.113.9"), &fx_headers(&["1.1.1.1"]), &fx_proxies(), FX_HEADER);
+
+		// -- Check
+		assert_eq!(client, ip("203.0.113.9"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_client_ip_behind_proxies() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_peer = ip("10.0.0.2");
+
+		// -- Exec & Check
+		// (the spoofed entry on the left is never reached)
+		let headers = fx_headers(&["6.6.6.6, 198.51.100.7", "10.0.0.1"]);
+		assert_eq!(resolve_client_ip(fx_peer, &headers, &fx_proxies(), FX_HEADER), ip("198.51.100.7"));
+
+		let headers = fx_headers(&["198.51.100.7:4711"]);
+		assert_eq!(resolve_client_ip(fx_peer, &headers, &fx_proxies(), FX_HEADER), ip("198.51.100.7"));
+
+		// (no header, or only trusted hops)
+		assert_eq!(resolve_client_ip(fx_peer, &HeaderMap::new(), &fx_proxies(), FX_HEADER), fx_peer);
+		let headers = fx_headers(&["10.0.0.9"]);
+		assert_eq!(resolve_client_ip(fx_peer, &headers, &fx_proxies(), FX_HEADER), ip("10.0.0.9"));
+
+		// (not an IP, the last trusted hop)
+		let headers = fx_headers(&["198.51.100.7, unknown, 10.0.0.1"]);
+		assert_eq!(resolve_client_ip(fx_peer, &headers, &fx_proxies(), FX_HEADER), ip("10.0.0.1"));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
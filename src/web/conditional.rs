@@ -0,0 +1,259 @@
+//! HTTP conditional requests (RFC 9110, section 13)
+//!
+//! Design:
+//!
+//! - The validators of an entity (`Versioned`) derive from its `updated_at`, set by the db on each update:
+//!   a strong, opaque `ETag`, and `Last-Modified` (second precision).
+//! - GET handlers answer `304 Not Modified` on a matching `If-None-Match`
+//!   (or, when not given, on an `If-Modified-Since` not before `Last-Modified`).
+//! - Updates (`PATCH` of posts and edits) check `If-Match` against the current version, `412` on mismatch
+//!   (`428` when missing and `server.require_if_match`). The update is then made only if the row is still
+//!   at that version (`updated_at` in the `WHERE`), so a concurrent update in between also gets `412`.
+//!
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use data_encoding::HEXLOWER;
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+use crate::models::author::Author;
+use crate::models::edit::Edit;
+use crate::models::media::{Media, MediaRendition};
+use crate::models::post::Post;
+
+use super::{ServerError, ServerResult};
+
+/// An entity with a version, its `updated_at`.
+pub trait Versioned {
+	fn id(&self) -> i64;
+
+	fn updated_at(&self) -> OffsetDateTime;
+}
+
+impl Versioned for Post {
+	fn id(&self) -> i64 {
+		self.id
+	}
+
+	fn updated_at(&self) -> OffsetDateTime {
+		self.updated_at
+	}
+}
+
+impl Versioned for Edit {
+	fn id(&self) -> i64 {
+		self.id
+	}
+
+	fn updated_at(&self) -> OffsetDateTime {
+		self.updated_at
+	}
+}
+
+impl Versioned for Author {
+	fn id(&self) -> i64 {
+		self.id
+	}
+
+	fn updated_at(&self) -> OffsetDateTime {
+		self.updated_at
+	}
+}
+
+/// (A media never changes, its only version being its creation)
+impl Versioned for Media {
+	fn id(&self) -> i64 {
+		self.id
+	}
+
+	fn updated_at(&self) -> OffsetDateTime {
+		self.created_at
+	}
+}
+
+/// (Regenerated renditions are created again)
+impl Versioned for MediaRendition {
+	fn id(&self) -> i64 {
+		self.media_id
+	}
+
+	fn updated_at(&self) -> OffsetDateTime {
+		self.created_at
+	}
+}
+
+/// The `ETag` and `Last-Modified` of an entity version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validators {
+	/// Quoted (e.g., `"3f2a..."`)
+	etag: String,
+	/// Truncated to the second, as sent
+	last_modified: SystemTime,
+}
+
+impl Validators {
+	pub fn of(entity: &impl Versioned) -> Self {
+		let updated_at = entity.updated_at();
+
+		Self::from_version(&format!("{}:{}", entity.id(), updated_at.unix_timestamp_nanos()), updated_at)
+	}
+
+	/// Of a composite resource (e.g., a feed), `version` changing with any of its parts.
+	pub fn from_version(version: &str, updated_at: OffsetDateTime) -> Self {
+		let hash = HEXLOWER.encode(&Sha256::digest(version.as_bytes()));
+		let etag = format!("\"{}\"", &hash[..16]);
+
+		let last_modified = UNIX_EPOCH + Duration::from_secs(updated_at.unix_timestamp().max(0) as u64);
+
+		Self { etag, last_modified }
+	}
+
+	pub fn etag(&self) -> &str {
+		&self.etag
+	}
+
+	/// Whether a GET can be answered with `304 Not Modified`.
+	pub fn is_not_modified(&self, headers: &HeaderMap) -> bool {
+		// (`If-Modified-Since` is ignored when `If-None-Match` is given)
+		if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+			return etag_matches(if_none_match, &self.etag, Comparison::Weak);
+		}
+
+		headers
+			.get(header::IF_MODIFIED_SINCE)
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| httpdate::parse_http_date(value).ok())
+			.is_some_and(|since| self.last_modified <= since)
+	}
+
+	/// Check `If-Match` before an update, `true` when given (the update must then be conditional).
+	pub fn check_if_match(&self, headers: &HeaderMap, required: bool) -> ServerResult<bool> {
+		match headers.get(header::IF_MATCH) {
+			Some(if_match) if etag_matches(if_match, &self.etag, Comparison::Strong) => Ok(true),
+			Some(_) => Err(ServerError::PreconditionFailed),
+			None if required => Err(ServerError::PreconditionRequired),
+			None => Ok(false),
+		}
+	}
+
+	/// The `ETag` and `Last-Modified` response headers.
+	pub fn headers(&self) -> [(HeaderName, String); 2] {
+		[
+			(header::ETAG, self.etag.clone()),
+			(header::LAST_MODIFIED, httpdate::fmt_http_date(self.last_modified)),
+		]
+	}
+
+	pub fn not_modified(&self) -> Response {
+		(StatusCode::NOT_MODIFIED, self.headers()).into_response()
+	}
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Comparison {
+	/// `If-Match`, weak tags (`W/"..."`) never match
+	Strong,
+	/// `If-None-Match`, weak tags match their strong counterpart
+	Weak,
+}
+
+/// Whether the `If-Match`/`If-None-Match` value (`*`, or a list of tags) matches `etag`.
+fn etag_matches(value: &HeaderValue, etag: &str, comparison: Comparison) -> bool {
+	let Ok(value) = value.to_str() else {
+		return false;
+	};
+	if value.trim() == "*" {
+		return true;
+	}
+
+	value.split(',').map(str::trim).any(|tag| match tag.strip_prefix("W/") {
+		Some(weak_tag) => comparison == Comparison::Weak && weak_tag == etag,
+		None => tag == etag,
+	})
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use time::format_description::well_known::Rfc3339;
+
+	fn fx_post(updated_at: &str) -> Post {
+		let updated_at = OffsetDateTime::parse(updated_at, &Rfc3339).unwrap();
+
+		Post {
+			id: 1000,
+			title: "fx title".to_string(),
+			content: "fx content".to_string(),
+			author_id: 1000,
+			created_at: updated_at,
+			updated_at,
+		}
+	}
+
+	fn fx_headers(headers: &[(HeaderName, &str)]) -> HeaderMap {
+		headers
+			.iter()
+			.map(|(name, value)| (name.clone(), HeaderValue::from_str(value).unwrap()))
+			.collect()
+	}
+
+	#[test]
+	fn test_validators_not_modified() -> Result<()> {
+		// -- Setup & Fixtures
+		let validators = Validators::of(&fx_post("2026-10-19T10:00:00.250Z"));
+		let etag = validators.etag().to_string();
+		let weak_etag = format!("W/{etag}");
+
+		// -- Exec & Check
+		assert!(validators.is_not_modified(&fx_headers(&[(header::IF_NONE_MATCH, &etag)])));
+		assert!(validators.is_not_modified(&fx_headers(&[(header::IF_NONE_MATCH, &format!("\"other\", {weak_etag}"))])));
+		assert!(!validators.is_not_modified(&fx_headers(&[(header::IF_NONE_MATCH, "\"other\"")])));
+		assert!(!validators.is_not_modified(&HeaderMap::new()));
+
+		// (same second as `updated_at`, then before)
+		let fx_since = "Mon, 19 Oct 2026 10:00:00 GMT";
+		assert!(validators.is_not_modified(&fx_headers(&[(header::IF_MODIFIED_SINCE, fx_since)])));
+		let fx_since = "Mon, 19 Oct 2026 09:59:59 GMT";
+		assert!(!validators.is_not_modified(&fx_headers(&[(header::IF_MODIFIED_SINCE, fx_since)])));
+		// (`If-None-Match` first)
+		let fx_since = "Mon, 19 Oct 2026 10:00:00 GMT";
+		let headers = fx_headers(&[(header::IF_NONE_MATCH, "\"other\""), (header::IF_MODIFIED_SINCE, fx_since)]);
+		assert!(!validators.is_not_modified(&headers));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_validators_check_if_match() -> Result<()> {
+		// -- Setup & Fixtures
+		let validators = Validators::of(&fx_post("2026-10-19T10:00:00.250Z"));
+		let fx_updated = Validators::of(&fx_post("2026-10-19T10:00:00.500Z"));
+		let etag = validators.etag().to_string();
+
+		// -- Exec & Check
+		assert!(validators.check_if_match(&fx_headers(&[(header::IF_MATCH, &etag)]), true)?);
+		assert!(validators.check_if_match(&fx_headers(&[(header::IF_MATCH, "*")]), true)?);
+		assert!(!validators.check_if_match(&HeaderMap::new(), false)?);
+		// (same second, still another version)
+		assert!(matches!(
+			fx_updated.check_if_match(&fx_headers(&[(header::IF_MATCH, &etag)]), true),
+			Err(ServerError::PreconditionFailed)
+		));
+		assert!(matches!(
+			validators.check_if_match(&fx_headers(&[(header::IF_MATCH, &format!("W/{etag}"))]), true),
+			Err(ServerError::PreconditionFailed)
+		));
+		assert!(matches!(
+			validators.check_if_match(&HeaderMap::new(), true),
+			Err(ServerError::PreconditionRequired)
+		));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
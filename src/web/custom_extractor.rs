@@ -1,35 +1,225 @@
-use axum::{extract::rejection::JsonRejection, response::IntoResponse, Json};
-use serde_json::json;
-use thiserror::Error;
+//! Request extractors, rejecting with a `ServerError` (instead of axum's default text rejections),
+//! so that extraction failures go through `main_response_mapper` and the request log like any other error.
+//!
+//! Use them in place of axum's `Json`, `Path` and `Query`.
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use axum::{body::HttpBody, extract::{rejection::{JsonRejection, PathRejection, QueryRejection}, FromRequest, FromRequestParts}, http::Request, BoxError, Json};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 use tracing::debug;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use super::ServerError;
+
+// region:    --- Validated JSON
+/// JSON body extractor, also validating the payload against the rules declared on it
+/// (`#[derive(Validate)]`, see the `validator` crate and `utils::validation`)
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for ValidJson<T>
+where
+	T: DeserializeOwned + Validate,
+	S: Send + Sync,
+	B: HttpBody + Send + 'static,
+	B::Data: Send,
+	B::Error: Into<BoxError>,
+{
+	type Rejection = ServerError;
+
+	async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+		// -- Check content type and syntax.
+		let Json(value) = Json::<Value>::from_request(req, state).await?;
+
+		// -- Deserialize, keeping track of the failing field.
+		let value: T = serde_path_to_error::deserialize(value).map_err(|err| {
+			let path = err.path().to_string();
+			let message = err.into_inner().to_string();
 
-#[derive(Debug, Error)]
+			// (A missing field is reported on its parent, by serde)
+			let field = match message.strip_prefix("missing field `").and_then(|rest| rest.strip_suffix('`')) {
+				Some(missing) if path == "." => missing.to_string(),
+				Some(missing) => format!("{path}.{missing}"),
+				None if path == "." => "body".to_string(),
+				None => path,
+			};
+			let fields = BTreeMap::from([(field, vec![message])]);
 
-/// Struct to help with JSON Deserialization error
-pub enum ApiError {
-	#[error(transparent)]
-	JsonExtractorRejection(#[from] JsonRejection),
+			ServerError::RequestValidationFail { fields }
+		})?;
+
+		// -- Validate.
+		value.validate().map_err(|errors| ServerError::RequestValidationFail {
+			fields: field_error_messages(&errors),
+		})?;
+
+		Ok(ValidJson(value))
+	}
 }
 
-/// Implement IntoResponse to enable sending a server response
-impl IntoResponse for ApiError {
-	fn into_response(self) -> axum::response::Response {
+/// Field name to its error messages (sorted, for a stable output)
+pub fn field_error_messages(errors: &ValidationErrors) -> BTreeMap<String, Vec<String>> {
+	errors
+		.field_errors()
+		.into_iter()
+		.map(|(field, field_errors)| {
+			let messages = field_errors.iter().map(field_error_message).collect();
+			(field.to_string(), messages)
+		})
+		.collect()
+}
 
-		// Get the json rejection status and error
-		let (status, message) = match self {
-			ApiError::JsonExtractorRejection(json_rejection) => {
-				(json_rejection.status(), json_rejection.body_text())
-			}
-		};
+/// The rule message if set, otherwise one built from the rule code and params.
+fn field_error_message(error: &ValidationError) -> String {
+	if let Some(message) = &error.message {
+		return message.to_string();
+	}
+
+	let param = |name: &str| error.params.get(name).map(|value| value.to_string());
 
-		// Create a JSON with it
-		let payload = json!({
-			"message": message,
-			"origin": "with_rejection"
-		});
+	match (error.code.as_ref(), param("min"), param("max")) {
+		("length", Some(min), Some(max)) => format!("must be between {min} and {max} characters"),
+		("length", Some(min), None) => format!("must be at least {min} characters"),
+		("length", None, Some(max)) => format!("must be at most {max} characters"),
+		("range", Some(min), Some(max)) => format!("must be between {min} and {max}"),
+		("email", _, _) => "must be a valid email".to_string(),
+		(code, _, _) => format!("is invalid ({code})"),
+	}
+}
+// endregion: --- Validated JSON
+
+// region:    --- Path & Query
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ServerError))]
+/// Path parameters extractor (see `axum::extract::Path`)
+pub struct Path<T>(pub T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ServerError))]
+/// Query string extractor (see `axum::extract::Query`)
+pub struct Query<T>(pub T);
+// endregion: --- Path & Query
 
+// region:    --- Rejection Froms
+impl From<JsonRejection> for ServerError {
+	fn from(rejection: JsonRejection) -> Self {
 		debug!(" {:<12} - json_rejection", "EXTRACTOR");
-		// Send this JSON as the error response
-		(status, Json(payload)).into_response()
+
+		match rejection {
+			JsonRejection::MissingJsonContentType(_) => ServerError::RequestJsonNoContentType,
+			rejection => ServerError::RequestJsonInvalid { detail: rejection.body_text() },
+		}
+	}
+}
+
+impl From<PathRejection> for ServerError {
+	fn from(rejection: PathRejection) -> Self {
+		debug!(" {:<12} - path_rejection", "EXTRACTOR");
+
+		ServerError::RequestPathInvalid { detail: rejection.body_text() }
 	}
-}
\ No newline at end of file
+}
+
+impl From<QueryRejection> for ServerError {
+	fn from(rejection: QueryRejection) -> Self {
+		debug!(" {:<12} - query_rejection", "EXTRACTOR");
+
+		ServerError::RequestQueryInvalid { detail: rejection.body_text() }
+	}
+}
+// endregion: --- Rejection Froms
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use axum::body::Body;
+	use serde::Deserialize;
+
+	#[derive(Debug, Deserialize, Validate)]
+	struct FxPayload {
+		#[validate(length(min = 1, max = 4))]
+		title: String,
+		#[validate(email, length(max = 128))]
+		email: String,
+		#[validate(custom(function = "crate::utils::validation::validate_pwd_strength"))]
+		password: String,
+	}
+
+	async fn extract(body: &str) -> Result<FxPayload, ServerError> {
+		let req = Request::builder()
+			.header("content-type", "application/json")
+			.body(Body::from(body.to_string()))
+			.unwrap();
+
+		ValidJson::<FxPayload>::from_request(req, &()).await.map(|ValidJson(payload)| payload)
+	}
+
+	#[test]
+	fn test_field_error_messages() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_payload = FxPayload {
+			title: "too long".to_string(),
+			email: "not-an-email".to_string(),
+			password: "welcome123".to_string(),
+		};
+
+		// -- Exec
+		let errors = fx_payload.validate().unwrap_err();
+		let messages = field_error_messages(&errors);
+
+		// -- Check
+		assert_eq!(messages.len(), 2);
+		assert_eq!(messages["title"], vec!["must be between 1 and 4 characters"]);
+		assert_eq!(messages["email"], vec!["must be a valid email"]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_valid_json_err_field_type() -> Result<()> {
+		// -- Exec
+		let res = extract(r#"{"title": 1, "email": "e@mail", "password": "welcome123"}"#).await;
+
+		// -- Check
+		let Err(ServerError::RequestValidationFail { fields }) = res else {
+			panic!("Should have been a `RequestValidationFail`, was {res:?}");
+		};
+		assert_eq!(fields.keys().collect::<Vec<_>>(), vec!["title"]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_valid_json_err_missing_field() -> Result<()> {
+		// -- Exec
+		let res = extract(r#"{"title": "t", "email": "e@mail"}"#).await;
+
+		// -- Check
+		let Err(ServerError::RequestValidationFail { fields }) = res else {
+			panic!("Should have been a `RequestValidationFail`, was {res:?}");
+		};
+		assert_eq!(fields["password"], vec!["missing field `password`"]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_valid_json_err_syntax() -> Result<()> {
+		// -- Exec
+		let res = extract(r#"{"title": "#).await;
+
+		// -- Check
+		assert!(
+			matches!(res, Err(ServerError::RequestJsonInvalid { .. })),
+			"Should have been a `RequestJsonInvalid`, was {res:?}"
+		);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
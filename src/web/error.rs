@@ -2,14 +2,16 @@ use axum::{response::IntoResponse, http::StatusCode, extract::rejection::JsonRej
 use serde::Serialize;
 use serde_json::{json, Value};
 use tracing::debug;
+use std::collections::BTreeMap;
 use std::string::ToString;
 
-use crate::{crypt, models, web};
+use crate::{crypt, models, oidc, web};
 pub type ServerResult<T> = core::result::Result<T, ServerError>;
 use super::*;
 
 /// Custom error enum, holding errors encountered during request handling
 #[derive(Debug, strum_macros::AsRefStr, Serialize)]
+#[serde(tag = "type", content = "data")]
 pub enum ServerError {
 
 	// -- Signup
@@ -21,6 +23,66 @@ pub enum ServerError {
 	LoginFailEmailNotFound,
 	LoginFailAuthorHasNoPwd {author_id: i64},
 	LoginFailPwdNotMatching {author_id: i64},
+	/// Login rejected without checking credentials, the IP or account is backing off or locked out
+	LoginFailTooManyAttempts {retry_after_sec: u64},
+	/// This failed login locked out the IP or account
+	LoginFailLockedOut {retry_after_sec: u64},
+	/// Two-factor challenge token is malformed, expired, or for an unknown author
+	LoginFail2faChallengeInvalid,
+	LoginFail2faCodeNotMatching {author_id: i64},
+
+	// -- OpenID Connect
+	OidcProviderNotFound,
+	/// Flow cookie is missing, tampered with or expired, or the `state` does not match
+	OidcFlowInvalid,
+	/// OidcAuthorizationDenied(`error` sent back by the provider)
+	OidcAuthorizationDenied(String),
+	/// No verified email in the ID token, required to sign up
+	OidcEmailNotVerified,
+	/// An author already exists with the identity email, they must log in and link it
+	OidcEmailTaken,
+	OidcIdentityLinkedToOtherAuthor,
+	/// The author already linked another account of this provider
+	OidcProviderAlreadyLinked,
+	OidcIdentityNotLinked,
+	/// Unlinking would leave the author without password nor identity to log in with
+	OidcIdentityIsLastLoginMethod,
+
+	// -- Request extraction (see `web::custom_extractor`)
+	/// Malformed JSON body (syntax error, or body could not be read)
+	RequestJsonInvalid { detail: String },
+	RequestJsonNoContentType,
+	/// Invalid fields (wrong type or failed validation rules), field name to messages
+	RequestValidationFail { fields: BTreeMap<String, Vec<String>> },
+	RequestPathInvalid { detail: String },
+	RequestQueryInvalid { detail: String },
+
+	// -- Conditional requests (see `web::conditional`)
+	/// Update without `If-Match`, while `server.require_if_match`
+	PreconditionRequired,
+	/// `If-Match` not matching the current version
+	PreconditionFailed,
+
+	// -- Rate limiting (see `web::rate_limit`)
+	RateLimited { retry_after_sec: u64 },
+
+	// -- CSRF (see `web::middlewares::csrf`)
+	/// Cookie authenticated request without the `X-CSRF-Token` header (or the `csrf-token` cookie)
+	CsrfTokenMissing,
+	CsrfTokenNotMatching,
+
+	// -- Media (see `web::handlers::media`)
+	MediaTooLarge { max_size_bytes: u64 },
+	/// Type detected from the content, not in `media.allowed_types`
+	MediaTypeNotAllowed { mime_type: String },
+	/// Malformed `multipart/form-data` body, or no `file` field
+	MediaUploadInvalid { detail: String },
+	/// On-demand resize URL not signed by the server (see `crypt::url_sig`)
+	MediaUrlSignatureInvalid,
+
+	// -- Admin
+	/// Admin only resource (e.g., webhooks), the author is not an admin
+	AdminRequired,
 
 	// -- CtxExtError
 	CtxExt(middlewares::auth::CtxExtError),
@@ -96,6 +158,10 @@ pub enum ServerError {
 	// Server
 	/// An error occurred on the server, not sure which
 	InternalServerError,
+	/// `mw_req_stamp` did not run before the `ReqStamp` extractor
+	ReqStampNotInReqExt,
+	/// Not served with the connect info (`into_make_service_with_connect_info`), for the `ClientIp` extractor
+	ClientAddrNotInReqExt,
 
 	// Cache
 	/// Something happened and Redis failed to connect
@@ -107,6 +173,7 @@ pub enum ServerError {
 	// -- Modules
 	Model(models::ModelError),
 	Crypt(crypt::CryptError),
+	Oidc(oidc::OidcError),
 	SerdeJson(String),
 
 }
@@ -125,6 +192,12 @@ impl From<crypt::CryptError> for ServerError {
 	}
 }
 
+impl From<oidc::OidcError> for ServerError {
+	fn from(val: oidc::OidcError) -> Self {
+		Self::Oidc(val)
+	}
+}
+
 impl From<serde_json::Error> for ServerError {
 	fn from(val: serde_json::Error) -> Self {
 		Self::SerdeJson(val.to_string())
@@ -150,6 +223,9 @@ impl IntoResponse for ServerError {
 impl ServerError {
 	pub fn client_status_and_error(&self) -> (StatusCode, ClientError) {
 		use web::ServerError::*;
+		use models::ModelError;
+		use models::media::MediaError;
+		use models::webhook::WebhookError;
 
 		// #[allow(unreachable_patterns)]
 		match self {
@@ -163,47 +239,229 @@ impl ServerError {
 			// -- Login
 			LoginFailEmailNotFound
 			| LoginFailAuthorHasNoPwd { .. }
-			| LoginFailPwdNotMatching { .. } => {
+			| LoginFailPwdNotMatching { .. }
+			| LoginFail2faChallengeInvalid
+			| LoginFail2faCodeNotMatching { .. } => {
 				(StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL)
 			},
 
+			LoginFailTooManyAttempts { retry_after_sec }
+			| LoginFailLockedOut { retry_after_sec } => {
+				let detail = format!("Too many failed login attempts, retry in {retry_after_sec} seconds");
+
+				(StatusCode::TOO_MANY_REQUESTS, ClientError::LOGIN_TOO_MANY_ATTEMPTS(detail))
+			},
+
+			// -- OpenID Connect
+			Oidc(_)
+			| OidcFlowInvalid
+			| OidcAuthorizationDenied(_) => {
+				(StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL)
+			},
+
+			OidcProviderNotFound => {
+				(StatusCode::NOT_FOUND, ClientError::OIDC_PROVIDER_NOT_FOUND("Unknown login provider".to_string()))
+			},
+
+			OidcEmailNotVerified => {
+				let detail = "A verified email is required to sign up with this provider".to_string();
+
+				(StatusCode::FORBIDDEN, ClientError::OIDC_EMAIL_NOT_VERIFIED(detail))
+			},
+
+			OidcEmailTaken => {
+				let detail = "An author with this email already exists, log in and link this provider instead".to_string();
+
+				(StatusCode::CONFLICT, ClientError::OIDC_EMAIL_TAKEN(detail))
+			},
+
+			OidcIdentityLinkedToOtherAuthor => {
+				let detail = "This provider account is linked to another author".to_string();
+
+				(StatusCode::CONFLICT, ClientError::OIDC_IDENTITY_LINKED_TO_OTHER_AUTHOR(detail))
+			},
+
+			OidcProviderAlreadyLinked => {
+				let detail = "Another account of this provider is already linked".to_string();
+
+				(StatusCode::CONFLICT, ClientError::OIDC_PROVIDER_ALREADY_LINKED(detail))
+			},
+
+			OidcIdentityNotLinked => {
+				(StatusCode::NOT_FOUND, ClientError::OIDC_IDENTITY_NOT_LINKED("No account linked for this provider".to_string()))
+			},
+
+			OidcIdentityIsLastLoginMethod => {
+				let detail = "Cannot unlink the only login method, set a password first".to_string();
+
+				(StatusCode::CONFLICT, ClientError::OIDC_LAST_LOGIN_METHOD(detail))
+			},
+
+			// -- Request extraction
+			RequestJsonInvalid { detail } => {
+				(StatusCode::BAD_REQUEST, ClientError::INVALID_JSON(detail.clone()))
+			},
+
+			RequestJsonNoContentType => {
+				let detail = "Expected request with `Content-Type: application/json`".to_string();
+
+				(StatusCode::UNSUPPORTED_MEDIA_TYPE, ClientError::UNSUPPORTED_MEDIA_TYPE(detail))
+			},
+
+			RequestValidationFail { fields } => {
+				(StatusCode::UNPROCESSABLE_ENTITY, ClientError::VALIDATION_FAIL { fields: fields.clone() })
+			},
+
+			RequestPathInvalid { detail } => {
+				(StatusCode::BAD_REQUEST, ClientError::INVALID_PATH(detail.clone()))
+			},
+
+			RequestQueryInvalid { detail } => {
+				(StatusCode::BAD_REQUEST, ClientError::INVALID_QUERY(detail.clone()))
+			},
+
+			// -- Conditional requests
+			PreconditionRequired => {
+				let detail = "`If-Match` required, with the `ETag` of the current version".to_string();
+
+				(StatusCode::PRECONDITION_REQUIRED, ClientError::PRECONDITION_REQUIRED(detail))
+			},
+
+			PreconditionFailed | Model(ModelError::EntityModified { .. }) => {
+				let detail = "Modified since, get the current version and retry".to_string();
+
+				(StatusCode::PRECONDITION_FAILED, ClientError::PRECONDITION_FAILED(detail))
+			},
+
+			// -- Rate limiting
+			RateLimited { retry_after_sec } => {
+				let detail = format!("Too many requests, retry in {retry_after_sec} seconds");
+
+				(StatusCode::TOO_MANY_REQUESTS, ClientError::RATE_LIMITED(detail))
+			},
+
+			// -- CSRF
+			CsrfTokenMissing | CsrfTokenNotMatching => {
+				let detail = "Missing or invalid `X-CSRF-Token`, send the `csrf-token` cookie value".to_string();
+
+				(StatusCode::FORBIDDEN, ClientError::CSRF_FAIL(detail))
+			},
+
+			// -- Media
+			MediaTooLarge { max_size_bytes } => {
+				let detail = format!("Media larger than {max_size_bytes} bytes");
+
+				(StatusCode::PAYLOAD_TOO_LARGE, ClientError::MEDIA_TOO_LARGE(detail))
+			},
+
+			MediaTypeNotAllowed { mime_type } => {
+				let detail = format!("Media type `{mime_type}` not allowed");
+
+				(StatusCode::UNSUPPORTED_MEDIA_TYPE, ClientError::UNSUPPORTED_MEDIA_TYPE(detail))
+			},
+
+			MediaUploadInvalid { detail } => {
+				(StatusCode::BAD_REQUEST, ClientError::BAD_REQUEST(detail.clone()))
+			},
+
+			MediaUrlSignatureInvalid => {
+				let detail = "Missing or invalid `sig`, get the URL from `/api/media/:id/resize-url`".to_string();
+
+				(StatusCode::FORBIDDEN, ClientError::FORBIDDEN(detail))
+			},
+
+			Model(ModelError::Media(MediaError::ImageInvalid(_))) => {
+				let detail = "Image could not be read (corrupted or truncated)".to_string();
+
+				(StatusCode::UNPROCESSABLE_ENTITY, ClientError::INVALID_VALUE(detail))
+			},
+
+			Model(ModelError::Media(MediaError::ImageTooLarge { width, height })) => {
+				let detail = format!("Image of {width}x{height} pixels, too large to be processed");
+
+				(StatusCode::PAYLOAD_TOO_LARGE, ClientError::MEDIA_TOO_LARGE(detail))
+			},
+
+			Model(ModelError::Media(MediaError::ImageTypeNotProcessed(mime_type))) => {
+				let detail = format!("Media type `{mime_type}` is not a processed image type");
+
+				(StatusCode::UNSUPPORTED_MEDIA_TYPE, ClientError::UNSUPPORTED_MEDIA_TYPE(detail))
+			},
+
+			// -- Webhooks
+			Model(ModelError::Webhook(WebhookError::DeliveryPending { id })) => {
+				let detail = format!("Delivery {id} is pending (being attempted, or to be retried), retry later");
+
+				(StatusCode::CONFLICT, ClientError::CONFLICT(detail))
+			},
+
+			Model(ModelError::Webhook(WebhookError::TargetNotAllowed { .. })) => {
+				let detail = "Webhook URL to a private, loopback or link-local address, not allowed".to_string();
+
+				(StatusCode::UNPROCESSABLE_ENTITY, ClientError::INVALID_VALUE(detail))
+			},
+
+			// -- Admin
+			AdminRequired => {
+				(StatusCode::FORBIDDEN, ClientError::FORBIDDEN("Admin only".to_string()))
+			},
+
 			// -- Auth
 			CtxExt(_) => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
 
 			// -- Crud
 			CreateFail(model_name, reason, crud_error) => {
-				let status_code: StatusCode = crud_error.into();
-				let error_message = format!("{} create failed, {}", model_name, reason);
-
-				(status_code, ClientError::CUSTOM(error_message))
+				crud_error.client_status_and_error(format!("{} create failed, {}", model_name, reason))
 			},
 			
 			ListFail(model_name, reason, crud_error) => {
-				let status_code: StatusCode = crud_error.into();
-				let error_message = format!("{}s list failed, {}", model_name, reason);
-
-				(status_code, ClientError::CUSTOM(error_message))
+				crud_error.client_status_and_error(format!("{}s list failed, {}", model_name, reason))
 			},
 			
 			GetFail(model_name, reason, crud_error) => {
-				let status_code: StatusCode = crud_error.into();
-				let error_message = format!("{} get failed, {}", model_name, reason);
-
-				(status_code, ClientError::CUSTOM(error_message))
+				crud_error.client_status_and_error(format!("{} get failed, {}", model_name, reason))
 			},
 			
 			UpdateFail(model_name, reason, crud_error) => {
-				let status_code: StatusCode = crud_error.into();
-				let error_message = format!("{} update failed, {}", model_name, reason);
-
-				(status_code, ClientError::CUSTOM(error_message))
+				crud_error.client_status_and_error(format!("{} update failed, {}", model_name, reason))
 			},
 			
 			DeleteFail(model_name, reason, crud_error) => {
-				let status_code: StatusCode = crud_error.into();
-				let error_message = format!("{} delete failed, {}", model_name, reason);
+				crud_error.client_status_and_error(format!("{} delete failed, {}", model_name, reason))
+			},
 
-				(status_code, ClientError::CUSTOM(error_message))
+			// -- Model
+			Model(ModelError::EntityNotFound { entity, id }) => {
+				(StatusCode::NOT_FOUND, ClientError::ENTITY_NOT_FOUND(format!("{entity} {id} not found")))
+			},
+
+			Model(ModelError::EntityAccessRequiresAuth) => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
+
+			// (classified db errors, details only disclose the constraint name)
+			Model(ModelError::UniqueViolation { constraint, .. }) => {
+				let detail = with_constraint("Conflict, already exists", constraint);
+
+				(StatusCode::CONFLICT, ClientError::CONFLICT(detail))
+			},
+
+			Model(ModelError::ForeignKeyViolation { constraint, .. }) => {
+				let detail = with_constraint("Invalid reference to another resource", constraint);
+
+				(StatusCode::BAD_REQUEST, ClientError::INVALID_REFERENCE(detail))
+			},
+
+			Model(ModelError::CheckViolation { constraint, .. }) => {
+				let detail = with_constraint("Invalid value", constraint);
+
+				(StatusCode::UNPROCESSABLE_ENTITY, ClientError::INVALID_VALUE(detail))
+			},
+
+			Model(ModelError::StringTooLong) => {
+				(StatusCode::UNPROCESSABLE_ENTITY, ClientError::VALUE_TOO_LONG)
+			},
+
+			Model(ModelError::TxConflict) => {
+				(StatusCode::SERVICE_UNAVAILABLE, ClientError::SERVICE_UNAVAILABLE)
 			},
 
 			// -- Fallback.
@@ -215,6 +473,13 @@ impl ServerError {
 	}
 }
 
+fn with_constraint(detail: &str, constraint: &Option<String>) -> String {
+	match constraint {
+		Some(constraint) => format!("{detail} ({constraint})"),
+		None => detail.to_string(),
+	}
+}
+
 
 // region:    --- ServerError Boilerplate
 
@@ -235,20 +500,105 @@ impl std::error::Error for ServerError {}
 // region:   --- Client Error
 
 #[derive(Debug, strum_macros::AsRefStr)]
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+/// Error sent to the client, the variant name being its stable, machine-readable code
+/// (Renaming a variant is a breaking change for the clients)
 pub enum ClientError {
+	// -- Auth
 	LOGIN_FAIL,
+	LOGIN_TOO_MANY_ATTEMPTS(String),
 	NO_AUTH,
+	CSRF_FAIL(String),
+	OIDC_PROVIDER_NOT_FOUND(String),
+	OIDC_EMAIL_NOT_VERIFIED(String),
+	OIDC_EMAIL_TAKEN(String),
+	OIDC_IDENTITY_LINKED_TO_OTHER_AUTHOR(String),
+	OIDC_PROVIDER_ALREADY_LINKED(String),
+	OIDC_IDENTITY_NOT_LINKED(String),
+	OIDC_LAST_LOGIN_METHOD(String),
+
+	// -- Request
+	INVALID_JSON(String),
+	UNSUPPORTED_MEDIA_TYPE(String),
+	/// Field name to its error messages
+	VALIDATION_FAIL { fields: BTreeMap<String, Vec<String>> },
+	INVALID_PATH(String),
+	INVALID_QUERY(String),
+	PRECONDITION_REQUIRED(String),
+	PRECONDITION_FAILED(String),
+	RATE_LIMITED(String),
+	MEDIA_TOO_LARGE(String),
+	BAD_REQUEST(String),
+	UNAUTHORIZED(String),
+	FORBIDDEN(String),
+	ENTITY_NOT_FOUND(String),
+	CONFLICT(String),
+	INVALID_REFERENCE(String),
+	INVALID_VALUE(String),
+	VALUE_TOO_LONG,
+
+	// -- Server
 	SERVICE_ERROR,
-	// #[strum(to_string = "{0}")]
-	CUSTOM(String)
+	/// Transient, the request can be retried
+	SERVICE_UNAVAILABLE,
+}
+
+impl ClientError {
+	/// Stable, machine-readable code (e.g., `ENTITY_NOT_FOUND`)
+	pub fn code(&self) -> &str {
+		self.as_ref()
+	}
+
+	/// Human readable explanation, specific to this occurrence
+	pub fn detail(&self) -> Option<&str> {
+		use ClientError::*;
+
+		match self {
+			LOGIN_TOO_MANY_ATTEMPTS(detail)
+			| CSRF_FAIL(detail)
+			| OIDC_PROVIDER_NOT_FOUND(detail)
+			| OIDC_EMAIL_NOT_VERIFIED(detail)
+			| OIDC_EMAIL_TAKEN(detail)
+			| OIDC_IDENTITY_LINKED_TO_OTHER_AUTHOR(detail)
+			| OIDC_PROVIDER_ALREADY_LINKED(detail)
+			| OIDC_IDENTITY_NOT_LINKED(detail)
+			| OIDC_LAST_LOGIN_METHOD(detail)
+			| INVALID_JSON(detail)
+			| UNSUPPORTED_MEDIA_TYPE(detail)
+			| INVALID_PATH(detail)
+			| INVALID_QUERY(detail)
+			| PRECONDITION_REQUIRED(detail)
+			| PRECONDITION_FAILED(detail)
+			| RATE_LIMITED(detail)
+			| MEDIA_TOO_LARGE(detail)
+			| BAD_REQUEST(detail)
+			| UNAUTHORIZED(detail)
+			| FORBIDDEN(detail)
+			| ENTITY_NOT_FOUND(detail)
+			| CONFLICT(detail)
+			| INVALID_REFERENCE(detail)
+			| INVALID_VALUE(detail) => Some(detail),
+
+			VALIDATION_FAIL { .. } => Some("Some fields are invalid"),
+
+			LOGIN_FAIL | NO_AUTH | VALUE_TOO_LONG | SERVICE_ERROR | SERVICE_UNAVAILABLE => None,
+		}
+	}
+
+	/// Per-field error messages, if any
+	pub fn field_errors(&self) -> Option<&BTreeMap<String, Vec<String>>> {
+		match self {
+			ClientError::VALIDATION_FAIL { fields } => Some(fields),
+			_ => None,
+		}
+	}
 }
 
 // endregion: --- Client Error
 
 // region:    --- CRUD Error codes
 #[derive(Debug, strum_macros::AsRefStr, Serialize)]
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
 pub enum CrudError {
 	FORBIDDEN,
 	BAD_REQUEST,
@@ -257,6 +607,21 @@ pub enum CrudError {
 	INTERNAL_SERVER_ERROR
 }
 
+impl CrudError {
+	/// The status code and client error for a failed CRUD operation, with its `detail`
+	fn client_status_and_error(&self, detail: String) -> (StatusCode, ClientError) {
+		let client_error = match self {
+			CrudError::BAD_REQUEST => ClientError::BAD_REQUEST(detail),
+			CrudError::FORBIDDEN => ClientError::FORBIDDEN(detail),
+			CrudError::UNAUTHORIZED => ClientError::UNAUTHORIZED(detail),
+			CrudError::CONFLICT => ClientError::CONFLICT(detail),
+			CrudError::INTERNAL_SERVER_ERROR => ClientError::SERVICE_ERROR,
+		};
+
+		(self.into(), client_error)
+	}
+}
+
 impl From<&CrudError> for StatusCode {
 	fn from(value: &CrudError) -> Self {
 		match value {
@@ -268,4 +633,27 @@ impl From<&CrudError> for StatusCode {
 		}
 	}
 }
-// endregion: --- CRUD Error codes
\ No newline at end of file
+// endregion: --- CRUD Error codes
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[test]
+	fn test_client_status_and_error_not_found() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_error = ServerError::Model(models::ModelError::EntityNotFound { entity: "posts", id: 100 });
+
+		// -- Exec
+		let (status, client_error) = fx_error.client_status_and_error();
+
+		// -- Check
+		assert_eq!(status, StatusCode::NOT_FOUND);
+		assert_eq!(client_error.code(), "ENTITY_NOT_FOUND");
+		assert_eq!(client_error.detail(), Some("posts 100 not found"));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
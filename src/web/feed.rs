@@ -0,0 +1,274 @@
+//! Rendering of the post feeds: RSS 2.0, Atom (RFC 4287) and JSON Feed 1.1
+//!
+//! NOTE: Pure rendering, the routes (`web::routes_feed`) gather the posts and handle the conditional GET.
+//!
+//! - RSS dates are RFC 822 (`pubDate`, as HTTP dates), Atom and JSON Feed dates are RFC 3339.
+//! - An item is published at its `created_at`, updated at its `updated_at`.
+//!
+
+use std::fmt::Write;
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+	Rss,
+	Atom,
+	Json,
+}
+
+impl FeedFormat {
+	pub fn content_type(&self) -> &'static str {
+		match self {
+			FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+			FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+			FeedFormat::Json => "application/feed+json",
+		}
+	}
+
+	/// Of the feed route (e.g., `feed.rss`)
+	pub fn extension(&self) -> &'static str {
+		match self {
+			FeedFormat::Rss => "rss",
+			FeedFormat::Atom => "atom",
+			FeedFormat::Json => "json",
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct Feed {
+	pub title: String,
+	pub description: String,
+	/// Without trailing `/`
+	pub site_url: String,
+	/// The feed itself
+	pub self_url: String,
+	/// The latest `updated` of the items (`None` without items)
+	pub updated: Option<OffsetDateTime>,
+	/// Latest first
+	pub items: Vec<FeedItem>,
+}
+
+#[derive(Debug)]
+pub struct FeedItem {
+	pub id: i64,
+	pub title: String,
+	pub content: String,
+	pub url: String,
+	pub author_name: String,
+	pub published: OffsetDateTime,
+	pub updated: OffsetDateTime,
+}
+
+impl Feed {
+	pub fn render(&self, format: FeedFormat) -> String {
+		match format {
+			FeedFormat::Rss => self.render_rss(),
+			FeedFormat::Atom => self.render_atom(),
+			FeedFormat::Json => self.render_json(),
+		}
+	}
+
+	fn render_rss(&self) -> String {
+		let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+		xml.push_str(r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/"><channel>"#);
+		let _ = write!(
+			xml,
+			r#"<title>{}</title><link>{}</link><description>{}</description><atom:link href="{}" rel="self" type="application/rss+xml"/>"#,
+			xml_escape(&self.title),
+			xml_escape(&self.site_url),
+			xml_escape(&self.description),
+			xml_escape(&self.self_url),
+		);
+		if let Some(updated) = self.updated {
+			let _ = write!(xml, "<lastBuildDate>{}</lastBuildDate>", rfc822(updated));
+		}
+
+		for item in &self.items {
+			let _ = write!(
+				xml,
+				r#"<item><title>{}</title><link>{}</link><guid isPermaLink="false">{}</guid><dc:creator>{}</dc:creator><pubDate>{}</pubDate><description>{}</description></item>"#,
+				xml_escape(&item.title),
+				xml_escape(&item.url),
+				xml_escape(&item.url),
+				xml_escape(&item.author_name),
+				rfc822(item.published),
+				xml_escape(&item.content),
+			);
+		}
+
+		xml.push_str("</channel></rss>");
+		xml
+	}
+
+	fn render_atom(&self) -> String {
+		let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+		xml.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+		// (`updated` is required, the epoch for an empty feed)
+		let _ = write!(
+			xml,
+			r#"<id>{}</id><title>{}</title><updated>{}</updated><link href="{}"/><link href="{}" rel="self"/>"#,
+			xml_escape(&self.self_url),
+			xml_escape(&self.title),
+			rfc3339(self.updated.unwrap_or(OffsetDateTime::UNIX_EPOCH)),
+			xml_escape(&self.site_url),
+			xml_escape(&self.self_url),
+		);
+		if !self.description.is_empty() {
+			let _ = write!(xml, "<subtitle>{}</subtitle>", xml_escape(&self.description));
+		}
+
+		for item in &self.items {
+			let _ = write!(
+				xml,
+				r#"<entry><id>{}</id><title>{}</title><link href="{}"/><author><name>{}</name></author><published>{}</published><updated>{}</updated><content type="text">{}</content></entry>"#,
+				xml_escape(&item.url),
+				xml_escape(&item.title),
+				xml_escape(&item.url),
+				xml_escape(&item.author_name),
+				rfc3339(item.published),
+				rfc3339(item.updated),
+				xml_escape(&item.content),
+			);
+		}
+
+		xml.push_str("</feed>");
+		xml
+	}
+
+	fn render_json(&self) -> String {
+		let items: Vec<Value> = self
+			.items
+			.iter()
+			.map(|item| {
+				json!({
+					"id": item.id.to_string(),
+					"url": item.url,
+					"title": item.title,
+					"content_text": item.content,
+					"date_published": rfc3339(item.published),
+					"date_modified": rfc3339(item.updated),
+					"authors": [{ "name": item.author_name }],
+				})
+			})
+			.collect();
+
+		let mut feed = json!({
+			"version": "https://jsonfeed.org/version/1.1",
+			"title": self.title,
+			"home_page_url": self.site_url,
+			"feed_url": self.self_url,
+			"items": items,
+		});
+		if !self.description.is_empty() {
+			feed["description"] = json!(self.description);
+		}
+
+		feed.to_string()
+	}
+}
+
+fn rfc3339(date: OffsetDateTime) -> String {
+	date.format(&Rfc3339).unwrap_or_default()
+}
+
+/// As `pubDate` (RFC 822, in GMT, e.g., `Mon, 19 Oct 2026 10:00:00 GMT`)
+fn rfc822(date: OffsetDateTime) -> String {
+	httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(date.unix_timestamp().max(0) as u64))
+}
+
+/// Escape the text and attribute values.
+pub fn xml_escape(value: &str) -> String {
+	let mut escaped = String::with_capacity(value.len());
+	for c in value.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			c => escaped.push(c),
+		}
+	}
+
+	escaped
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	fn fx_feed() -> Feed {
+		let published = OffsetDateTime::parse("2026-10-19T10:00:00Z", &Rfc3339).unwrap();
+		let updated = OffsetDateTime::parse("2026-10-19T12:30:00Z", &Rfc3339).unwrap();
+
+		Feed {
+			title: "fx blog".to_string(),
+			description: "fx <description>".to_string(),
+			site_url: "https://blog.example.com".to_string(),
+			self_url: "https://blog.example.com/feed.rss".to_string(),
+			updated: Some(updated),
+			items: vec![FeedItem {
+				id: 1000,
+				title: "Tom & Jerry".to_string(),
+				content: "<p>fx content</p>".to_string(),
+				url: "https://blog.example.com/api/post/1000".to_string(),
+				author_name: "fx author".to_string(),
+				published,
+				updated,
+			}],
+		}
+	}
+
+	#[test]
+	fn test_feed_render_rss() -> Result<()> {
+		// -- Exec
+		let rss = fx_feed().render(FeedFormat::Rss);
+
+		// -- Check
+		assert!(rss.contains("<title>Tom &amp; Jerry</title>"));
+		assert!(rss.contains("<description>&lt;p&gt;fx content&lt;/p&gt;</description>"));
+		assert!(rss.contains("<pubDate>Mon, 19 Oct 2026 10:00:00 GMT</pubDate>"));
+		assert!(rss.contains("<lastBuildDate>Mon, 19 Oct 2026 12:30:00 GMT</lastBuildDate>"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_feed_render_atom() -> Result<()> {
+		// -- Exec
+		let atom = fx_feed().render(FeedFormat::Atom);
+
+		// -- Check
+		assert!(atom.contains("<updated>2026-10-19T12:30:00Z</updated>"));
+		assert!(atom.contains("<published>2026-10-19T10:00:00Z</published>"));
+		assert!(atom.contains("<subtitle>fx &lt;description&gt;</subtitle>"));
+		assert!(atom.contains(r#"<link href="https://blog.example.com/feed.rss" rel="self"/>"#));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_feed_render_json() -> Result<()> {
+		// -- Exec
+		let feed: Value = serde_json::from_str(&fx_feed().render(FeedFormat::Json))?;
+
+		// -- Check
+		assert_eq!(feed["version"], "https://jsonfeed.org/version/1.1");
+		let item = &feed["items"][0];
+		assert_eq!(item["id"], "1000");
+		assert_eq!(item["title"], "Tom & Jerry");
+		assert_eq!(item["date_published"], "2026-10-19T10:00:00Z");
+		assert_eq!(item["date_modified"], "2026-10-19T12:30:00Z");
+		assert_eq!(item["authors"][0]["name"], "fx author");
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -1,22 +1,24 @@
 use std::fmt::format;
 
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::{debug_handler, Extension, Json};
-use axum::extract::{Path, State};
+use axum::extract::State;
 use axum_extra::extract::WithRejection;
 use tracing::{debug, info};
 
 use crate::ctx::Ctx;
 use crate::models::author::{Author, AuthorBmc, AuthorForCreate, AuthorForEdit};
+use crate::web::conditional::Validators;
 use crate::web::custom_response::{CustomResponse, CustomResponseData};
 use crate::web::error::{ServerResult, ServerError};
-use crate::web::custom_extractor::ApiError;
+use crate::web::Path;
 use crate::models::AppState;
-use crate::web::{IncomingServerRequest, ServerResponse};
+use crate::web::{IncomingServerRequest, ServerResponse, ValidJson};
 
 pub async fn handler_author_create(
 	State(app_state): State<AppState>,
-	WithRejection(Json(data), _): IncomingServerRequest<AuthorForCreate>
+	WithRejection(ValidJson(data), _): IncomingServerRequest<AuthorForCreate>
 ) -> ServerResponse<Author> {
 	debug!(" {:<12} - handler_author_create", "HANDLER");
 
@@ -26,7 +28,7 @@ pub async fn handler_author_create(
 
 	let response = CustomResponse::<Author>::new(
 		true,
-		Some(format!("Author Created")),
+		Some("Author Created".to_string()),
 		Some(CustomResponseData::Item(author))
 	);
 
@@ -41,7 +43,7 @@ pub async fn handler_author_list(State(app_state): State<AppState>) -> ServerRes
 
 	let response = CustomResponse::<Author>::new(
 		true,
-		Some(format!("Authors Retrieved")),
+		Some("Authors Retrieved".to_string()),
 		Some(CustomResponseData::Collection(authors))
 	);
 
@@ -49,16 +51,21 @@ pub async fn handler_author_list(State(app_state): State<AppState>) -> ServerRes
 }
 
 /// Handler to get an author
-pub async fn handler_author_get(State(app_state): State<AppState>, Path(id): Path<i64>) -> ServerResponse<Author> {
+pub async fn handler_author_get(State(app_state): State<AppState>, Path(id): Path<i64>, headers: HeaderMap) -> ServerResult<Response> {
 	debug!("{:>12} - handler_author", "HANDLER");
 
 	let author: Author = AuthorBmc::get_no_auth(&app_state, id).await?;
 
+	let validators = Validators::of(&author);
+	if validators.is_not_modified(&headers) {
+		return Ok(validators.not_modified());
+	}
+
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Author Retrieved")),
+		Some("Author Retrieved".to_string()),
 		Some(CustomResponseData::Item(author))
 	);
 
-	Ok((StatusCode::OK, Json(response)))
+	Ok((StatusCode::OK, validators.headers(), Json(response)).into_response())
 }
\ No newline at end of file
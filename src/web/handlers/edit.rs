@@ -1,17 +1,21 @@
-use axum::{extract::{Path, State}, http::StatusCode, Extension, Json};
+use axum::{extract::State, http::{HeaderMap, StatusCode}, response::{IntoResponse, Response}, Extension, Json};
 use axum_extra::extract::WithRejection;
 use serde_json::json;
 use tracing::debug;
 
-use crate::{ctx::Ctx, models::{edit::{Edit, EditBmc, EditFilter, EditForAccept, EditForCreate, EditForCreateRequestBody, EditForReject, EditForUpdate, EditForUpdateClientRequest, EditStatus}, post::{Post, PostBmc, PostFilter, PostForUpdate}, AppState}, web::{error::CrudError, IncomingServerRequest, ServerResponse}};
+use crate::{ctx::Ctx, models::{edit::{Edit, EditBmc, EditFilter, EditForAccept, EditForCreate, EditForCreateRequestBody, EditForReject, EditForUpdate, EditForUpdateClientRequest, EditStatus}, post::{Post, PostBmc, PostFilter, PostForUpdate}, AppState}, web::{error::CrudError, IncomingServerRequest, ServerResponse, ValidJson}};
 use crate::web::{custom_response::{CustomResponse, CustomResponseData}, error::{ServerError, ServerResult}};
-use crate::web::custom_extractor::ApiError;
+use crate::web::Path;
+use crate::web::conditional::Validators;
+use crate::config::config;
+use crate::web::metrics::{record_edit, EditEvent};
+use crate::models::webhook::{WebhookBmc, WebhookEventType};
 
 const TABLE_NAME: &str = "EDIT";
 pub async fn handler_edit_create(
 	ctx: Ctx,
 	State(app_state): State<AppState>,
-	WithRejection((Json(edit_info)), _): IncomingServerRequest<EditForCreateRequestBody>,
+	WithRejection(ValidJson(edit_info), _): IncomingServerRequest<EditForCreateRequestBody>,
 	) -> ServerResponse<Edit>
 	{
 		
@@ -26,12 +30,14 @@ pub async fn handler_edit_create(
 	};
 
 	let id = EditBmc::create(&ctx, &app_state, data).await?;
+	record_edit(EditEvent::Created);
 
 	let edit = EditBmc::get(&ctx, &app_state, id).await?;
+	WebhookBmc::emit(&app_state, WebhookEventType::EditCreated, &edit).await;
 
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Edit created successfully")),
+		Some("Edit created successfully".to_string()),
 		Some(CustomResponseData::Item(edit))
 	);
 
@@ -42,7 +48,8 @@ pub async fn handler_edit_get(
 	ctx: Ctx,
 	Path(id): Path<i64>,
 	State(app_state): State<AppState>,
-	) -> ServerResponse<Edit>
+	headers: HeaderMap,
+	) -> ServerResult<Response>
 	{
 		
 	debug!("{:<12} - handler_edit_get", "HANDLER");
@@ -57,28 +64,33 @@ pub async fn handler_edit_get(
 	if edit.editor_id != user_id && post.author_id != user_id{
 		let error = ServerError::GetFail(
 			TABLE_NAME.to_string(),
-			format!("You do not have permission to view this edit"),
+			"You do not have permission to view this edit".to_string(),
 			CrudError::UNAUTHORIZED
 		);
 
 		return Err(error)
 	}
 
+	let validators = Validators::of(&edit);
+	if validators.is_not_modified(&headers) {
+		return Ok(validators.not_modified());
+	}
+
 	// -- Build response
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Edit retrieved successfully")),
+		Some("Edit retrieved successfully".to_string()),
 		Some(CustomResponseData::Item(edit))
 	);
 
-	Ok((StatusCode::OK, Json(response)))
+	Ok((StatusCode::OK, validators.headers(), Json(response)).into_response())
 }
 
 pub async fn handler_edit_accept(
 	ctx: Ctx,
 	State(app_state): State<AppState>,
 	Path(id): Path<i64>,
-	WithRejection((Json(data)), _): IncomingServerRequest<EditForAccept>,
+	WithRejection(ValidJson(data), _): IncomingServerRequest<EditForAccept>,
 	) -> ServerResponse<Edit>
 	{
 		
@@ -113,7 +125,7 @@ pub async fn handler_edit_accept(
 		title: None
 	};
 
-	let _result = PostBmc::update(&ctx, &app_state, edit.post_id, post_u).await?;	
+	PostBmc::update(&ctx, &app_state, edit.post_id, post_u).await?;	
 
 	// Update edit status
 	let edit_u = EditForUpdate {
@@ -121,12 +133,18 @@ pub async fn handler_edit_accept(
 		status: Some(EditStatus::ACCEPTED)
 	};
 
-	let _result = EditBmc::update(&ctx, &app_state, id, edit_u).await?;
+	EditBmc::update(&ctx, &app_state, id, edit_u).await?;
+	record_edit(EditEvent::Accepted);
 	let edit = EditBmc::get(&ctx, &app_state, id).await?;
 
+	// (the post content is the edit's now)
+	let post = PostBmc::get(&ctx, &app_state, edit.post_id).await?;
+	WebhookBmc::emit(&app_state, WebhookEventType::PostUpdated, &post).await;
+	WebhookBmc::emit(&app_state, WebhookEventType::EditAccepted, &edit).await;
+
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Edit accepted successfully")),
+		Some("Edit accepted successfully".to_string()),
 		Some(CustomResponseData::Item(edit))
 	);
 
@@ -138,7 +156,7 @@ pub async fn handler_edit_reject(
 	ctx: Ctx,
 	State(app_state): State<AppState>,
 	Path(id): Path<i64>,
-	WithRejection((Json(data)), _): IncomingServerRequest<EditForReject>,
+	WithRejection(ValidJson(data), _): IncomingServerRequest<EditForReject>,
 	) -> ServerResponse<Edit>
 	{
 		
@@ -171,12 +189,14 @@ pub async fn handler_edit_reject(
 		status: Some(EditStatus::REJECTED)
 	};
 
-	let _result = EditBmc::update(&ctx, &app_state, id, edit_u).await?;
+	EditBmc::update(&ctx, &app_state, id, edit_u).await?;
+	record_edit(EditEvent::Rejected);
 	let edit = EditBmc::get(&ctx, &app_state, id).await?;
+	WebhookBmc::emit(&app_state, WebhookEventType::EditRejected, &edit).await;
 
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Edit rejected successfully")),
+		Some("Edit rejected successfully".to_string()),
 		Some(CustomResponseData::Item(edit))
 	);
 
@@ -206,7 +226,7 @@ pub async fn handler_edit_list_outgoing(
 
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Outgoing edits retrieved successfully")),
+		Some("Outgoing edits retrieved successfully".to_string()),
 		Some(CustomResponseData::Collection(edits))
 	);
 
@@ -251,7 +271,7 @@ pub async fn handler_edit_list_incoming(
 
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Incoming edits retrieved successfully")),
+		Some("Incoming edits retrieved successfully".to_string()),
 		Some(CustomResponseData::Collection(edits))
 	);
 
@@ -299,7 +319,7 @@ pub async fn handler_edit_list_all(
 
 	let response = CustomResponse::new(
 		true,
-		Some(format!("All edits retrieved successfully")),
+		Some("All edits retrieved successfully".to_string()),
 		Some(CustomResponseData::Collection(edits))
 	);
 
@@ -312,8 +332,9 @@ pub async fn handler_edit_update(
 	ctx: Ctx,
 	State(app_state): State<AppState>,
 	Path(id): Path<i64>,
-	WithRejection((Json(data)), _): IncomingServerRequest<EditForUpdateClientRequest>
-	) -> ServerResponse<Edit>
+	headers: HeaderMap,
+	WithRejection(ValidJson(data), _): IncomingServerRequest<EditForUpdateClientRequest>
+	) -> ServerResult<Response>
 	{
 		
 	debug!("{:<12} - handler_edit_update", "HANDLER");
@@ -329,7 +350,7 @@ pub async fn handler_edit_update(
 
 		let error = ServerError::UpdateFail(
 			TABLE_NAME.to_string(),
-			format!("You do not have permission to alter the content of this edit"),
+			"You do not have permission to alter the content of this edit".to_string(),
 			CrudError::UNAUTHORIZED
 		);
 		return Err(error);
@@ -351,19 +372,25 @@ pub async fn handler_edit_update(
 		status: None
 	};
 
-	let _result = EditBmc::update(&ctx, &app_state, edit_id, edit_u).await?;
+	// (only the version the editor got, see `web::conditional`)
+	if Validators::of(&edit).check_if_match(&headers, config().server.require_if_match)? {
+		EditBmc::update_if_unmodified(&ctx, &app_state, edit_id, edit_u, edit.updated_at).await?;
+	} else {
+		EditBmc::update(&ctx, &app_state, edit_id, edit_u).await?;
+	}
 	
 	let edit = EditBmc::get(&ctx, &app_state, edit_id).await?;
+	let validators = Validators::of(&edit);
 
 	
 	// -- Build response
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Edit updated successfully")),
+		Some("Edit updated successfully".to_string()),
 		Some(CustomResponseData::Item(edit))
 	);
 
-	Ok((StatusCode::OK, Json(response)))
+	Ok((StatusCode::OK, validators.headers(), Json(response)).into_response())
 	
 }
 
@@ -386,7 +413,7 @@ pub async fn handler_edit_delete(
 	if edit.editor_id != user_id {
 		let error = ServerError::DeleteFail(
 			TABLE_NAME.to_string(),
-			format!("You do not have permission to delete this edit"),
+			"You do not have permission to delete this edit".to_string(),
 			CrudError::UNAUTHORIZED
 		);
 		return Err(error);
@@ -402,13 +429,13 @@ pub async fn handler_edit_delete(
 	}
 	
 	// -- Update edit
-	let _result = EditBmc::delete(&ctx, &app_state, edit_id).await?;
+	EditBmc::delete(&ctx, &app_state, edit_id).await?;
 	
 	
 	// -- Build response
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Edit deleted successfully")),
+		Some("Edit deleted successfully".to_string()),
 		None
 	);
 
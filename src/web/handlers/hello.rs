@@ -1,4 +1,6 @@
-use axum::{response::{IntoResponse, Html}, extract::{Query, Path}};
+use axum::response::{IntoResponse, Html};
+
+use crate::web::{Path, Query};
 use tracing::debug;
 
 use crate::web::HelloParams;
@@ -0,0 +1,559 @@
+use axum::extract::multipart::{MultipartError, MultipartRejection};
+use axum::extract::{Multipart, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use axum_extra::extract::WithRejection;
+use bytes::{Bytes, BytesMut};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use validator::Validate;
+
+use crate::config::{config, ImageFormatKind};
+use crate::crypt::url_sig;
+use crate::ctx::Ctx;
+use crate::models::media::{imaging, Media, MediaBmc, MediaError, MediaRendition};
+use crate::models::post::PostBmc;
+use crate::models::{AppState, ModelError};
+use crate::web::conditional::Validators;
+use crate::web::custom_response::{CustomResponse, CustomResponseData};
+use crate::web::error::CrudError;
+use crate::web::{IncomingServerRequest, Path, Query, ServerError, ServerResponse, ServerResult, ValidJson};
+
+const TABLE_NAME: &str = "MEDIA";
+/// Multipart field of the uploaded file
+const FILE_FIELD: &str = "file";
+const FILE_NAME_MAX_LEN: usize = 256;
+/// Media content never changes (a new upload is a new media)
+const CONTENT_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Handler to upload a media, the `file` field of a `multipart/form-data` body
+pub async fn handler_media_upload(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	multipart: Result<Multipart, MultipartRejection>,
+) -> ServerResponse<Media> {
+	debug!("{:<12} - handler_media_upload", "HANDLER");
+
+	let media_config = &config().media;
+	let mut multipart = multipart.map_err(|ex| ServerError::MediaUploadInvalid { detail: ex.body_text() })?;
+
+	// -- Read the file, up to the max size.
+	let mut file = None;
+	while let Some(mut field) = multipart.next_field().await.map_err(upload_error)? {
+		if field.name() != Some(FILE_FIELD) {
+			continue;
+		}
+
+		let file_name = clean_file_name(field.file_name().unwrap_or_default());
+		let declared_type = field.content_type().map(str::to_string);
+		let mut content = BytesMut::new();
+		while let Some(chunk) = field.chunk().await.map_err(upload_error)? {
+			if (content.len() + chunk.len()) as u64 > media_config.max_size_bytes {
+				return Err(ServerError::MediaTooLarge { max_size_bytes: media_config.max_size_bytes });
+			}
+			content.extend_from_slice(&chunk);
+		}
+
+		file = Some((file_name, declared_type, content.freeze()));
+		break;
+	}
+	let Some((file_name, declared_type, content)) = file else {
+		return Err(ServerError::MediaUploadInvalid { detail: format!("Missing `{FILE_FIELD}` field") });
+	};
+
+	// -- Check the type, as detected from the content.
+	let mime_type = detect_mime_type(&content, declared_type.as_deref());
+	if !media_config.allowed_types.iter().any(|allowed| allowed == mime_type) {
+		return Err(ServerError::MediaTypeNotAllowed { mime_type: mime_type.to_string() });
+	}
+
+	let id = MediaBmc::upload(&ctx, &app_state, &file_name, mime_type, content).await?;
+	let media = MediaBmc::get(&ctx, &app_state, id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Media uploaded successfully".to_string()),
+		Some(CustomResponseData::Item(media))
+	);
+
+	Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Handler to list the media of the logged in author
+pub async fn handler_media_list(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+) -> ServerResponse<Media> {
+	debug!("{:<12} - handler_media_list", "HANDLER");
+
+	let media = MediaBmc::list_for_owner(&ctx, &app_state, ctx.user_id()).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Media retrieved successfully".to_string()),
+		Some(CustomResponseData::Collection(media))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to get a media (its metadata)
+pub async fn handler_media_get(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(id): Path<i64>,
+) -> ServerResponse<Media> {
+	debug!("{:<12} - handler_media_get", "HANDLER");
+
+	let media = MediaBmc::get(&ctx, &app_state, id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Media retrieved successfully".to_string()),
+		Some(CustomResponseData::Item(media))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to get the content of a media, public (e.g., for the `<img>` of a post)
+pub async fn handler_media_content(
+	State(app_state): State<AppState>,
+	Path(id): Path<i64>,
+	headers: HeaderMap,
+) -> ServerResult<Response> {
+	debug!("{:<12} - handler_media_content", "HANDLER");
+
+	let media = MediaBmc::get(&Ctx::root_ctx(), &app_state, id).await?;
+
+	let validators = Validators::of(&media);
+	if validators.is_not_modified(&headers) {
+		return Ok(validators.not_modified());
+	}
+
+	let content = MediaBmc::content(&app_state, &media).await?;
+
+	content_response(&validators, &media.mime_type, content_disposition(&media.file_name), content)
+}
+
+/// Handler to delete a media, and its references from posts
+pub async fn handler_media_delete(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(id): Path<i64>,
+) -> ServerResponse<()> {
+	debug!("{:<12} - handler_media_delete", "HANDLER");
+
+	let media = MediaBmc::get(&ctx, &app_state, id).await?;
+
+	if media.owner_id != ctx.user_id() {
+		return Err(
+			ServerError::DeleteFail(
+				TABLE_NAME.to_string(),
+				"Only media owner can delete media".to_string(),
+				CrudError::UNAUTHORIZED
+			)
+		)
+	}
+
+	MediaBmc::delete(&ctx, &app_state, &media).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Media deleted successfully".to_string()),
+		None
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+// region:    --- Renditions
+#[derive(Debug, Deserialize)]
+/// On-demand resize, fitting within `w` x `h` (never enlarged)
+pub struct ResizeParams {
+	pub w: u32,
+	pub h: u32,
+	#[serde(default = "default_resize_format")]
+	pub format: ImageFormatKind,
+	/// Signature of the other parameters (see `handler_media_resize_url`)
+	pub sig: Option<String>,
+}
+
+fn default_resize_format() -> ImageFormatKind {
+	ImageFormatKind::Webp
+}
+
+impl ResizeParams {
+	/// The signed parameters, e.g., `media=1000&w=200&h=200&format=webp`
+	fn signed_params(&self, media_id: i64) -> String {
+		format!("media={media_id}&w={}&h={}&format={}", self.w, self.h, format_name(self.format))
+	}
+
+	/// The rendition name of the resize, e.g., `resize-200x200-webp`
+	fn rendition_name(&self) -> String {
+		format!("resize-{}x{}-{}", self.w, self.h, format_name(self.format))
+	}
+
+	fn check_dimensions(&self) -> ServerResult<()> {
+		let max = config().media.resize_max_dimension;
+		if !(1..=max).contains(&self.w) || !(1..=max).contains(&self.h) {
+			return Err(ServerError::RequestQueryInvalid { detail: format!("`w` and `h` must be within 1 and {max}") });
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Debug, Serialize)]
+pub struct MediaResizeUrl {
+	pub url: String,
+}
+
+/// Handler to list the renditions of a media
+pub async fn handler_media_renditions_list(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(id): Path<i64>,
+) -> ServerResponse<MediaRendition> {
+	debug!("{:<12} - handler_media_renditions_list", "HANDLER");
+
+	// (checks the media exists)
+	MediaBmc::get(&ctx, &app_state, id).await?;
+	let renditions = MediaBmc::list_renditions(&ctx, &app_state, id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Media renditions retrieved successfully".to_string()),
+		Some(CustomResponseData::Collection(renditions))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to get the content of a rendition, public
+pub async fn handler_media_rendition_content(
+	State(app_state): State<AppState>,
+	Path((id, name)): Path<(i64, String)>,
+	headers: HeaderMap,
+) -> ServerResult<Response> {
+	debug!("{:<12} - handler_media_rendition_content", "HANDLER");
+
+	let rendition = MediaBmc::get_rendition(&Ctx::root_ctx(), &app_state, id, &name)
+		.await?
+		.ok_or(ModelError::EntityNotFound { entity: "media_renditions", id })?;
+
+	let validators = Validators::of(&rendition);
+	if validators.is_not_modified(&headers) {
+		return Ok(validators.not_modified());
+	}
+
+	let content = MediaBmc::rendition_content(&app_state, &rendition).await?;
+
+	content_response(&validators, &rendition.mime_type, HeaderValue::from_static("inline"), content)
+}
+
+/// Handler to get a signed on-demand resize URL of an own image
+pub async fn handler_media_resize_url(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(id): Path<i64>,
+	Query(params): Query<ResizeParams>,
+) -> ServerResponse<MediaResizeUrl> {
+	debug!("{:<12} - handler_media_resize_url", "HANDLER");
+
+	let media = MediaBmc::get(&ctx, &app_state, id).await?;
+
+	if media.owner_id != ctx.user_id() {
+		return Err(
+			ServerError::CreateFail(
+				TABLE_NAME.to_string(),
+				"Only media owner can get resize URLs".to_string(),
+				CrudError::UNAUTHORIZED
+			)
+		)
+	}
+	if imaging::image_format(&media.mime_type).is_none() {
+		return Err(ModelError::from(MediaError::ImageTypeNotProcessed(media.mime_type)).into());
+	}
+	params.check_dimensions()?;
+
+	let signed_params = params.signed_params(id);
+	let sig = url_sig::sign_url_params(&signed_params).map_err(ModelError::from)?;
+	let url = format!(
+		"/api/media/{id}/resize?w={}&h={}&format={}&sig={sig}",
+		params.w, params.h, format_name(params.format)
+	);
+
+	let response = CustomResponse::new(
+		true,
+		Some("Media resize URL signed successfully".to_string()),
+		Some(CustomResponseData::Item(MediaResizeUrl { url }))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to get an on-demand resize (signed URL), public, generated on the first request
+pub async fn handler_media_resize(
+	State(app_state): State<AppState>,
+	Path(id): Path<i64>,
+	Query(params): Query<ResizeParams>,
+	headers: HeaderMap,
+) -> ServerResult<Response> {
+	debug!("{:<12} - handler_media_resize", "HANDLER");
+
+	let sig = params.sig.as_deref().ok_or(ServerError::MediaUrlSignatureInvalid)?;
+	url_sig::validate_url_params_sig(&params.signed_params(id), sig)
+		.map_err(|_| ServerError::MediaUrlSignatureInvalid)?;
+	params.check_dimensions()?;
+
+	let ctx = Ctx::root_ctx();
+	let name = params.rendition_name();
+	let rendition = match MediaBmc::get_rendition(&ctx, &app_state, id, &name).await? {
+		Some(rendition) => rendition,
+		None => {
+			let media = MediaBmc::get(&ctx, &app_state, id).await?;
+			let format = imaging::image_format(&media.mime_type)
+				.ok_or_else(|| MediaError::ImageTypeNotProcessed(media.mime_type.clone()))
+				.map_err(ModelError::from)?;
+
+			let content = MediaBmc::content(&app_state, &media).await?;
+			let max_pixels = config().media.max_image_pixels;
+			let (w, h, resize_format) = (params.w, params.h, params.format);
+			let rendered = app_state.media_processor.run(move || {
+				let image = imaging::decode(&content, format, max_pixels)?;
+				imaging::render(&image, Some(w), Some(h), false, resize_format)
+			}).await.map_err(ModelError::from)?;
+
+			MediaBmc::save_rendition(&ctx, &app_state, &media, &name, resize_format.mime_type(), rendered).await?
+		},
+	};
+
+	let validators = Validators::of(&rendition);
+	if validators.is_not_modified(&headers) {
+		return Ok(validators.not_modified());
+	}
+
+	let content = MediaBmc::rendition_content(&app_state, &rendition).await?;
+
+	content_response(&validators, &rendition.mime_type, HeaderValue::from_static("inline"), content)
+}
+// endregion: --- Renditions
+
+// region:    --- Post Media
+#[derive(Debug, Deserialize, Validate)]
+pub struct PostMediaForSet {
+	/// In order, only the post author's media
+	#[validate(length(max = 64))]
+	pub media_ids: Vec<i64>,
+}
+
+/// Handler to list the media referenced by a post
+pub async fn handler_post_media_list(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(post_id): Path<i64>,
+) -> ServerResponse<Media> {
+	debug!("{:<12} - handler_post_media_list", "HANDLER");
+
+	// (checks the post exists)
+	PostBmc::get(&ctx, &app_state, post_id).await?;
+	let media = MediaBmc::list_for_post(&ctx, &app_state, post_id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Post media retrieved successfully".to_string()),
+		Some(CustomResponseData::Collection(media))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to set the media referenced by a post
+pub async fn handler_post_media_set(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(post_id): Path<i64>,
+	WithRejection(ValidJson(data), _): IncomingServerRequest<PostMediaForSet>,
+) -> ServerResponse<Media> {
+	debug!("{:<12} - handler_post_media_set", "HANDLER");
+
+	let post = PostBmc::get(&ctx, &app_state, post_id).await?;
+	let author_id = ctx.user_id();
+
+	if post.author_id != author_id {
+		return Err(
+			ServerError::UpdateFail(
+				"POST".to_string(),
+				"Only post author can set post media".to_string(),
+				CrudError::UNAUTHORIZED
+			)
+		)
+	}
+
+	let mut media_ids = data.media_ids;
+	dedup_keep_order(&mut media_ids);
+	for &media_id in &media_ids {
+		let media = MediaBmc::get(&ctx, &app_state, media_id).await?;
+		if media.owner_id != author_id {
+			return Err(
+				ServerError::UpdateFail(
+					"POST".to_string(),
+					format!("Media {media_id} is not owned by the post author"),
+					CrudError::FORBIDDEN
+				)
+			)
+		}
+	}
+
+	MediaBmc::set_for_post(&ctx, &app_state, post_id, &media_ids).await?;
+	let media = MediaBmc::list_for_post(&ctx, &app_state, post_id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Post media set successfully".to_string()),
+		Some(CustomResponseData::Collection(media))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+// endregion: --- Post Media
+
+// region:    --- Support
+/// Media content (immutable, see `CONTENT_CACHE_CONTROL`), with its validators.
+fn content_response(
+	validators: &Validators,
+	mime_type: &str,
+	disposition: HeaderValue,
+	content: Bytes,
+) -> ServerResult<Response> {
+	let content_headers = [
+		(header::CONTENT_TYPE, HeaderValue::from_str(mime_type).map_err(|_| ServerError::InternalServerError)?),
+		(header::CACHE_CONTROL, HeaderValue::from_static(CONTENT_CACHE_CONTROL)),
+		(header::CONTENT_DISPOSITION, disposition),
+	];
+
+	Ok((StatusCode::OK, validators.headers(), content_headers, content).into_response())
+}
+
+/// e.g., `webp`
+fn format_name(format: ImageFormatKind) -> &'static str {
+	format.mime_type().trim_start_matches("image/")
+}
+
+fn upload_error(err: MultipartError) -> ServerError {
+	match err.status() {
+		StatusCode::PAYLOAD_TOO_LARGE => ServerError::MediaTooLarge { max_size_bytes: config().media.max_size_bytes },
+		_ => ServerError::MediaUploadInvalid { detail: err.body_text() },
+	}
+}
+
+/// Magic numbers of the types recognized from the content
+const SNIFFED_TYPES: &[(&[u8], &str)] = &[
+	(b"\x89PNG\r\n\x1a\n", "image/png"),
+	(b"\xff\xd8\xff", "image/jpeg"),
+	(b"GIF87a", "image/gif"),
+	(b"GIF89a", "image/gif"),
+	(b"%PDF-", "application/pdf"),
+];
+
+/// The type recognized from the content, otherwise the declared one (unless it claims a recognizable type),
+/// otherwise `application/octet-stream`.
+fn detect_mime_type<'a>(content: &[u8], declared_type: Option<&'a str>) -> &'a str {
+	let is_webp = content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP";
+	if is_webp {
+		return "image/webp";
+	}
+	if let Some((_, mime_type)) = SNIFFED_TYPES.iter().find(|(magic, _)| content.starts_with(magic)) {
+		return mime_type;
+	}
+
+	let declared_type = declared_type.and_then(|value| value.parse::<mime::Mime>().ok());
+	match declared_type {
+		Some(mime) if mime.essence_str() == "image/webp" || SNIFFED_TYPES.iter().any(|(_, t)| *t == mime.essence_str()) => {
+			"application/octet-stream"
+		},
+		// (the allowed types being from the config, any other is refused)
+		Some(mime) => config()
+			.media
+			.allowed_types
+			.iter()
+			.find(|allowed| allowed.as_str() == mime.essence_str())
+			.map(String::as_str)
+			.unwrap_or("application/octet-stream"),
+		None => "application/octet-stream",
+	}
+}
+
+/// The last path segment of the client file name, without control characters, e.g., `photo.png`
+fn clean_file_name(file_name: &str) -> String {
+	let file_name: String = file_name
+		.rsplit(['/', '\\'])
+		.next()
+		.unwrap_or_default()
+		.chars()
+		.filter(|c| !c.is_control())
+		.take(FILE_NAME_MAX_LEN)
+		.collect();
+	let file_name = file_name.trim();
+
+	if file_name.is_empty() { "file".to_string() } else { file_name.to_string() }
+}
+
+/// `inline`, with the file name reduced to safe ASCII characters
+fn content_disposition(file_name: &str) -> HeaderValue {
+	let file_name: String = file_name
+		.chars()
+		.map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+		.collect();
+
+	HeaderValue::from_str(&format!("inline; filename=\"{file_name}\"")).unwrap_or(HeaderValue::from_static("inline"))
+}
+
+fn dedup_keep_order(ids: &mut Vec<i64>) {
+	let mut seen = std::collections::HashSet::new();
+	ids.retain(|id| seen.insert(*id));
+}
+// endregion: --- Support
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[test]
+	fn test_detect_mime_type() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_png = b"\x89PNG\r\n\x1a\n fx png";
+		let fx_webp = b"RIFF\x00\x00\x00\x00WEBPVP8 fx";
+		let fx_text = b"fx text";
+
+		// -- Exec & Check
+		assert_eq!(detect_mime_type(fx_png, Some("application/octet-stream")), "image/png");
+		assert_eq!(detect_mime_type(fx_webp, None), "image/webp");
+		assert_eq!(detect_mime_type(b"%PDF-1.7", Some("text/plain")), "application/pdf");
+		// (claims a recognizable type, but is not)
+		assert_eq!(detect_mime_type(fx_text, Some("image/png")), "application/octet-stream");
+		// (not in the default `media.allowed_types`)
+		assert_eq!(detect_mime_type(fx_text, Some("text/plain")), "application/octet-stream");
+		assert_eq!(detect_mime_type(fx_text, None), "application/octet-stream");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_clean_file_name() -> Result<()> {
+		// -- Exec & Check
+		assert_eq!(clean_file_name("photo.png"), "photo.png");
+		assert_eq!(clean_file_name("../../etc/passwd"), "passwd");
+		assert_eq!(clean_file_name("C:\\fx\\photo 01.jpg"), "photo 01.jpg");
+		assert_eq!(clean_file_name("fx\u{0}\n.png"), "fx.png");
+		assert_eq!(clean_file_name(""), "file");
+		assert_eq!(content_disposition("photo 01 é.png"), "inline; filename=\"photo_01__.png\"");
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -2,4 +2,7 @@ pub mod hello;
 pub mod author;
 pub mod post;
 pub mod edit;
+pub mod two_factor;
+pub mod media;
+pub mod webhook;
 pub mod routes_static;
\ No newline at end of file
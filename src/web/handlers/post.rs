@@ -1,17 +1,20 @@
-use axum::{extract::{Path, Query, State}, http::StatusCode, Extension, Json};
+use axum::{extract::State, http::{HeaderMap, StatusCode}, response::{IntoResponse, Response}, Extension, Json};
 use axum_extra::extract::WithRejection;
 use tracing::debug;
 
-use crate::{ctx::Ctx, models::{post::{self, Post, PostBmc, PostForCreate, PostForUpdate}, AppState}, web::{error::CrudError, IncomingServerRequest, ServerResponse}};
-use crate::web::custom_extractor::ApiError;
+use crate::{ctx::Ctx, models::{post::{self, Post, PostBmc, PostForCreate, PostForUpdate}, AppState}, web::{error::CrudError, IncomingServerRequest, ServerResponse, ValidJson}};
+use crate::web::{Path, Query};
 use crate::web::{error::{ServerResult, ServerError}, custom_response::{CustomResponse, CustomResponseData}};
+use crate::web::conditional::Validators;
+use crate::config::config;
+use crate::models::webhook::{WebhookBmc, WebhookEventType};
 
 const TABLE_NAME: &str = "POST";
 /// Handler to create a post
 pub async fn handler_post_create(
 	State(app_state): State<AppState>,
 	ctx: Ctx,
-	WithRejection((Json(data)), _): IncomingServerRequest<PostForCreate>,
+	WithRejection(ValidJson(data), _): IncomingServerRequest<PostForCreate>,
 	) -> ServerResponse<Post> {
 	debug!("{:<12} - handler_post_create", "HANDLER");
 	
@@ -20,10 +23,11 @@ pub async fn handler_post_create(
 	let id = PostBmc::create(&ctx, &app_state, data).await?;
 
 	let post = PostBmc::get(&ctx, &app_state, id).await?;
+	WebhookBmc::emit(&app_state, WebhookEventType::PostCreated, &post).await;
 
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Post created successfully")),
+		Some("Post created successfully".to_string()),
 		Some(CustomResponseData::Item(post))
 	);
 
@@ -39,7 +43,7 @@ pub async fn handler_post_list(
 
 	let response = CustomResponse::new(
 		true,
-		Some(format!("Posts retrieved successfully")),
+		Some("Posts retrieved successfully".to_string()),
 		Some(CustomResponseData::Collection(posts))
 	);
 
@@ -50,18 +54,24 @@ pub async fn handler_post_list(
 pub async fn handler_post_get(
 	ctx: Ctx,
 	State(app_state): State<AppState>,
-	Path(id): Path<i64>
-) -> ServerResponse<Post> {
+	Path(id): Path<i64>,
+	headers: HeaderMap
+) -> ServerResult<Response> {
 
 	let post: Post = PostBmc::get(&ctx, &app_state, id).await?;
 
+	let validators = Validators::of(&post);
+	if validators.is_not_modified(&headers) {
+		return Ok(validators.not_modified());
+	}
+
 	let response  = CustomResponse::new(
 		true,
-		Some(format!("Post retrieved successfully")),
+		Some("Post retrieved successfully".to_string()),
 		Some(CustomResponseData::Item(post))
 	);
 
-	Ok((StatusCode::OK, Json(response)))
+	Ok((StatusCode::OK, validators.headers(), Json(response)).into_response())
 }
 
 /// Handler to update a specific post
@@ -69,8 +79,9 @@ pub async fn handler_post_update(
 	ctx: Ctx,
 	State(app_state): State<AppState>,
 	Path(id): Path<i64>,
-	WithRejection((Json(post_e)), _): IncomingServerRequest<PostForUpdate>
-	) -> ServerResponse<Post> {
+	headers: HeaderMap,
+	WithRejection(ValidJson(post_e), _): IncomingServerRequest<PostForUpdate>
+	) -> ServerResult<Response> {
 	debug!("{:<12} - handler_post_update", "HANDLER");
 	let post = PostBmc::get(&ctx, &app_state, id).await?;
 
@@ -86,17 +97,24 @@ pub async fn handler_post_update(
 		)
 	}
 
-	let _result = PostBmc::update(&ctx, &app_state, id, post_e).await?;
+	// Concurrent editors must update the version they got (`If-Match`)
+	if Validators::of(&post).check_if_match(&headers, config().server.require_if_match)? {
+		PostBmc::update_if_unmodified(&ctx, &app_state, id, post_e, post.updated_at).await?;
+	} else {
+		PostBmc::update(&ctx, &app_state, id, post_e).await?;
+	}
 
 	let post = PostBmc::get(&ctx, &app_state, id).await?;
+	WebhookBmc::emit(&app_state, WebhookEventType::PostUpdated, &post).await;
+	let validators = Validators::of(&post);
 
 	let response  = CustomResponse::new(
 		true,
-		Some(format!("Post updated successfully")),
+		Some("Post updated successfully".to_string()),
 		Some(CustomResponseData::Item(post))
 	);
 
-	Ok((StatusCode::ACCEPTED, Json(response)))
+	Ok((StatusCode::ACCEPTED, validators.headers(), Json(response)).into_response())
 }
 
 /// Handler to delete a post
@@ -122,11 +140,12 @@ pub async fn handler_post_delete(
 		)
 	}
 
-	let _result = PostBmc::delete(&ctx, &app_state, id).await?;
+	PostBmc::delete(&ctx, &app_state, id).await?;
+	WebhookBmc::emit(&app_state, WebhookEventType::PostDeleted, &post).await;
 
 	let response  = CustomResponse::new(
 		true,
-		Some(format!("Post deleted successfully")),
+		Some("Post deleted successfully".to_string()),
 		None
 	);
 
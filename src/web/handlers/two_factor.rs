@@ -0,0 +1,160 @@
+use axum::{extract::State, http::StatusCode, Json};
+use axum_extra::extract::WithRejection;
+use tracing::debug;
+
+use crate::{crypt::totp, ctx::Ctx, models::{author::{AuthorBmc, AuthorForTotp}, recovery_code::RecoveryCodeBmc, AppState}, web::{auth::{TwoFactorCodePayload, TwoFactorEnrollment}, error::CrudError, IncomingServerRequest, ServerResponse, ValidJson}};
+use crate::web::{custom_response::{CustomResponse, CustomResponseData}, error::{ServerError, ServerResult}};
+
+const TABLE_NAME: &str = "AUTHOR";
+
+/// Handler to start two-factor enrollment, generating a new TOTP secret
+pub async fn handler_2fa_enroll(
+	ctx: Ctx,
+	State(app_state): State<AppState>,
+	) -> ServerResponse<TwoFactorEnrollment>
+	{
+
+	debug!("{:<12} - handler_2fa_enroll", "HANDLER");
+
+	// -- Retrieve info
+	let author: AuthorForTotp = AuthorBmc::get(&ctx, &app_state, ctx.user_id()).await?;
+
+	// -- Checks & Guards
+	if author.totp_enabled {
+		let error = ServerError::UpdateFail(
+			TABLE_NAME.to_string(),
+			"Two-factor authentication is already enabled".to_string(),
+			CrudError::CONFLICT
+		);
+		return Err(error);
+	}
+
+	// -- Store the pending secret
+	let secret = totp::generate_totp_secret();
+	AuthorBmc::set_totp_pending(&ctx, &app_state, author.id, &secret).await?;
+
+	// -- Build response
+	let enrollment = TwoFactorEnrollment {
+		otpauth_uri: totp::totp_otpauth_uri(&secret, &author.email),
+		secret
+	};
+
+	let response = CustomResponse::new(
+		true,
+		Some("Two-factor enrollment started, confirm it with a code from your authenticator app".to_string()),
+		Some(CustomResponseData::Item(enrollment))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to confirm two-factor enrollment, returning the (one time displayed) recovery codes
+pub async fn handler_2fa_confirm(
+	ctx: Ctx,
+	State(app_state): State<AppState>,
+	WithRejection(ValidJson(data), _): IncomingServerRequest<TwoFactorCodePayload>,
+	) -> ServerResponse<String>
+	{
+
+	debug!("{:<12} - handler_2fa_confirm", "HANDLER");
+
+	// -- Retrieve info
+	let author: AuthorForTotp = AuthorBmc::get(&ctx, &app_state, ctx.user_id()).await?;
+
+	// -- Checks & Guards
+	if author.totp_enabled {
+		let error = ServerError::UpdateFail(
+			TABLE_NAME.to_string(),
+			"Two-factor authentication is already enabled".to_string(),
+			CrudError::CONFLICT
+		);
+		return Err(error);
+	}
+
+	let Some(totp_secret) = &author.totp_secret else {
+		let error = ServerError::UpdateFail(
+			TABLE_NAME.to_string(),
+			"Two-factor enrollment has not been started".to_string(),
+			CrudError::BAD_REQUEST
+		);
+		return Err(error);
+	};
+
+	let step = totp::validate_totp(totp_secret, &data.code, None).map_err(|_| invalid_code_error())?;
+
+	// -- Enable two-factor, with a new set of recovery codes
+	let recovery_codes = totp::generate_recovery_codes();
+	let code_hashes = recovery_codes
+		.iter()
+		.map(|code| totp::hash_recovery_code(code, &author.password_salt.to_string()))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	RecoveryCodeBmc::replace_for_author(&ctx, &app_state, author.id, code_hashes).await?;
+	AuthorBmc::enable_totp(&ctx, &app_state, author.id, step).await?;
+
+	// -- Build response
+	let response = CustomResponse::new(
+		true,
+		Some("Two-factor authentication enabled, store these recovery codes safely".to_string()),
+		Some(CustomResponseData::Collection(recovery_codes))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to disable two-factor, with a TOTP code or a recovery code
+pub async fn handler_2fa_disable(
+	ctx: Ctx,
+	State(app_state): State<AppState>,
+	WithRejection(ValidJson(data), _): IncomingServerRequest<TwoFactorCodePayload>,
+	) -> ServerResponse<()>
+	{
+
+	debug!("{:<12} - handler_2fa_disable", "HANDLER");
+
+	// -- Retrieve info
+	let author: AuthorForTotp = AuthorBmc::get(&ctx, &app_state, ctx.user_id()).await?;
+
+	// -- Checks & Guards
+	let (true, Some(totp_secret)) = (author.totp_enabled, &author.totp_secret) else {
+		let error = ServerError::UpdateFail(
+			TABLE_NAME.to_string(),
+			"Two-factor authentication is not enabled".to_string(),
+			CrudError::BAD_REQUEST
+		);
+		return Err(error);
+	};
+
+	let is_valid = match totp::validate_totp(totp_secret, &data.code, author.totp_last_step) {
+		Ok(step) => AuthorBmc::consume_totp_step(&app_state, author.id, step).await?,
+		Err(_) => {
+			let code_hash = totp::hash_recovery_code(&data.code, &author.password_salt.to_string())?;
+			RecoveryCodeBmc::consume(&app_state, author.id, &code_hash).await?
+		}
+	};
+
+	if !is_valid {
+		return Err(invalid_code_error());
+	}
+
+	// -- Disable two-factor
+	AuthorBmc::disable_totp(&ctx, &app_state, author.id).await?;
+	RecoveryCodeBmc::delete_for_author(&ctx, &app_state, author.id).await?;
+
+	// -- Build response
+	let response = CustomResponse::new(
+		true,
+		Some("Two-factor authentication disabled".to_string()),
+		None
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+fn invalid_code_error() -> ServerError {
+	ServerError::UpdateFail(
+		TABLE_NAME.to_string(),
+		"Invalid two-factor code".to_string(),
+		CrudError::BAD_REQUEST
+	)
+}
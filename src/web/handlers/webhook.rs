@@ -0,0 +1,242 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use axum_extra::extract::WithRejection;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use validator::Validate;
+
+use crate::crypt::webhook_sig::generate_webhook_secret;
+use crate::ctx::Ctx;
+use crate::models::webhook::{
+	WebhookBmc, WebhookDelivery, WebhookDeliveryAttempt, WebhookDeliveryBmc, WebhookEventType, WebhookForCreate,
+	WebhookForUpdate, WebhookSubscription,
+};
+use crate::models::AppState;
+use crate::utils::validation::validate_http_url;
+use crate::web::custom_response::{CustomResponse, CustomResponseData};
+use crate::web::{IncomingServerRequest, Path, ServerResponse, ValidJson};
+
+// region:    --- Payloads
+#[derive(Debug, Deserialize, Validate)]
+pub struct WebhookForCreateRequestBody {
+	#[validate(length(max = 2048), custom = "validate_http_url")]
+	pub url: String,
+	#[validate(length(min = 1))]
+	pub event_types: Vec<WebhookEventType>,
+	/// Generated when not given
+	#[validate(length(min = 16, max = 256))]
+	pub secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct WebhookForUpdateRequestBody {
+	#[validate(length(max = 2048), custom = "validate_http_url")]
+	pub url: Option<String>,
+	#[validate(length(min = 1))]
+	pub event_types: Option<Vec<WebhookEventType>>,
+	pub active: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+/// A created subscription, with its secret (only sent back then)
+pub struct WebhookSubscriptionCreated {
+	#[serde(flatten)]
+	pub subscription: WebhookSubscription,
+	pub secret: String,
+}
+
+#[derive(Debug, Serialize)]
+/// A delivery, with its attempts
+pub struct WebhookDeliveryLog {
+	#[serde(flatten)]
+	pub delivery: WebhookDelivery,
+	pub log: Vec<WebhookDeliveryAttempt>,
+}
+// endregion: --- Payloads
+
+/// Handler to create a subscription (its secret generated when not given)
+pub async fn handler_webhook_create(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	WithRejection(ValidJson(data), _): IncomingServerRequest<WebhookForCreateRequestBody>,
+) -> ServerResponse<WebhookSubscriptionCreated> {
+	debug!("{:<12} - handler_webhook_create", "HANDLER");
+
+	let secret = data.secret.unwrap_or_else(generate_webhook_secret);
+	let data = WebhookForCreate {
+		owner_id: ctx.user_id(),
+		url: data.url,
+		secret: secret.clone(),
+		event_types: event_type_names(&data.event_types),
+	};
+
+	let id = WebhookBmc::create(&ctx, &app_state, data).await?;
+	let subscription = WebhookBmc::get(&ctx, &app_state, id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Webhook created successfully".to_string()),
+		Some(CustomResponseData::Item(WebhookSubscriptionCreated { subscription, secret }))
+	);
+
+	Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Handler to list the subscriptions
+pub async fn handler_webhook_list(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+) -> ServerResponse<WebhookSubscription> {
+	debug!("{:<12} - handler_webhook_list", "HANDLER");
+
+	let subscriptions = WebhookBmc::list(&ctx, &app_state).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Webhooks retrieved successfully".to_string()),
+		Some(CustomResponseData::Collection(subscriptions))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+pub async fn handler_webhook_get(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(id): Path<i64>,
+) -> ServerResponse<WebhookSubscription> {
+	debug!("{:<12} - handler_webhook_get", "HANDLER");
+
+	let subscription = WebhookBmc::get(&ctx, &app_state, id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Webhook retrieved successfully".to_string()),
+		Some(CustomResponseData::Item(subscription))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to update a subscription (e.g., `active: false` to pause it, its deliveries failing meanwhile)
+pub async fn handler_webhook_update(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(id): Path<i64>,
+	WithRejection(ValidJson(data), _): IncomingServerRequest<WebhookForUpdateRequestBody>,
+) -> ServerResponse<WebhookSubscription> {
+	debug!("{:<12} - handler_webhook_update", "HANDLER");
+
+	WebhookBmc::get(&ctx, &app_state, id).await?;
+
+	let data = WebhookForUpdate {
+		url: data.url,
+		event_types: data.event_types.as_deref().map(event_type_names),
+		active: data.active,
+	};
+	WebhookBmc::update(&ctx, &app_state, id, data).await?;
+	let subscription = WebhookBmc::get(&ctx, &app_state, id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Webhook updated successfully".to_string()),
+		Some(CustomResponseData::Item(subscription))
+	);
+
+	Ok((StatusCode::ACCEPTED, Json(response)))
+}
+
+/// Handler to delete a subscription, and its deliveries
+pub async fn handler_webhook_delete(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(id): Path<i64>,
+) -> ServerResponse<()> {
+	debug!("{:<12} - handler_webhook_delete", "HANDLER");
+
+	WebhookBmc::get(&ctx, &app_state, id).await?;
+	WebhookBmc::delete(&ctx, &app_state, id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Webhook deleted successfully".to_string()),
+		None
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+// region:    --- Deliveries
+/// Handler to list the latest deliveries of a subscription
+pub async fn handler_webhook_deliveries_list(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(id): Path<i64>,
+) -> ServerResponse<WebhookDelivery> {
+	debug!("{:<12} - handler_webhook_deliveries_list", "HANDLER");
+
+	WebhookBmc::get(&ctx, &app_state, id).await?;
+	let deliveries = WebhookDeliveryBmc::list_for_subscription(&ctx, &app_state, id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Webhook deliveries retrieved successfully".to_string()),
+		Some(CustomResponseData::Collection(deliveries))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to get a delivery, with its attempts
+pub async fn handler_webhook_delivery_get(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path((id, delivery_id)): Path<(i64, i64)>,
+) -> ServerResponse<WebhookDeliveryLog> {
+	debug!("{:<12} - handler_webhook_delivery_get", "HANDLER");
+
+	let delivery = WebhookDeliveryBmc::get(&ctx, &app_state, id, delivery_id).await?;
+	let log = WebhookDeliveryBmc::list_attempts(&ctx, &app_state, delivery_id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Webhook delivery retrieved successfully".to_string()),
+		Some(CustomResponseData::Item(WebhookDeliveryLog { delivery, log }))
+	);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to deliver again a delivery (e.g., `failed`), its attempts starting over
+pub async fn handler_webhook_redeliver(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path((id, delivery_id)): Path<(i64, i64)>,
+) -> ServerResponse<WebhookDelivery> {
+	debug!("{:<12} - handler_webhook_redeliver", "HANDLER");
+
+	WebhookDeliveryBmc::redeliver(&ctx, &app_state, id, delivery_id).await?;
+	let delivery = WebhookDeliveryBmc::get(&ctx, &app_state, id, delivery_id).await?;
+
+	let response = CustomResponse::new(
+		true,
+		Some("Webhook delivery queued".to_string()),
+		Some(CustomResponseData::Item(delivery))
+	);
+
+	Ok((StatusCode::ACCEPTED, Json(response)))
+}
+// endregion: --- Deliveries
+
+/// The names of `event_types`, deduplicated.
+fn event_type_names(event_types: &[WebhookEventType]) -> Vec<String> {
+	let mut names: Vec<String> = Vec::new();
+	for event_type in event_types {
+		if !names.iter().any(|name| name == event_type.as_ref()) {
+			names.push(event_type.as_ref().to_string());
+		}
+	}
+
+	names
+}
@@ -0,0 +1,386 @@
+//! Login brute-force protection
+//!
+//! Design:
+//!
+//! - Failed login attempts are tracked per client IP and per account (email),
+//!   whether or not the account exists, so the guard itself does not leak
+//!   which emails are registered.
+//! - After `free_attempts` failures, each new attempt must wait an exponentially
+//!   growing delay (1s, 2s, 4s, ...) since the last failure.
+//! - After `lockout_after` failures, the key is locked out for `lockout_duration`.
+//! - A successful login clears the account counter (not the IP one, so one valid
+//!   account cannot be used to reset the counter of an attacking IP).
+//! - The IP is the client one (see `web::client_ip`), so the clients behind a trusted proxy
+//!   are not locked out together.
+//! - At most `MAX_TRACKED` keys are tracked, the least recently failed ones evicted beyond
+//!   (the locked out ones last), so a flood of random emails cannot grow the memory.
+//!
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// region:    --- Policies
+/// Thresholds applied to a tracked key (IP or account)
+#[derive(Debug, Clone, Copy)]
+pub struct GuardPolicy {
+	/// Number of failures allowed before backoff kicks in
+	pub free_attempts: u32,
+	/// Number of failures after which the key is locked out
+	pub lockout_after: u32,
+	/// How long a lockout lasts (also the max backoff delay)
+	pub lockout_duration: Duration,
+	/// Failures older than this are forgotten
+	pub window: Duration,
+}
+
+const ACCOUNT_POLICY: GuardPolicy = GuardPolicy {
+	free_attempts: 3,
+	lockout_after: 10,
+	lockout_duration: Duration::from_secs(15 * 60),
+	window: Duration::from_secs(15 * 60),
+};
+
+const IP_POLICY: GuardPolicy = GuardPolicy {
+	free_attempts: 10,
+	lockout_after: 50,
+	lockout_duration: Duration::from_secs(15 * 60),
+	window: Duration::from_secs(15 * 60),
+};
+
+/// Above this number of tracked keys, stale entries are pruned on insert
+const PRUNE_THRESHOLD: usize = 10_000;
+/// Max tracked keys, beyond which the least recently failed ones are evicted (a tenth at once)
+const MAX_TRACKED: usize = 100_000;
+// endregion: --- Policies
+
+// region:    --- LoginGuard
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum GuardKey {
+	Ip(IpAddr),
+	Account(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Attempts {
+	fails: u32,
+	last_fail: Instant,
+	locked_until: Option<Instant>,
+}
+
+/// Outcome of recording a failed login
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailOutcome {
+	/// The failure was recorded, no lockout (yet)
+	Recorded,
+	/// The failure triggered a lockout, for the given number of seconds
+	LockedOut { retry_after_sec: u64 },
+}
+
+#[derive(Clone)]
+/// Tracks failed login attempts, shared through `AppState`
+pub struct LoginGuard {
+	attempts: Arc<Mutex<HashMap<GuardKey, Attempts>>>,
+	ip_policy: GuardPolicy,
+	account_policy: GuardPolicy,
+	max_tracked: usize,
+}
+
+impl Default for LoginGuard {
+	fn default() -> Self {
+		Self::new(IP_POLICY, ACCOUNT_POLICY)
+	}
+}
+
+impl LoginGuard {
+	/// Constructor
+	pub fn new(ip_policy: GuardPolicy, account_policy: GuardPolicy) -> Self {
+		Self {
+			attempts: Arc::new(Mutex::new(HashMap::new())),
+			ip_policy,
+			account_policy,
+			max_tracked: MAX_TRACKED,
+		}
+	}
+
+	/// Returns `Err(retry_after_sec)` if a login attempt from `ip` for `email`
+	/// must be rejected without checking credentials.
+	pub fn check(&self, ip: IpAddr, email: &str) -> Result<(), u64> {
+		self.check_at(ip, email, Instant::now())
+	}
+
+	/// Record a failed login attempt from `ip` for `email`.
+	pub fn record_failure(&self, ip: IpAddr, email: &str) -> FailOutcome {
+		self.record_failure_at(ip, email, Instant::now())
+	}
+
+	/// Record a successful login for `email`, clearing its failures.
+	pub fn record_success(&self, email: &str) {
+		let mut attempts = self.attempts.lock().unwrap();
+		attempts.remove(&GuardKey::Account(normalize_email(email)));
+	}
+}
+
+// region:    --- (private) Time-injected implementation
+impl LoginGuard {
+	fn keys(&self, ip: IpAddr, email: &str) -> [(GuardKey, GuardPolicy); 2] {
+		[
+			(GuardKey::Ip(ip), self.ip_policy),
+			(GuardKey::Account(normalize_email(email)), self.account_policy),
+		]
+	}
+
+	fn check_at(&self, ip: IpAddr, email: &str, now: Instant) -> Result<(), u64> {
+		let attempts = self.attempts.lock().unwrap();
+
+		let retry_after = self
+			.keys(ip, email)
+			.iter()
+			.filter_map(|(key, policy)| {
+				attempts.get(key).and_then(|a| blocked_until(a, policy, now))
+			})
+			.max();
+
+		match retry_after {
+			Some(until) => Err(ceil_secs(until - now)),
+			None => Ok(()),
+		}
+	}
+
+	fn record_failure_at(&self, ip: IpAddr, email: &str, now: Instant) -> FailOutcome {
+		let mut attempts = self.attempts.lock().unwrap();
+
+		if attempts.len() > PRUNE_THRESHOLD {
+			attempts.retain(|key, a| {
+				let window = match key {
+					GuardKey::Ip(_) => self.ip_policy.window,
+					GuardKey::Account(_) => self.account_policy.window,
+				};
+				!is_stale(a, window, now)
+			});
+		}
+		// (room for the two keys of this failure)
+		if attempts.len() + 2 > self.max_tracked {
+			evict_least_recent(&mut attempts, self.max_tracked - self.max_tracked / 10, now);
+		}
+
+		let mut outcome = FailOutcome::Recorded;
+
+		for (key, policy) in self.keys(ip, email) {
+			let entry = attempts.entry(key).or_insert(Attempts {
+				fails: 0,
+				last_fail: now,
+				locked_until: None,
+			});
+
+			// -- Forget failures that fell out of the window.
+			if is_stale(entry, policy.window, now) {
+				*entry = Attempts { fails: 0, last_fail: now, locked_until: None };
+			}
+
+			entry.fails += 1;
+			entry.last_fail = now;
+
+			if entry.fails >= policy.lockout_after && entry.locked_until.is_none() {
+				entry.locked_until = Some(now + policy.lockout_duration);
+				outcome = FailOutcome::LockedOut {
+					retry_after_sec: policy.lockout_duration.as_secs(),
+				};
+			}
+		}
+
+		outcome
+	}
+}
+
+/// Evict the least recently failed keys, the locked out ones last, down to `keep` keys.
+fn evict_least_recent(attempts: &mut HashMap<GuardKey, Attempts>, keep: usize, now: Instant) {
+	let mut by_eviction_order: Vec<(bool, Instant, GuardKey)> = attempts
+		.iter()
+		.map(|(key, a)| (a.locked_until.is_some_and(|until| until > now), a.last_fail, key.clone()))
+		.collect();
+	by_eviction_order.sort_unstable_by_key(|(locked, last_fail, _)| (*locked, *last_fail));
+
+	let evict_count = attempts.len().saturating_sub(keep);
+	for (_, _, key) in by_eviction_order.into_iter().take(evict_count) {
+		attempts.remove(&key);
+	}
+}
+
+fn blocked_until(attempts: &Attempts, policy: &GuardPolicy, now: Instant) -> Option<Instant> {
+	if let Some(locked_until) = attempts.locked_until {
+		return (locked_until > now).then_some(locked_until);
+	}
+
+	if attempts.fails < policy.free_attempts {
+		return None;
+	}
+
+	let until = attempts.last_fail + backoff(attempts.fails - policy.free_attempts, policy);
+	(until > now).then_some(until)
+}
+
+/// 1s, 2s, 4s, ... capped at the lockout duration
+fn backoff(excess_fails: u32, policy: &GuardPolicy) -> Duration {
+	Duration::from_secs(1u64 << excess_fails.min(20)).min(policy.lockout_duration)
+}
+
+/// An expired lockout gives a fresh start, otherwise failures expire with the window.
+fn is_stale(attempts: &Attempts, window: Duration, now: Instant) -> bool {
+	match attempts.locked_until {
+		Some(until) => until <= now,
+		None => now.duration_since(attempts.last_fail) > window,
+	}
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+	duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+fn normalize_email(email: &str) -> String {
+	email.trim().to_lowercase()
+}
+// endregion: --- (private) Time-injected implementation
+// endregion: --- LoginGuard
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::Ipv4Addr;
+
+	const FX_POLICY: GuardPolicy = GuardPolicy {
+		free_attempts: 2,
+		lockout_after: 5,
+		lockout_duration: Duration::from_secs(60),
+		window: Duration::from_secs(120),
+	};
+
+	const FX_LENIENT_POLICY: GuardPolicy = GuardPolicy {
+		free_attempts: 100,
+		lockout_after: 1000,
+		lockout_duration: Duration::from_secs(60),
+		window: Duration::from_secs(120),
+	};
+
+	fn fx_ip(last: u8) -> IpAddr {
+		IpAddr::V4(Ipv4Addr::new(10, 0, 0, last))
+	}
+
+	#[test]
+	fn test_login_guard_backoff_ok() {
+		// -- Setup & Fixtures
+		let guard = LoginGuard::new(FX_LENIENT_POLICY, FX_POLICY);
+		let now = Instant::now();
+		let fx_email = "e@mail";
+
+		// -- Exec & Check
+		for _ in 0..2 {
+			assert_eq!(guard.check_at(fx_ip(1), fx_email, now), Ok(()));
+			guard.record_failure_at(fx_ip(1), fx_email, now);
+		}
+
+		// Third failure in, backoff is 1s, then 2s.
+		assert_eq!(guard.check_at(fx_ip(1), fx_email, now), Err(1));
+		guard.record_failure_at(fx_ip(1), fx_email, now + Duration::from_secs(1));
+		assert_eq!(guard.check_at(fx_ip(1), fx_email, now + Duration::from_secs(1)), Err(2));
+		assert_eq!(guard.check_at(fx_ip(1), fx_email, now + Duration::from_secs(3)), Ok(()));
+
+		// Backoff is per account, whatever the IP.
+		assert_eq!(guard.check_at(fx_ip(2), " E@Mail ", now + Duration::from_secs(1)), Err(2));
+	}
+
+	#[test]
+	fn test_login_guard_lockout_ok() {
+		// -- Setup & Fixtures
+		let guard = LoginGuard::new(FX_LENIENT_POLICY, FX_POLICY);
+		let now = Instant::now();
+		let fx_email = "unknown@mail";
+
+		// -- Exec
+		let outcomes: Vec<FailOutcome> = (0..5)
+			.map(|_| guard.record_failure_at(fx_ip(1), fx_email, now))
+			.collect();
+
+		// -- Check
+		assert!(outcomes[..4].iter().all(|o| *o == FailOutcome::Recorded));
+		assert_eq!(outcomes[4], FailOutcome::LockedOut { retry_after_sec: 60 });
+		assert_eq!(guard.check_at(fx_ip(1), fx_email, now + Duration::from_secs(30)), Err(30));
+		assert_eq!(guard.check_at(fx_ip(1), fx_email, now + Duration::from_secs(60)), Ok(()));
+	}
+
+	#[test]
+	fn test_login_guard_ip_across_accounts_ok() {
+		// -- Setup & Fixtures
+		let guard = LoginGuard::new(FX_POLICY, FX_LENIENT_POLICY);
+		let now = Instant::now();
+
+		// -- Exec
+		for i in 0..5 {
+			guard.record_failure_at(fx_ip(1), &format!("user{i}@mail"), now);
+		}
+
+		// -- Check
+		assert_eq!(guard.check_at(fx_ip(1), "other@mail", now), Err(60));
+		assert_eq!(guard.check_at(fx_ip(2), "other@mail", now), Ok(()));
+	}
+
+	#[test]
+	fn test_login_guard_success_resets_account_ok() {
+		// -- Setup & Fixtures
+		let guard = LoginGuard::new(FX_LENIENT_POLICY, FX_POLICY);
+		let now = Instant::now();
+		let fx_email = "e@mail";
+		for _ in 0..3 {
+			guard.record_failure_at(fx_ip(1), fx_email, now);
+		}
+
+		// -- Exec
+		guard.record_success(fx_email);
+
+		// -- Check
+		assert_eq!(guard.check_at(fx_ip(1), fx_email, now), Ok(()));
+	}
+
+	#[test]
+	fn test_login_guard_max_tracked_ok() {
+		// -- Setup & Fixtures
+		let mut guard = LoginGuard::new(FX_LENIENT_POLICY, FX_POLICY);
+		guard.max_tracked = 20;
+		let now = Instant::now();
+		let fx_email = "locked@mail";
+		for _ in 0..5 {
+			guard.record_failure_at(fx_ip(1), fx_email, now);
+		}
+
+		// -- Exec (a flood of random emails)
+		for i in 0..100 {
+			guard.record_failure_at(fx_ip(2), &format!("fx-random-{i}@mail"), now + Duration::from_millis(i));
+		}
+
+		// -- Check
+		assert!(guard.attempts.lock().unwrap().len() <= 20);
+		assert_eq!(guard.check_at(fx_ip(3), fx_email, now + Duration::from_secs(1)), Err(59));
+	}
+
+	#[test]
+	fn test_login_guard_window_expiry_ok() {
+		// -- Setup & Fixtures
+		let guard = LoginGuard::new(FX_LENIENT_POLICY, FX_POLICY);
+		let now = Instant::now();
+		let fx_email = "e@mail";
+		for _ in 0..4 {
+			guard.record_failure_at(fx_ip(1), fx_email, now);
+		}
+
+		// -- Exec
+		let later = now + Duration::from_secs(121);
+		let outcome = guard.record_failure_at(fx_ip(1), fx_email, later);
+
+		// -- Check
+		assert_eq!(outcome, FailOutcome::Recorded);
+		assert_eq!(guard.check_at(fx_ip(1), fx_email, later), Ok(()));
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,174 @@
+//! Prometheus metrics
+//!
+//! Design:
+//!
+//! - The recorder is global (`init_metrics`, once at startup), metrics are recorded with the `metrics` macros
+//!   (no-op when the recorder is not installed, e.g., in unit tests).
+//! - Http metrics are recorded by `mw_metrics`, per route template (e.g., `/api/post/:id`), so that ids do not
+//!   blow up the label cardinality.
+//! - The db pool gauges are read when scraped (see `handler_metrics`).
+//!
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use ::metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Unit};
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use crate::error::{CoreError, CoreResult};
+use crate::models::AppState;
+
+// region:    --- Metric Names
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+
+pub const DB_POOL_CONNECTIONS: &str = "db_pool_connections";
+pub const DB_POOL_IDLE_CONNECTIONS: &str = "db_pool_idle_connections";
+pub const DB_POOL_MAX_CONNECTIONS: &str = "db_pool_max_connections";
+
+pub const AUTH_LOGINS_TOTAL: &str = "auth_logins_total";
+pub const AUTH_LOGIN_FAILURES_TOTAL: &str = "auth_login_failures_total";
+pub const AUTH_SIGNUPS_TOTAL: &str = "auth_signups_total";
+
+pub const EDITS_TOTAL: &str = "edits_total";
+
+/// Request latency buckets, in seconds
+const HTTP_DURATION_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1., 2.5, 5., 10.];
+// endregion: --- Metric Names
+
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the global Prometheus recorder (must be called once, before recording).
+pub fn init_metrics() -> CoreResult<()> {
+	let handle = PrometheusBuilder::new()
+		.set_buckets_for_metric(Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()), HTTP_DURATION_BUCKETS)
+		.and_then(|builder| builder.install_recorder())
+		.map_err(|ex| CoreError::MetricsInit(ex.to_string()))?;
+
+	PROMETHEUS_HANDLE
+		.set(handle)
+		.map_err(|_| CoreError::MetricsInit("recorder already initialized".to_string()))?;
+
+	describe_counter!(HTTP_REQUESTS_TOTAL, "Http requests, per method, route and status");
+	describe_histogram!(HTTP_REQUEST_DURATION_SECONDS, Unit::Seconds, "Http request latency, per method, route and status");
+	describe_gauge!(DB_POOL_CONNECTIONS, "Open db connections (idle and in use)");
+	describe_gauge!(DB_POOL_IDLE_CONNECTIONS, "Idle db connections");
+	describe_gauge!(DB_POOL_MAX_CONNECTIONS, "Max db connections of the pool");
+	describe_counter!(AUTH_LOGINS_TOTAL, "Successful logins, per method (password, oidc, or 2fa for the second step of either)");
+	describe_counter!(AUTH_LOGIN_FAILURES_TOTAL, "Failed logins, per reason");
+	describe_counter!(AUTH_SIGNUPS_TOTAL, "Signups, per method (password, oidc)");
+	describe_counter!(EDITS_TOTAL, "Edit suggestions lifecycle events (created, accepted, rejected)");
+
+	Ok(())
+}
+
+// region:    --- Recording
+pub fn record_http_request(method: &str, route: &str, status: StatusCode, duration: Duration) {
+	let labels = [
+		("method", method.to_string()),
+		("route", route.to_string()),
+		("status", status.as_u16().to_string()),
+	];
+
+	counter!(HTTP_REQUESTS_TOTAL, 1, &labels);
+	histogram!(HTTP_REQUEST_DURATION_SECONDS, duration.as_secs_f64(), &labels);
+}
+
+pub fn record_login(method: &'static str) {
+	counter!(AUTH_LOGINS_TOTAL, 1, "method" => method);
+}
+
+/// `reason` is the `ServerError` variant name (e.g., `LoginFailPwdNotMatching`)
+pub fn record_login_failure(reason: &str) {
+	counter!(AUTH_LOGIN_FAILURES_TOTAL, 1, "reason" => reason.to_string());
+}
+
+pub fn record_signup(method: &'static str) {
+	counter!(AUTH_SIGNUPS_TOTAL, 1, "method" => method);
+}
+
+pub enum EditEvent {
+	Created,
+	Accepted,
+	Rejected,
+}
+
+pub fn record_edit(event: EditEvent) {
+	let event = match event {
+		EditEvent::Created => "created",
+		EditEvent::Accepted => "accepted",
+		EditEvent::Rejected => "rejected",
+	};
+
+	counter!(EDITS_TOTAL, 1, "event" => event);
+}
+
+/// NOTE: sqlx does not expose the number of tasks waiting for a connection,
+///       saturation shows as `db_pool_idle_connections` at 0 with `db_pool_connections` at max.
+fn record_db_pool(app_state: &AppState) {
+	let pool = &app_state.pool;
+
+	gauge!(DB_POOL_CONNECTIONS, pool.size() as f64);
+	gauge!(DB_POOL_IDLE_CONNECTIONS, pool.num_idle() as f64);
+	gauge!(DB_POOL_MAX_CONNECTIONS, pool.options().get_max_connections() as f64);
+}
+// endregion: --- Recording
+
+// region:    --- Routes
+/// `GET /metrics`, on the main or the admin server (see `config().server.metrics_addr`)
+pub fn routes(app_state: AppState) -> Router {
+	Router::new()
+		.route("/metrics", get(handler_metrics))
+		.with_state(app_state)
+}
+
+async fn handler_metrics(State(app_state): State<AppState>) -> Response {
+	let Some(handle) = PROMETHEUS_HANDLE.get() else {
+		return StatusCode::NOT_FOUND.into_response();
+	};
+
+	record_db_pool(&app_state);
+
+	(
+		[(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+		handle.render(),
+	)
+		.into_response()
+}
+// endregion: --- Routes
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[test]
+	fn test_metrics_render_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		// (The only test installing the global recorder)
+		init_metrics()?;
+
+		// -- Exec
+		record_http_request("GET", "/api/post/:id", StatusCode::OK, Duration::from_millis(20));
+		record_http_request("GET", "/api/post/:id", StatusCode::OK, Duration::from_millis(200));
+		record_edit(EditEvent::Accepted);
+		let rendered = PROMETHEUS_HANDLE.get().unwrap().render();
+
+		// -- Check
+		assert!(rendered.contains(r#"http_requests_total{method="GET",route="/api/post/:id",status="200"} 2"#), "{rendered}");
+		assert!(
+			rendered.contains(r#"http_request_duration_seconds_bucket{method="GET",route="/api/post/:id",status="200",le="0.025"} 1"#),
+			"{rendered}"
+		);
+		assert!(rendered.contains(r#"edits_total{event="accepted"} 1"#), "{rendered}");
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
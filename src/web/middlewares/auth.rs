@@ -2,12 +2,13 @@ use crate::crypt::token::{validate_web_token, Token};
 use crate::ctx::Ctx;
 use crate::models::author::{AuthorBmc, AuthorForAuth};
 use crate::models::AppState;
-use crate::web::{set_token_cookie, AUTH_TOKEN};
+use crate::web::{named_cookie, set_csrf_cookie, set_token_cookie, AUTH_TOKEN, CSRF_TOKEN};
 use crate::web::{ServerError, ServerResult};
 use async_trait::async_trait;
 use axum::extract::{FromRequestParts, State};
+use axum::http::header::AUTHORIZATION;
 use axum::http::request::Parts;
-use axum::http::Request;
+use axum::http::{HeaderMap, Request};
 use axum::middleware::Next;
 use axum::response::Response;
 use serde::Serialize;
@@ -28,6 +29,23 @@ pub async fn mw_ctx_require<B>(
 	Ok(next.run(req).await)
 }
 
+/// Requires the `Ctx` be of an admin author (e.g., for the webhooks)
+pub async fn mw_admin_require<B>(
+	State(app_state): State<AppState>,
+	ctx: ServerResult<Ctx>,
+	req: Request<B>,
+	next: Next<B>,
+) -> ServerResult<Response> {
+	debug!("{:<12} - mw_admin_require", "MIDDLEWARE");
+
+	let ctx = ctx?;
+	if !AuthorBmc::is_admin(&ctx, &app_state, ctx.user_id()).await? {
+		return Err(ServerError::AdminRequired);
+	}
+
+	Ok(next.run(req).await)
+}
+
 pub async fn mw_ctx_resolve<B>(
 	mm: State<AppState>,
 	cookies: Cookies,
@@ -36,12 +54,14 @@ pub async fn mw_ctx_resolve<B>(
 ) -> ServerResult<Response> {
 	debug!("{:<12} - mw_ctx_resolve", "MIDDLEWARE");
 
-	let ctx_ext_result = _ctx_resolve(mm, &cookies).await;
+	let bearer_token = bearer_token(req.headers()).map(str::to_string);
+	let ctx_ext_result = _ctx_resolve(mm, bearer_token.as_deref(), &cookies).await;
 
 	if ctx_ext_result.is_err()
+		&& bearer_token.is_none()
 		&& !matches!(ctx_ext_result, Err(CtxExtError::TokenNotInCookie))
 	{
-		cookies.remove(Cookie::named(AUTH_TOKEN))
+		cookies.remove(named_cookie(AUTH_TOKEN))
 	}
 
 	// Store the ctx_ext_result in the request extension
@@ -51,12 +71,24 @@ pub async fn mw_ctx_resolve<B>(
 	Ok(next.run(req).await)
 }
 
-async fn _ctx_resolve(app_state: State<AppState>, cookies: &Cookies) -> CtxExtResult {
+/// The token of `Authorization: Bearer <token>` (e.g., from `token issue`), if any
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+	let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+	let (scheme, token) = value.split_once(' ')?;
+
+	scheme.eq_ignore_ascii_case("bearer").then_some(token.trim())
+}
+
+/// Resolve the `Ctx` from the bearer token if any, otherwise from the `auth-token` cookie.
+async fn _ctx_resolve(app_state: State<AppState>, bearer_token: Option<&str>, cookies: &Cookies) -> CtxExtResult {
 	// -- Get Token String
-	let token = cookies
-		.get(AUTH_TOKEN)
-		.map(|c| c.value().to_string())
-		.ok_or(CtxExtError::TokenNotInCookie)?;
+	let token = match bearer_token {
+		Some(token) => token.to_string(),
+		None => cookies
+			.get(AUTH_TOKEN)
+			.map(|c| c.value().to_string())
+			.ok_or(CtxExtError::TokenNotInCookie)?,
+	};
 
 	// -- Parse Token
 	let token: Token = token.parse().map_err(|_| CtxExtError::TokenWrongFormat)?;
@@ -72,9 +104,16 @@ async fn _ctx_resolve(app_state: State<AppState>, cookies: &Cookies) -> CtxExtRe
 	validate_web_token(&token, &author.token_salt.to_string())
 		.map_err(|_| CtxExtError::FailValidate)?;
 
-	// -- Update Token
-	set_token_cookie(cookies, &author.email, &author.token_salt.to_string())
-		.map_err(|_| CtxExtError::CannotSetTokenCookie)?;
+	// -- Update Token (cookie only, bearer tokens are not refreshed)
+	if bearer_token.is_none() {
+		set_token_cookie(cookies, &author.email, &author.token_salt.to_string())
+			.map_err(|_| CtxExtError::CannotSetTokenCookie)?;
+
+		// (e.g., logged in before the CSRF tokens)
+		if cookies.get(CSRF_TOKEN).is_none() {
+			set_csrf_cookie(cookies);
+		}
+	}
 
 	// -- Create CtxExtResult
 	Ctx::new(author.id).map_err(|ex| CtxExtError::CtxCreateFail(ex.to_string()))
@@ -114,4 +153,75 @@ pub enum CtxExtError {
 	CtxNotInRequestExt,
 	CtxCreateFail(String),
 }
-// endregion: --- Ctx Extractor Result/Error
\ No newline at end of file
+// endregion: --- Ctx Extractor Result/Error
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use axum::body::Body;
+	use axum::http::StatusCode;
+	use axum::routing::get;
+	use axum::{middleware, Router};
+	use hyper::service::Service;
+	use serial_test::serial;
+
+	/// A response of a route behind `mw_admin_require`, for the `user_id` ctx.
+	async fn fx_admin_route_response(app_state: &AppState, user_id: i64) -> Result<Response> {
+		let mut router = Router::new()
+			.route("/admin", get(|| async { "admin" }))
+			.route_layer(middleware::from_fn_with_state(app_state.clone(), mw_admin_require));
+
+		let mut req = Request::get("/admin").body(Body::empty())?;
+		req.extensions_mut().insert(CtxExtResult::Ok(Ctx::new(user_id)?));
+
+		Ok(router.call(req).await?)
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_mw_admin_require_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_authors = &[("test_mw_admin_require_ok", "test_mw_admin_require_ok@mail", "password")];
+		let author = _dev_utils::seed_authors(&ctx, &app_state, fx_authors).await?.remove(0);
+		AuthorBmc::set_admin(&ctx, &app_state, author.id, true).await?;
+
+		// -- Exec
+		let res = fx_admin_route_response(&app_state, author.id).await?;
+
+		// -- Check
+		assert_eq!(res.status(), StatusCode::OK);
+
+		// -- Clean
+		AuthorBmc::delete(&ctx, &app_state, author.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_mw_admin_require_err_not_admin() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_authors = &[("test_mw_admin_require_err", "test_mw_admin_require_err@mail", "password")];
+		let author = _dev_utils::seed_authors(&ctx, &app_state, fx_authors).await?.remove(0);
+
+		// -- Exec
+		let res = fx_admin_route_response(&app_state, author.id).await?;
+
+		// -- Check
+		let error = res.extensions().get::<ServerError>();
+		assert!(matches!(error, Some(ServerError::AdminRequired)), "{error:?}");
+		assert_eq!(ServerError::AdminRequired.client_status_and_error().0, StatusCode::FORBIDDEN);
+
+		// -- Clean
+		AuthorBmc::delete(&ctx, &app_state, author.id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
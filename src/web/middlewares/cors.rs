@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use axum::http::header::{
+	AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, LOCATION,
+	RETRY_AFTER,
+};
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::config::CorsConfig;
+use crate::web::middlewares::csrf::CSRF_TOKEN_HEADER;
+use crate::web::middlewares::stamp::REQUEST_ID_HEADER;
+use crate::web::rate_limit::{RATE_LIMIT_LIMIT_HEADER, RATE_LIMIT_REMAINING_HEADER, RATE_LIMIT_RESET_HEADER};
+
+/// The CORS layer, none when no origin is allowed (i.e., same origin clients only).
+///
+/// NOTE: Cross-origin clients using cookies must also read the `csrf-token` cookie,
+///       i.e., be served from a subdomain of `cookies.domain`.
+pub fn cors_layer(config: &CorsConfig) -> Option<CorsLayer> {
+	if config.allowed_origins.is_empty() {
+		return None;
+	}
+
+	// (values checked at config load)
+	let allow_origin = if config.allowed_origins.iter().any(|origin| origin == "*") {
+		AllowOrigin::any()
+	} else {
+		AllowOrigin::list(config.allowed_origins.iter().filter_map(|origin| HeaderValue::from_str(origin).ok()))
+	};
+	let methods: Vec<Method> = config
+		.allowed_methods
+		.iter()
+		.filter_map(|method| Method::from_bytes(method.as_bytes()).ok())
+		.collect();
+
+	let layer = CorsLayer::new()
+		.allow_origin(allow_origin)
+		.allow_credentials(config.allow_credentials)
+		.allow_methods(methods)
+		.allow_headers([
+			CONTENT_TYPE,
+			AUTHORIZATION,
+			IF_MATCH,
+			IF_NONE_MATCH,
+			IF_MODIFIED_SINCE,
+			HeaderName::from_static(CSRF_TOKEN_HEADER),
+			HeaderName::from_static(REQUEST_ID_HEADER),
+		])
+		.expose_headers([
+			ETAG,
+			LAST_MODIFIED,
+			LOCATION,
+			RETRY_AFTER,
+			HeaderName::from_static(RATE_LIMIT_LIMIT_HEADER),
+			HeaderName::from_static(RATE_LIMIT_REMAINING_HEADER),
+			HeaderName::from_static(RATE_LIMIT_RESET_HEADER),
+			HeaderName::from_static(REQUEST_ID_HEADER),
+		])
+		.max_age(Duration::from_secs(config.max_age_sec));
+
+	Some(layer)
+}
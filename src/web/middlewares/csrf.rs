@@ -0,0 +1,121 @@
+//! CSRF protection of the cookie authenticated requests (double-submit token)
+//!
+//! Design:
+//!
+//! - At login, a random token is set in the `csrf-token` cookie, readable by the client script
+//!   (also set by `mw_ctx_resolve` when missing, e.g., for the sessions from before).
+//! - Requests with a non-safe method (i.e., not `GET`, `HEAD` nor `OPTIONS`) carrying the `auth-token` cookie
+//!   must send it back in `X-CSRF-Token`, which another site cannot do (it cannot read the cookie).
+//! - Requests authenticated with `Authorization: Bearer` are not checked (never sent by the browser on its own),
+//!   nor the ones without the `auth-token` cookie (nothing to forge).
+//!
+
+use axum::http::{HeaderMap, Method, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use tower_cookies::Cookies;
+use tracing::debug;
+
+use crate::crypt::pwd::constant_time_eq;
+use crate::web::middlewares::auth::bearer_token;
+use crate::web::{ServerError, ServerResult, AUTH_TOKEN, CSRF_TOKEN};
+
+pub const CSRF_TOKEN_HEADER: &str = "x-csrf-token";
+
+/// Rejects the cookie authenticated, non-safe requests without the CSRF token (see module doc).
+///
+/// Must run inside `mw_ctx_resolve` (after the cookies are refreshed), and inside the response mapper.
+pub async fn mw_csrf_check<B>(cookies: Cookies, req: Request<B>, next: Next<B>) -> ServerResult<Response> {
+	debug!("{:<12} - mw_csrf_check", "MIDDLEWARE");
+
+	let csrf_cookie = cookies.get(CSRF_TOKEN).map(|c| c.value().to_string());
+	check_csrf(
+		req.method(),
+		req.headers(),
+		cookies.get(AUTH_TOKEN).is_some(),
+		csrf_cookie.as_deref(),
+	)?;
+
+	Ok(next.run(req).await)
+}
+
+fn check_csrf(method: &Method, headers: &HeaderMap, has_auth_cookie: bool, csrf_cookie: Option<&str>) -> ServerResult<()> {
+	let is_safe_method = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+	if is_safe_method || !has_auth_cookie || bearer_token(headers).is_some() {
+		return Ok(());
+	}
+
+	let csrf_header = headers.get(CSRF_TOKEN_HEADER).and_then(|value| value.to_str().ok());
+	let (Some(csrf_cookie), Some(csrf_header)) = (csrf_cookie, csrf_header) else {
+		return Err(ServerError::CsrfTokenMissing);
+	};
+
+	if constant_time_eq(csrf_cookie.as_bytes(), csrf_header.as_bytes()) {
+		Ok(())
+	} else {
+		Err(ServerError::CsrfTokenNotMatching)
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use axum::http::header::AUTHORIZATION;
+
+	const FX_CSRF_TOKEN: &str = "fx-csrf-token-01";
+
+	fn fx_headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		for (name, value) in pairs {
+			headers.insert(*name, value.parse().unwrap());
+		}
+		headers
+	}
+
+	#[test]
+	fn test_check_csrf_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_with_token = fx_headers(&[(CSRF_TOKEN_HEADER, FX_CSRF_TOKEN)]);
+		let fx_bearer = fx_headers(&[(AUTHORIZATION.as_str(), "Bearer fx-token")]);
+		let fx_none = HeaderMap::new();
+
+		// -- Exec & Check
+		assert!(check_csrf(&Method::POST, &fx_with_token, true, Some(FX_CSRF_TOKEN)).is_ok());
+		// (safe method, bearer auth, not cookie authenticated)
+		assert!(check_csrf(&Method::GET, &fx_none, true, Some(FX_CSRF_TOKEN)).is_ok());
+		assert!(check_csrf(&Method::DELETE, &fx_bearer, true, None).is_ok());
+		assert!(check_csrf(&Method::POST, &fx_none, false, None).is_ok());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_check_csrf_err() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_other_token = fx_headers(&[(CSRF_TOKEN_HEADER, "fx-csrf-token-02")]);
+		let fx_basic = fx_headers(&[(AUTHORIZATION.as_str(), "Basic fx-credentials")]);
+
+		// -- Exec & Check
+		assert!(matches!(
+			check_csrf(&Method::DELETE, &HeaderMap::new(), true, Some(FX_CSRF_TOKEN)),
+			Err(ServerError::CsrfTokenMissing)
+		));
+		assert!(matches!(
+			check_csrf(&Method::POST, &fx_other_token, true, None),
+			Err(ServerError::CsrfTokenMissing)
+		));
+		assert!(matches!(
+			check_csrf(&Method::PATCH, &fx_other_token, true, Some(FX_CSRF_TOKEN)),
+			Err(ServerError::CsrfTokenNotMatching)
+		));
+		assert!(matches!(
+			check_csrf(&Method::POST, &fx_basic, true, Some(FX_CSRF_TOKEN)),
+			Err(ServerError::CsrfTokenMissing)
+		));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
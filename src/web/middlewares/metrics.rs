@@ -0,0 +1,27 @@
+use std::time::Instant;
+
+use crate::web::metrics::record_http_request;
+use axum::extract::MatchedPath;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Label of the requests not matching any route
+const ROUTE_UNMATCHED: &str = "unmatched";
+
+/// Records the http request count and latency, per route template (see `web::metrics`)
+pub async fn mw_metrics<B>(req: Request<B>, next: Next<B>) -> Response {
+	let route = req
+		.extensions()
+		.get::<MatchedPath>()
+		.map(|matched_path| matched_path.as_str().to_string())
+		.unwrap_or_else(|| ROUTE_UNMATCHED.to_string());
+	let method = req.method().clone();
+
+	let start = Instant::now();
+	let res = next.run(req).await;
+
+	record_http_request(method.as_str(), &route, res.status(), start.elapsed());
+
+	res
+}
@@ -1,8 +1,21 @@
 /// Auth middleware functionalities
 pub mod auth;
 
+/// CSRF protection of the cookie authenticated requests
+pub mod csrf;
+
 pub mod res_map;
 
+/// Request id and timing
+pub mod stamp;
+
+/// Http request metrics
+pub mod metrics;
+
+/// CORS and security headers, of all the responses
+pub mod cors;
+pub mod security_headers;
+
 
 pub const AUTH_TOKEN: &str = "auth_token";
 pub const AUTHORIZATION_HEADER: &str = "Authorization";
\ No newline at end of file
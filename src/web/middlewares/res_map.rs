@@ -1,59 +1,76 @@
 use crate::ctx::Ctx;
-use crate::log::log_request;
-use crate::web::{self, ClientError};
-use crate::web::custom_response::CustomResponse;
-use axum::http::{Method, Uri};
+use crate::log::{log_request, RequestInfo, RequestLogger};
+use crate::utils::now_utc;
+use crate::web;
+use crate::web::client_ip::ClientIp;
+use crate::web::middlewares::stamp::ReqStamp;
+use crate::web::problem_details::ProblemDetails;
+use axum::body::HttpBody;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, Method, Uri};
 use axum::response::{IntoResponse, Response};
-use axum::Json;
-use serde_json::json;
 use tracing::debug;
 use uuid::Uuid;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn main_response_mapper(
+	State(request_logger): State<RequestLogger>,
 	ctx: Option<Ctx>,
+	req_stamp: Option<ReqStamp>,
+	client_ip: Option<ClientIp>,
 	uri: Uri,
 	req_method: Method,
-	res: Response,
+	headers: HeaderMap,
+	mut res: Response,
 ) -> Response {
 	debug!("{:<12} - main_response_mapper", "RES_MAPPER");
-	let uuid = Uuid::new_v4();
+	let ReqStamp { request_id, time_in } = req_stamp.unwrap_or_else(|| ReqStamp {
+		request_id: Uuid::new_v4().to_string(),
+		time_in: now_utc(),
+	});
 
 	// -- Get the eventual response error.
-	let web_error = res.extensions().get::<web::ServerError>();
-	let client_status_error = web_error.map(|se| se.client_status_and_error());
+	let web_error = res.extensions_mut().remove::<web::ServerError>();
+	let client_status_error = web_error.as_ref().map(|se| se.client_status_and_error());
 
-	// -- If client error, build the new reponse.
+	// -- If client error, build the new reponse (problem+json).
 	let error_response =
 		client_status_error
 			.as_ref()
 			.map(|(status_code, client_error)| {
+				let problem = ProblemDetails::new(*status_code, client_error, Some(uri.path().to_string()), &request_id);
 
-				
-				let error_msg = match client_error {
-					// TODO: Fix `strum(to_string)` not working on `CUSTOM` variant
-					ClientError::CUSTOM(m) => m, // Destructure if custom error message
-					k => k.as_ref()
-				};
+				debug!("CLIENT ERROR BODY:\n{problem:?}");
 
-				let client_error_body = CustomResponse::<()>::new(
-					false,
-					Some(error_msg.to_string()),
-					None
-				);
+				let mut error_response = problem.into_response();
+				// (keep the headers set along the error, e.g., `RateLimit-*`)
+				for (name, value) in res.headers() {
+					if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+						error_response.headers_mut().append(name, value.clone());
+					}
+				}
 
-				debug!("CLIENT ERROR BODY:\n{client_error_body}");
-
-				// Build the new response from the client_error_body
-
-				(*status_code, Json(client_error_body)).into_response()
+				error_response
 			});
 
-	// -- Build and log the server log line.
-	let client_error = client_status_error.unzip().1;
-	// TODO: Need to hander if log_request fail (but should not fail request)
-	let _ = log_request(uuid, req_method, uri, ctx, web_error, client_error).await;
+	let final_res = error_response.unwrap_or(res);
+
+	// -- Build and log the server log line (never fails the request).
+	let client_error = client_status_error.as_ref().map(|(_, client_error)| client_error);
+	let req_info = RequestInfo {
+		request_id: &request_id,
+		time_in,
+		req_method: &req_method,
+		uri: &uri,
+		ctx: ctx.as_ref(),
+		client_ip: client_ip.map(|ClientIp(ip)| ip),
+		user_agent: headers.get(header::USER_AGENT).and_then(|v| v.to_str().ok()),
+	};
+	// (None when streamed, e.g., static files)
+	let response_size = final_res.body().size_hint().exact();
+	log_request(&request_logger, req_info, final_res.status(), response_size, web_error.as_ref(), client_error);
 
 	debug!("\n");
 
-	error_response.unwrap_or(res)
-}
\ No newline at end of file
+	final_res
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::header::{
+	CONTENT_SECURITY_POLICY, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use axum::response::Response;
+
+use crate::config::{SecurityHeadersConfig, SecurityHeadersOverrides};
+
+/// The security headers of a set of routes, computed once from the config.
+#[derive(Debug, Clone, Default)]
+pub struct SecurityHeaders {
+	headers: Arc<HeaderMap>,
+}
+
+impl SecurityHeaders {
+	/// For the API routes, none when disabled
+	pub fn from_config(config: &SecurityHeadersConfig) -> Self {
+		Self::with_overrides(config, SecurityHeadersOverrides::default())
+	}
+
+	/// For the static files, i.e., with `security_headers.static_files`
+	pub fn from_config_static(config: &SecurityHeadersConfig) -> Self {
+		Self::with_overrides(config, config.static_files.clone())
+	}
+
+	fn with_overrides(config: &SecurityHeadersConfig, overrides: SecurityHeadersOverrides) -> Self {
+		if !config.enabled {
+			return Self::default();
+		}
+
+		let csp = overrides.content_security_policy.as_ref().unwrap_or(&config.content_security_policy);
+		let frame_ancestors = overrides.frame_ancestors.as_ref().unwrap_or(&config.frame_ancestors);
+		let referrer_policy = overrides.referrer_policy.as_ref().unwrap_or(&config.referrer_policy);
+
+		let mut headers: Vec<(HeaderName, String)> = vec![
+			(X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+			(CONTENT_SECURITY_POLICY, format!("{csp}; frame-ancestors {frame_ancestors}")),
+			(REFERRER_POLICY, referrer_policy.clone()),
+		];
+		// (for the browsers not supporting `frame-ancestors`)
+		match frame_ancestors.as_str() {
+			"'none'" => headers.push((X_FRAME_OPTIONS, "DENY".to_string())),
+			"'self'" => headers.push((X_FRAME_OPTIONS, "SAMEORIGIN".to_string())),
+			_ => (),
+		}
+		if let Some(max_age_sec) = config.hsts_max_age_sec {
+			headers.push((STRICT_TRANSPORT_SECURITY, format!("max-age={max_age_sec}; includeSubDomains")));
+		}
+
+		// (values checked at config load)
+		let headers = headers
+			.into_iter()
+			.filter_map(|(name, value)| Some((name, HeaderValue::from_str(&value).ok()?)))
+			.collect();
+
+		Self {
+			headers: Arc::new(headers),
+		}
+	}
+}
+
+/// Adds the security headers, except the ones already set by the handler.
+pub async fn mw_security_headers(State(security_headers): State<SecurityHeaders>, mut res: Response) -> Response {
+	for (name, value) in security_headers.headers.iter() {
+		if !res.headers().contains_key(name) {
+			res.headers_mut().insert(name, value.clone());
+		}
+	}
+
+	res
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	fn fx_config() -> SecurityHeadersConfig {
+		SecurityHeadersConfig {
+			enabled: true,
+			content_security_policy: "default-src 'none'".to_string(),
+			frame_ancestors: "'none'".to_string(),
+			referrer_policy: "no-referrer".to_string(),
+			hsts_max_age_sec: Some(600),
+			static_files: SecurityHeadersOverrides {
+				content_security_policy: Some("default-src 'self'".to_string()),
+				frame_ancestors: Some("https://fx.example.com".to_string()),
+				referrer_policy: None,
+			},
+		}
+	}
+
+	#[test]
+	fn test_security_headers_with_overrides() -> Result<()> {
+		// -- Exec
+		let api = SecurityHeaders::from_config(&fx_config());
+		let static_files = SecurityHeaders::from_config_static(&fx_config());
+
+		// -- Check
+		assert_eq!(api.headers[CONTENT_SECURITY_POLICY], "default-src 'none'; frame-ancestors 'none'");
+		assert_eq!(api.headers[X_FRAME_OPTIONS], "DENY");
+		assert_eq!(api.headers[X_CONTENT_TYPE_OPTIONS], "nosniff");
+		assert_eq!(api.headers[STRICT_TRANSPORT_SECURITY], "max-age=600; includeSubDomains");
+		assert_eq!(
+			static_files.headers[CONTENT_SECURITY_POLICY],
+			"default-src 'self'; frame-ancestors https://fx.example.com"
+		);
+		assert!(!static_files.headers.contains_key(X_FRAME_OPTIONS));
+		assert_eq!(static_files.headers[REFERRER_POLICY], "no-referrer");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_security_headers_disabled() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_config = SecurityHeadersConfig { enabled: false, ..fx_config() };
+
+		// -- Exec & Check
+		assert!(SecurityHeaders::from_config(&fx_config).headers.is_empty());
+		assert!(SecurityHeaders::from_config_static(&fx_config).headers.is_empty());
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,150 @@
+use crate::utils::now_utc;
+use crate::web::{ServerError, ServerResult};
+use async_trait::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use time::OffsetDateTime;
+use tracing::{debug, info_span, Instrument};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Longer (or non visible ASCII) incoming request ids are replaced by a generated one
+const REQUEST_ID_MAX_LEN: usize = 128;
+
+#[derive(Debug, Clone)]
+/// Request id and arrival time, for the request log line (and the error responses)
+pub struct ReqStamp {
+	/// `X-Request-Id` sent by the client (or proxy), otherwise a new uuid
+	pub request_id: String,
+	pub time_in: OffsetDateTime,
+}
+
+/// Stamps the request and runs the rest in a `request` span (with the `request_id`),
+/// echoing the `X-Request-Id` in the response.
+///
+/// Must be the outermost layer, so that the span and latency cover all the others.
+pub async fn mw_req_stamp<B>(mut req: Request<B>, next: Next<B>) -> ServerResult<Response> {
+	let request_id = req
+		.headers()
+		.get(REQUEST_ID_HEADER)
+		.and_then(|value| value.to_str().ok())
+		.filter(|value| is_valid_request_id(value))
+		.map(str::to_string)
+		.unwrap_or_else(|| Uuid::new_v4().to_string());
+
+	let span = info_span!(
+		"request",
+		request_id = %request_id,
+		method = %req.method(),
+		path = %req.uri().path(),
+	);
+
+	let stamp = ReqStamp {
+		request_id,
+		time_in: now_utc(),
+	};
+	let header_value = HeaderValue::from_str(&stamp.request_id).ok();
+
+	req.extensions_mut().insert(stamp);
+
+	async move {
+		debug!("{:<12} - mw_req_stamp", "MIDDLEWARE");
+
+		let mut res = next.run(req).await;
+		if let Some(header_value) = header_value {
+			res.headers_mut().insert(REQUEST_ID_HEADER, header_value);
+		}
+
+		Ok(res)
+	}
+	.instrument(span)
+	.await
+}
+
+fn is_valid_request_id(value: &str) -> bool {
+	!value.is_empty()
+		&& value.len() <= REQUEST_ID_MAX_LEN
+		&& value.bytes().all(|b| b.is_ascii_graphic())
+}
+
+// region:    --- ReqStamp Extractor
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ReqStamp {
+	type Rejection = ServerError;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> ServerResult<Self> {
+		debug!("{:<12} - ReqStamp", "EXTRACTOR");
+
+		parts
+			.extensions
+			.get::<ReqStamp>()
+			.cloned()
+			.ok_or(ServerError::ReqStampNotInReqExt)
+	}
+}
+// endregion: --- ReqStamp Extractor
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use axum::routing::get;
+	use axum::{middleware, Router};
+	use std::net::SocketAddr;
+
+	async fn handler_request_id(stamp: ReqStamp) -> String {
+		stamp.request_id
+	}
+
+	/// Returns the response `x-request-id` header and the request id seen by the handler.
+	async fn call(request_id: Option<&str>) -> Result<(Option<String>, String)> {
+		let app = Router::new()
+			.route("/", get(handler_request_id))
+			.layer(middleware::from_fn(mw_req_stamp));
+		let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(app.into_make_service());
+		let url = format!("http://{}/", server.local_addr());
+		tokio::spawn(server);
+
+		let mut req = reqwest::Client::new().get(url);
+		if let Some(request_id) = request_id {
+			req = req.header(REQUEST_ID_HEADER, request_id);
+		}
+		let res = req.send().await?;
+
+		let header = res.headers().get(REQUEST_ID_HEADER).map(|v| v.to_str().unwrap().to_string());
+
+		Ok((header, res.text().await?))
+	}
+
+	#[tokio::test]
+	async fn test_req_stamp_request_id_received() -> Result<()> {
+		// -- Exec
+		let (header, handler_request_id) = call(Some("fx-req-01")).await?;
+
+		// -- Check
+		assert_eq!(header.as_deref(), Some("fx-req-01"));
+		assert_eq!(handler_request_id, "fx-req-01");
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_req_stamp_request_id_generated() -> Result<()> {
+		for fx_request_id in [None, Some(""), Some("has space"), Some(&*"x".repeat(129))] {
+			// -- Exec
+			let (header, handler_request_id) = call(fx_request_id).await?;
+
+			// -- Check
+			let header = header.expect("Should have an x-request-id header");
+			assert!(Uuid::parse_str(&header).is_ok(), "Should have been a uuid, was {header}");
+			assert_eq!(handler_request_id, header);
+		}
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -1,24 +1,41 @@
 pub use self::error::ClientError;
 pub use self::error::{ServerError, ServerResult};
 use crate::crypt::token::generate_web_token;
-use axum::http::StatusCode;
+use axum::http::uri::Authority;
+use axum::http::{header, HeaderMap, StatusCode};
 use axum::Json;
 use axum_extra::extract::WithRejection;
-use custom_extractor::ApiError;
+pub use custom_extractor::{Path, Query, ValidJson};
 use custom_response::CustomResponse;
+use tower_cookies::cookie::SameSite;
+use rand::RngCore;
 use tower_cookies::{Cookie, Cookies};
 
+use crate::config::{config, CookieSameSite};
+
 pub mod handlers;
 pub mod middlewares;
 pub mod routes;
 mod custom_extractor;
 mod error;
 pub mod custom_response;
+pub mod conditional;
+pub mod problem_details;
 pub mod auth;
+pub mod client_ip;
+pub mod login_guard;
+pub mod rate_limit;
+pub mod metrics;
 pub mod routes_login;
+pub mod routes_oidc;
+pub mod routes_health;
+pub mod feed;
+pub mod routes_feed;
+pub mod sitemap;
+pub mod routes_sitemap;
 
 type ServerResponse<T> = ServerResult<(StatusCode, Json<CustomResponse<T>>)>;
-type IncomingServerRequest<T> =  WithRejection<Json<T>, ApiError>;
+type IncomingServerRequest<T> =  WithRejection<ValidJson<T>, ServerError>;
 
 #[derive(Debug)]
 pub struct HelloParams {
@@ -26,6 +43,8 @@ pub struct HelloParams {
 }
 
 pub const AUTH_TOKEN: &str = "auth-token";
+/// Double-submit token, readable by the client script (see `middlewares::csrf`)
+pub const CSRF_TOKEN: &str = "csrf-token";
 
 // endregion: --- Modules
 
@@ -33,9 +52,8 @@ pub const AUTH_TOKEN: &str = "auth-token";
 fn set_token_cookie(cookies: &Cookies, user: &str, salt: &str) -> ServerResult<()> {
 	let token = generate_web_token(user, salt)?;
 
-	let mut cookie = Cookie::new(AUTH_TOKEN, token.to_string());
+	let mut cookie = new_cookie(AUTH_TOKEN, token.to_string());
 	cookie.set_http_only(true); // prevent client-side script access
-	cookie.set_path("/"); // set valid domain to server rot
 
 	cookies.add(cookie);
 
@@ -43,10 +61,76 @@ fn set_token_cookie(cookies: &Cookies, user: &str, salt: &str) -> ServerResult<(
 }
 
 fn remove_token_cookie(cookies: &Cookies) -> ServerResult<()> {
-	let mut cookie = Cookie::named(AUTH_TOKEN);
-	cookie.set_path("/");
-
-	cookies.remove(cookie);
+	cookies.remove(named_cookie(AUTH_TOKEN));
+	cookies.remove(named_cookie(CSRF_TOKEN));
 
 	Ok(())
-}
\ No newline at end of file
+}
+
+/// Issue a new CSRF token (at login), sent back by the client in `X-CSRF-Token`.
+fn set_csrf_cookie(cookies: &Cookies) {
+	let mut bytes = [0u8; 32];
+	rand::thread_rng().fill_bytes(&mut bytes);
+
+	// (not `http_only`, the client script reads it)
+	cookies.add(new_cookie(CSRF_TOKEN, base64_url::encode(&bytes)));
+}
+
+/// Cookie with the `config().cookies` attributes
+fn new_cookie(name: &'static str, value: String) -> Cookie<'static> {
+	let cookie_config = &config().cookies;
+
+	let mut cookie = named_cookie(name);
+	cookie.set_value(value);
+	cookie.set_secure(cookie_config.secure);
+	cookie.set_same_site(match cookie_config.same_site {
+		CookieSameSite::Strict => SameSite::Strict,
+		CookieSameSite::Lax => SameSite::Lax,
+		CookieSameSite::None => SameSite::None,
+	});
+	if let Some(max_age_sec) = cookie_config.max_age_sec {
+		cookie.set_max_age(time::Duration::seconds(max_age_sec as i64));
+	}
+
+	cookie
+}
+
+/// Cookie with the path and domain, enough to be removed
+fn named_cookie(name: &'static str) -> Cookie<'static> {
+	let mut cookie = Cookie::named(name);
+	cookie.set_path("/"); // set valid domain to server rot
+	if let Some(domain) = &config().cookies.domain {
+		cookie.set_domain(domain.clone()); // (must match to be removed)
+	}
+
+	cookie
+}
+
+/// Base of the absolute links (e.g., feeds, sitemap): `server.public_url`, else from the `Host` header
+/// (client controlled, hence the startup warning outside dev, see `cli::serve`).
+pub fn public_url(headers: &HeaderMap) -> String {
+	if let Some(public_url) = &config().server.public_url {
+		return public_url.clone();
+	}
+
+	// (only a host and port, nothing else spliced into the links)
+	let host = headers
+		.get(header::HOST)
+		.and_then(|host| host.to_str().ok())
+		.and_then(|host| host.parse::<Authority>().ok())
+		.filter(|authority| !authority.as_str().contains('@'))
+		.map(|authority| authority.to_string())
+		.unwrap_or("localhost".to_string());
+
+	format!("http://{host}")
+}
+
+/// The URL of a page from its template (e.g., `server.post_page_url`), relative ones under `base_url`.
+pub fn page_url(base_url: &str, template: &str, id: i64) -> String {
+	let url = template.replace("{id}", &id.to_string());
+
+	match url.starts_with('/') {
+		true => format!("{base_url}{url}"),
+		false => url,
+	}
+}
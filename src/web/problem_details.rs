@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use super::ClientError;
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+/// Error response body, as per RFC 7807 (`application/problem+json`)
+///
+/// Clients should branch on `code` (stable), `title` and `detail` are for humans.
+pub struct ProblemDetails {
+	/// Problem type URI, `about:blank` as problems are told apart by `code`
+	#[serde(rename = "type")]
+	pub type_: String,
+	/// Reason phrase of the status code
+	pub title: String,
+	pub status: u16,
+	pub detail: Option<String>,
+	/// Request path
+	pub instance: Option<String>,
+
+	// -- Extensions
+	/// Stable, machine-readable error code (see `ClientError`)
+	pub code: String,
+	/// Also in the `X-Request-Id` response header, and the server request log line
+	pub request_id: String,
+	/// Field name to its error messages (on `VALIDATION_FAIL`)
+	pub errors: Option<BTreeMap<String, Vec<String>>>,
+}
+
+impl ProblemDetails {
+	pub fn new(status: StatusCode, client_error: &ClientError, instance: Option<String>, request_id: &str) -> Self {
+		Self {
+			type_: "about:blank".to_string(),
+			title: status.canonical_reason().unwrap_or("Error").to_string(),
+			status: status.as_u16(),
+			detail: client_error.detail().map(str::to_string),
+			instance,
+			code: client_error.code().to_string(),
+			request_id: request_id.to_string(),
+			errors: client_error.field_errors().cloned(),
+		}
+	}
+}
+
+impl IntoResponse for ProblemDetails {
+	fn into_response(self) -> Response {
+		let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+		let mut response = (status, Json(self)).into_response();
+		response
+			.headers_mut()
+			.insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+
+		response
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use serde_json::{json, Value};
+
+	#[test]
+	fn test_problem_details_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_request_id = "fx-req-01";
+		let fx_error = ClientError::ENTITY_NOT_FOUND("posts 100 not found".to_string());
+
+		// -- Exec
+		let problem = ProblemDetails::new(StatusCode::NOT_FOUND, &fx_error, Some("/api/post/100".to_string()), fx_request_id);
+		let body = serde_json::to_value(&problem)?;
+		let response = problem.into_response();
+
+		// -- Check
+		assert_eq!(response.status(), StatusCode::NOT_FOUND);
+		assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+		assert_eq!(
+			body,
+			json!({
+				"type": "about:blank",
+				"title": "Not Found",
+				"status": 404,
+				"detail": "posts 100 not found",
+				"instance": "/api/post/100",
+				"code": "ENTITY_NOT_FOUND",
+				"request_id": fx_request_id,
+			})
+		);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,30 @@
+use serde::Serialize;
+
+pub type RateLimitResult<T> = core::result::Result<T, RateLimitError>;
+
+#[derive(Debug, Serialize)]
+pub enum RateLimitError {
+	RedisConnectFail(String),
+	Redis(String),
+}
+
+// region:    --- Froms
+impl From<redis::RedisError> for RateLimitError {
+	fn from(val: redis::RedisError) -> Self {
+		Self::Redis(val.to_string())
+	}
+}
+// endregion: --- Froms
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for RateLimitError {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for RateLimitError {}
+// endregion: --- Error Boilerplate
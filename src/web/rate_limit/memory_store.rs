@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use super::{Bucket, RateLimitDecision, RateLimitResult, RateLimitStore};
+use crate::config::RateLimitPolicy;
+
+/// Above this number of buckets, the full ones are pruned on take
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// In-process buckets.
+///
+/// NOTE: Not shared between instances, i.e., each instance allows the full rate
+///       (use `RedisStore` when running several instances).
+#[derive(Default)]
+pub struct MemoryStore {
+	buckets: Mutex<HashMap<String, Entry>>,
+}
+
+struct Entry {
+	bucket: Bucket,
+	/// When the bucket is full again, i.e., same as no bucket
+	full_at_ms: u64,
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+	fn name(&self) -> &'static str {
+		"memory"
+	}
+
+	async fn take(&self, key: &str, policy: &RateLimitPolicy, now_ms: u64) -> RateLimitResult<RateLimitDecision> {
+		let mut buckets = self.buckets.lock().unwrap();
+
+		if buckets.len() > PRUNE_THRESHOLD {
+			buckets.retain(|_, entry| entry.full_at_ms > now_ms);
+		}
+
+		let (bucket, allowed) = Bucket::take(buckets.get(key).map(|entry| entry.bucket), policy, now_ms);
+		let decision = RateLimitDecision::new(policy, bucket.tokens, allowed);
+		let full_at_ms = now_ms + decision.reset_sec * 1000;
+		buckets.insert(key.to_string(), Entry { bucket, full_at_ms });
+
+		Ok(decision)
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::RateLimitBy;
+	use anyhow::Result;
+
+	#[tokio::test]
+	async fn test_memory_store_take() -> Result<()> {
+		// -- Setup & Fixtures
+		let store = MemoryStore::default();
+		let fx_policy = RateLimitPolicy { by: RateLimitBy::Ip, burst: 2, per_minute: 60 };
+
+		// -- Exec
+		let first = store.take("fx:ip:01", &fx_policy, 0).await?;
+		let second = store.take("fx:ip:01", &fx_policy, 0).await?;
+		let third = store.take("fx:ip:01", &fx_policy, 0).await?;
+		let other_key = store.take("fx:ip:02", &fx_policy, 0).await?;
+		let later = store.take("fx:ip:01", &fx_policy, 1_000).await?;
+
+		// -- Check
+		assert!(first.allowed && second.allowed);
+		assert_eq!(second.remaining, 0);
+		assert!(!third.allowed);
+		assert_eq!(third.retry_after_sec, 1);
+		assert!(other_key.allowed);
+		assert!(later.allowed);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,270 @@
+//! Rate limiting, per route group (see `web::routes`)
+//!
+//! Design:
+//!
+//! - A token bucket per route group and client: `burst` tokens, refilled at `per_minute`,
+//!   each request taking one (see `RateLimitPolicy`, configured in `rate_limit.groups`).
+//! - The client is the IP (see `web::client_ip`, behind the trusted proxies), or the `Ctx` user id
+//!   for the `user` groups (the IP when not logged in).
+//! - Buckets are kept in process (`MemoryStore`), or in Redis to be shared between instances (`RedisStore`).
+//! - Responses carry `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` (seconds until full),
+//!   and the rejected ones are a `429` (`RATE_LIMITED`) with `Retry-After`.
+//! - The store never fails a request: on failure, the request is let through (and the failure logged).
+//!
+
+// region:    --- Modules
+mod error;
+mod memory_store;
+mod redis_store;
+
+pub use self::error::{RateLimitError, RateLimitResult};
+pub use self::memory_store::MemoryStore;
+pub use self::redis_store::RedisStore;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::http::header::RETRY_AFTER;
+use axum::http::{HeaderName, HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tracing::{debug, warn};
+
+use crate::config::{RateLimitBy, RateLimitConfig, RateLimitPolicy, RateLimitStoreKind};
+use crate::ctx::Ctx;
+use crate::web::client_ip::ClientIp;
+use crate::web::ServerError;
+// endregion: --- Modules
+
+pub const RATE_LIMIT_LIMIT_HEADER: &str = "ratelimit-limit";
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "ratelimit-remaining";
+pub const RATE_LIMIT_RESET_HEADER: &str = "ratelimit-reset";
+
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+	fn name(&self) -> &'static str;
+
+	/// Take a token from the bucket of `key` (full when new), at `now_ms` (since the epoch).
+	async fn take(&self, key: &str, policy: &RateLimitPolicy, now_ms: u64) -> RateLimitResult<RateLimitDecision>;
+}
+
+// region:    --- Token Bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+	pub tokens: f64,
+	/// Last refill, ms since the epoch
+	pub at_ms: u64,
+}
+
+impl Bucket {
+	/// Refill the bucket (full when new) up to `now_ms`, then take a token if any, `true` if taken.
+	pub fn take(bucket: Option<Bucket>, policy: &RateLimitPolicy, now_ms: u64) -> (Bucket, bool) {
+		let burst = f64::from(policy.burst);
+		let tokens = match bucket {
+			Some(bucket) => {
+				let elapsed_ms = now_ms.saturating_sub(bucket.at_ms) as f64;
+				(bucket.tokens + elapsed_ms * refill_per_ms(policy)).min(burst)
+			}
+			None => burst,
+		};
+
+		let taken = tokens >= 1.;
+		let tokens = if taken { tokens - 1. } else { tokens };
+
+		(Bucket { tokens, at_ms: now_ms }, taken)
+	}
+}
+
+fn refill_per_ms(policy: &RateLimitPolicy) -> f64 {
+	f64::from(policy.per_minute) / 60_000.
+}
+
+/// Outcome of a request for a token, with the values of the `RateLimit-*` headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+	pub allowed: bool,
+	pub limit: u32,
+	pub remaining: u32,
+	/// Until the bucket is full again
+	pub reset_sec: u64,
+	/// Until the next token, when not allowed
+	pub retry_after_sec: u64,
+}
+
+impl RateLimitDecision {
+	pub fn new(policy: &RateLimitPolicy, tokens: f64, allowed: bool) -> Self {
+		let rate = refill_per_ms(policy);
+		let ms_to_secs = |ms: f64| (ms.max(0.) / 1000.).ceil() as u64;
+
+		Self {
+			allowed,
+			limit: policy.burst,
+			remaining: tokens.floor() as u32,
+			reset_sec: ms_to_secs((f64::from(policy.burst) - tokens) / rate),
+			retry_after_sec: if allowed { 0 } else { ms_to_secs((1. - tokens) / rate).max(1) },
+		}
+	}
+
+	pub fn headers(&self) -> [(HeaderName, HeaderValue); 3] {
+		[
+			(HeaderName::from_static(RATE_LIMIT_LIMIT_HEADER), self.limit.into()),
+			(HeaderName::from_static(RATE_LIMIT_REMAINING_HEADER), self.remaining.into()),
+			(HeaderName::from_static(RATE_LIMIT_RESET_HEADER), self.reset_sec.into()),
+		]
+	}
+}
+// endregion: --- Token Bucket
+
+// region:    --- RateLimiter
+/// The rate limiter of the `AppState`, for all the route groups.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+	/// None when disabled
+	store: Option<Arc<dyn RateLimitStore>>,
+	groups: Arc<BTreeMap<String, RateLimitPolicy>>,
+}
+
+impl RateLimiter {
+	pub fn new(store: Arc<dyn RateLimitStore>, groups: BTreeMap<String, RateLimitPolicy>) -> Self {
+		Self {
+			store: Some(store),
+			groups: Arc::new(groups),
+		}
+	}
+
+	pub fn disabled() -> Self {
+		Self::default()
+	}
+
+	pub async fn from_config(rate_limit_config: &RateLimitConfig) -> RateLimitResult<Self> {
+		if !rate_limit_config.enabled {
+			return Ok(Self::disabled());
+		}
+
+		let store: Arc<dyn RateLimitStore> = match rate_limit_config.store {
+			RateLimitStoreKind::Memory => Arc::new(MemoryStore::default()),
+			RateLimitStoreKind::Redis => {
+				let url = rate_limit_config.redis_url.as_deref().map(String::as_str).unwrap_or_default();
+				Arc::new(RedisStore::connect(url).await?)
+			}
+		};
+
+		Ok(Self::new(store, rate_limit_config.groups.clone()))
+	}
+
+	/// The limiter of a route group, the state of `mw_rate_limit` (not limited when disabled).
+	pub fn group(&self, group: &'static str) -> GroupRateLimiter {
+		let limiter = self.store.clone().zip(self.groups.get(group).copied());
+
+		GroupRateLimiter { group, limiter }
+	}
+}
+
+#[derive(Clone)]
+pub struct GroupRateLimiter {
+	group: &'static str,
+	limiter: Option<(Arc<dyn RateLimitStore>, RateLimitPolicy)>,
+}
+
+/// Rate limits the requests of a route group (`route_layer` with `RateLimiter::group` as state).
+///
+/// Must run inside `mw_ctx_resolve`, for the `user` groups.
+pub async fn mw_rate_limit<B>(
+	State(group_limiter): State<GroupRateLimiter>,
+	ctx: Option<Ctx>,
+	client_ip: Option<ClientIp>,
+	req: Request<B>,
+	next: Next<B>,
+) -> Response {
+	debug!("{:<12} - mw_rate_limit", "MIDDLEWARE");
+
+	let GroupRateLimiter { group, limiter: Some((store, policy)) } = group_limiter else {
+		return next.run(req).await;
+	};
+
+	let key = match (policy.by, ctx, client_ip) {
+		(RateLimitBy::User, Some(ctx), _) => format!("{group}:user:{}", ctx.user_id()),
+		(_, _, Some(ClientIp(ip))) => format!("{group}:ip:{ip}"),
+		// (no client address, i.e., not served with the connect info)
+		_ => return next.run(req).await,
+	};
+
+	let decision = match store.take(&key, &policy, now_ms()).await {
+		Ok(decision) => decision,
+		Err(err) => {
+			warn!("{:<12} - {} take {key} failed - {err}", "RATE_LIMIT", store.name());
+			return next.run(req).await;
+		}
+	};
+
+	let mut res = if decision.allowed {
+		next.run(req).await
+	} else {
+		debug!("{:<12} - {key} limited", "RATE_LIMIT");
+		let mut res = ServerError::RateLimited { retry_after_sec: decision.retry_after_sec }.into_response();
+		res.headers_mut().insert(RETRY_AFTER, decision.retry_after_sec.into());
+		res
+	};
+	res.headers_mut().extend(decision.headers());
+
+	res
+}
+
+fn now_ms() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+// endregion: --- RateLimiter
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	const FX_POLICY: RateLimitPolicy = RateLimitPolicy {
+		by: RateLimitBy::Ip,
+		burst: 2,
+		per_minute: 6, // (a token every 10s)
+	};
+
+	#[test]
+	fn test_bucket_take_and_refill() -> Result<()> {
+		// -- Exec & Check
+		let (bucket, taken) = Bucket::take(None, &FX_POLICY, 0);
+		assert!(taken);
+		assert_eq!(bucket.tokens, 1.);
+		let (bucket, taken) = Bucket::take(Some(bucket), &FX_POLICY, 0);
+		assert!(taken);
+		let (bucket, taken) = Bucket::take(Some(bucket), &FX_POLICY, 5_000);
+		assert!(!taken);
+		assert_eq!(bucket.tokens, 0.5);
+
+		// (a token refilled)
+		let (bucket, taken) = Bucket::take(Some(bucket), &FX_POLICY, 10_000);
+		assert!(taken);
+		assert_eq!(bucket.tokens, 0.);
+
+		// (never above the burst)
+		let (bucket, _) = Bucket::take(Some(bucket), &FX_POLICY, 1_000_000);
+		assert_eq!(bucket.tokens, 1.);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_decision_values() -> Result<()> {
+		// -- Exec
+		let allowed = RateLimitDecision::new(&FX_POLICY, 1., true);
+		let limited = RateLimitDecision::new(&FX_POLICY, 0.5, false);
+
+		// -- Check
+		assert_eq!((allowed.limit, allowed.remaining, allowed.reset_sec, allowed.retry_after_sec), (2, 1, 10, 0));
+		assert_eq!((limited.remaining, limited.reset_sec, limited.retry_after_sec), (0, 15, 5));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
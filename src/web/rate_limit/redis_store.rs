@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::Script;
+
+use super::{refill_per_ms, RateLimitDecision, RateLimitError, RateLimitResult, RateLimitStore};
+use crate::config::RateLimitPolicy;
+
+/// Namespace of the keys, so that the Redis instance can be shared
+const KEY_NAMESPACE: &str = "axum-cms:rate-limit:";
+
+/// Same as `Bucket::take`, atomically, on a hash (`tokens`, `at_ms`) expiring once full again.
+/// ARGV: burst, refill per ms, now ms. Returns `{taken, tokens}` (tokens as a string, Lua numbers being truncated).
+const TAKE_SCRIPT: &str = r"
+local burst = tonumber(ARGV[1])
+local refill_per_ms = tonumber(ARGV[2])
+local now_ms = tonumber(ARGV[3])
+
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'at_ms')
+local tokens = burst
+if bucket[1] then
+	local elapsed_ms = math.max(0, now_ms - tonumber(bucket[2]))
+	tokens = math.min(burst, tonumber(bucket[1]) + elapsed_ms * refill_per_ms)
+end
+
+local taken = 0
+if tokens >= 1 then
+	tokens = tokens - 1
+	taken = 1
+end
+
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'at_ms', ARGV[3])
+redis.call('PEXPIRE', KEYS[1], math.ceil((burst - tokens) / refill_per_ms) + 1000)
+
+return {taken, tostring(tokens)}
+";
+
+/// Buckets shared between instances (the connection is re-established when lost).
+pub struct RedisStore {
+	conn: ConnectionManager,
+	take_script: Script,
+}
+
+impl RedisStore {
+	/// Connect to `url`, e.g., `redis://127.0.0.1:6379`.
+	pub async fn connect(url: &str) -> RateLimitResult<Self> {
+		let client = redis::Client::open(url).map_err(|ex| RateLimitError::RedisConnectFail(ex.to_string()))?;
+		let conn = ConnectionManager::new(client)
+			.await
+			.map_err(|ex| RateLimitError::RedisConnectFail(ex.to_string()))?;
+
+		Ok(Self {
+			conn,
+			take_script: Script::new(TAKE_SCRIPT),
+		})
+	}
+}
+
+#[async_trait]
+impl RateLimitStore for RedisStore {
+	fn name(&self) -> &'static str {
+		"redis"
+	}
+
+	async fn take(&self, key: &str, policy: &RateLimitPolicy, now_ms: u64) -> RateLimitResult<RateLimitDecision> {
+		let (taken, tokens): (i64, String) = self
+			.take_script
+			.key(format!("{KEY_NAMESPACE}{key}"))
+			.arg(policy.burst)
+			.arg(refill_per_ms(policy))
+			.arg(now_ms)
+			.invoke_async(&mut self.conn.clone())
+			.await?;
+
+		let tokens: f64 = tokens
+			.parse()
+			.map_err(|_| RateLimitError::Redis(format!("unexpected tokens value `{tokens}`")))?;
+
+		Ok(RateLimitDecision::new(policy, tokens, taken == 1))
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::RateLimitBy;
+	use anyhow::Result;
+	use std::time::Duration;
+
+	/// Local instance for the tests, `REDIS_TEST_URL` (default `redis://127.0.0.1:6379`), failing when unreachable
+	async fn fx_redis() -> Result<RedisStore> {
+		let url = std::env::var("REDIS_TEST_URL").unwrap_or("redis://127.0.0.1:6379".to_string());
+
+		let store = tokio::time::timeout(Duration::from_secs(2), RedisStore::connect(&url))
+			.await
+			.map_err(|_| anyhow::anyhow!("no Redis at {url} (timed out)"))??;
+
+		Ok(store)
+	}
+
+	#[ignore = "needs a Redis, run with `REDIS_TEST_URL=redis://... cargo test -- --ignored redis`"]
+	#[tokio::test]
+	async fn test_redis_store_take() -> Result<()> {
+		// -- Setup & Fixtures
+		let store = fx_redis().await?;
+		let fx_key = format!("fx-test:ip:{}", uuid::Uuid::new_v4());
+		let fx_policy = RateLimitPolicy { by: RateLimitBy::Ip, burst: 2, per_minute: 60 };
+
+		// -- Exec
+		let first = store.take(&fx_key, &fx_policy, 0).await?;
+		let second = store.take(&fx_key, &fx_policy, 0).await?;
+		let third = store.take(&fx_key, &fx_policy, 500).await?;
+		let later = store.take(&fx_key, &fx_policy, 1_500).await?;
+
+		// -- Check
+		assert!(first.allowed && second.allowed);
+		assert_eq!(second.remaining, 0);
+		assert!(!third.allowed);
+		assert_eq!(third.retry_after_sec, 1);
+		assert!(later.allowed);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
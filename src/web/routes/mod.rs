@@ -1,17 +1,22 @@
-use axum::{extract::State, http::StatusCode, middleware, response::IntoResponse, routing::{delete, get, patch, post}, Router};
+use axum::{extract::{DefaultBodyLimit, State}, http::StatusCode, middleware, response::IntoResponse, routing::{delete, get, patch, post}, Router};
 use sqlx::{Pool, Postgres};
 use tower_cookies::CookieManagerLayer;
 
 use crate::web::handlers::{author::{handler_author_list, handler_author_get}, hello::{handler_hello, handler_hello_2}, post::{handler_post_create, handler_post_delete, handler_post_update}};
+use crate::config::config;
 use crate::models::AppState;
+use crate::web::rate_limit::mw_rate_limit;
 
-use super::{handlers::{edit::{handler_edit_accept, handler_edit_create, handler_edit_delete, handler_edit_get, handler_edit_list_all, handler_edit_list_incoming, handler_edit_list_outgoing, handler_edit_reject, handler_edit_update}, post::{handler_post_get, handler_post_list}}, middlewares::auth::mw_ctx_require};
+use super::{handlers::{edit::{handler_edit_accept, handler_edit_create, handler_edit_delete, handler_edit_get, handler_edit_list_all, handler_edit_list_incoming, handler_edit_list_outgoing, handler_edit_reject, handler_edit_update}, post::{handler_post_get, handler_post_list}, two_factor::{handler_2fa_confirm, handler_2fa_disable, handler_2fa_enroll}, media::{handler_media_content, handler_media_delete, handler_media_get, handler_media_list, handler_media_rendition_content, handler_media_renditions_list, handler_media_resize, handler_media_resize_url, handler_media_upload, handler_post_media_list, handler_post_media_set}, webhook::{handler_webhook_create, handler_webhook_delete, handler_webhook_deliveries_list, handler_webhook_delivery_get, handler_webhook_get, handler_webhook_list, handler_webhook_redeliver, handler_webhook_update}}, middlewares::auth::{mw_admin_require, mw_ctx_require}};
 
 pub fn routes_main(app_state: AppState) -> Router {
 	Router::new()
 		.merge(routes_post(app_state.clone()))
 		.merge(routes_author(app_state.clone()))
 		.merge(routes_edit(app_state.clone()))
+		.merge(routes_2fa(app_state.clone()))
+		.merge(routes_media(app_state.clone()))
+		.merge(routes_webhook(app_state.clone()))
 		// .nest("/edit-suggestion", router)
 		// .merge(routes_edit_suggestion(app_state.clone()))
 
@@ -27,6 +32,7 @@ fn routes_author(app_state: AppState) -> Router {
 		.route("/author/:id",
 			get(handler_author_get)
 		)
+		.route_layer(middleware::from_fn_with_state(app_state.rate_limiter.group("author"), mw_rate_limit))
 		.with_state(app_state)
 }
 
@@ -50,6 +56,7 @@ fn routes_post(app_state: AppState) -> Router {
 			.delete(handler_post_delete)
 			.route_layer(middleware::from_fn(mw_ctx_require))
 		)
+		.route_layer(middleware::from_fn_with_state(app_state.rate_limiter.group("post"), mw_rate_limit))
 		.with_state(app_state)
 }
 
@@ -84,6 +91,109 @@ fn routes_edit(app_state: AppState) -> Router {
 			get(handler_edit_list_incoming)
 		)
 		.route_layer(middleware::from_fn(mw_ctx_require))
+		.route_layer(middleware::from_fn_with_state(app_state.rate_limiter.group("edit"), mw_rate_limit))
+		.with_state(app_state)
+}
+
+/// Handling of two-factor authentication enrollment
+fn routes_2fa(app_state: AppState) -> Router {
+	Router::new()
+		.route(
+			"/2fa/enroll",
+			post(handler_2fa_enroll)
+		)
+		.route(
+			"/2fa/confirm",
+			post(handler_2fa_confirm)
+		)
+		.route(
+			"/2fa/disable",
+			post(handler_2fa_disable)
+		)
+		.route_layer(middleware::from_fn(mw_ctx_require))
+		.route_layer(middleware::from_fn_with_state(app_state.rate_limiter.group("2fa"), mw_rate_limit))
+		.with_state(app_state)
+}
+
+/// Handling of media, and of the media of posts
+fn routes_media(app_state: AppState) -> Router {
+	// (the multipart framing on top of the largest file)
+	let upload_body_limit = config().media.max_size_bytes as usize + 1024 * 1024;
+
+	Router::new()
+		.route(
+			"/media",
+			post(handler_media_upload)
+			.layer(DefaultBodyLimit::max(upload_body_limit))
+			.get(handler_media_list)
+			.route_layer(middleware::from_fn(mw_ctx_require))
+		)
+		.route(
+			"/media/:id",
+			get(handler_media_get)
+			.delete(handler_media_delete)
+			.route_layer(middleware::from_fn(mw_ctx_require))
+		)
+		.route(
+			"/media/:id/renditions",
+			get(handler_media_renditions_list)
+			.route_layer(middleware::from_fn(mw_ctx_require))
+		)
+		.route(
+			"/media/:id/resize-url",
+			get(handler_media_resize_url)
+			.route_layer(middleware::from_fn(mw_ctx_require))
+		)
+		.route(
+			"/media/:id/content",
+			get(handler_media_content)
+		)
+		.route(
+			"/media/:id/renditions/:name/content",
+			get(handler_media_rendition_content)
+		)
+		.route(
+			"/media/:id/resize",
+			get(handler_media_resize)
+		)
+		.route(
+			"/post/:id/media",
+			get(handler_post_media_list)
+			.put(handler_post_media_set)
+			.route_layer(middleware::from_fn(mw_ctx_require))
+		)
+		.route_layer(middleware::from_fn_with_state(app_state.rate_limiter.group("media"), mw_rate_limit))
+		.with_state(app_state)
+}
+
+/// Handling of the webhook subscriptions and their deliveries (admin only)
+fn routes_webhook(app_state: AppState) -> Router {
+	Router::new()
+		.route(
+			"/webhooks",
+			post(handler_webhook_create)
+			.get(handler_webhook_list)
+		)
+		.route(
+			"/webhooks/:id",
+			get(handler_webhook_get)
+			.patch(handler_webhook_update)
+			.delete(handler_webhook_delete)
+		)
+		.route(
+			"/webhooks/:id/deliveries",
+			get(handler_webhook_deliveries_list)
+		)
+		.route(
+			"/webhooks/:id/deliveries/:delivery_id",
+			get(handler_webhook_delivery_get)
+		)
+		.route(
+			"/webhooks/:id/deliveries/:delivery_id/redeliver",
+			post(handler_webhook_redeliver)
+		)
+		.route_layer(middleware::from_fn_with_state(app_state.clone(), mw_admin_require))
+		.route_layer(middleware::from_fn_with_state(app_state.rate_limiter.group("webhook"), mw_rate_limit))
 		.with_state(app_state)
 }
 
@@ -0,0 +1,170 @@
+//! Public feeds of the latest posts (see `web::feed`)
+//!
+//! - `/feed.{rss,atom,json}` of all the posts, `/author/:id/feed.{rss,atom,json}` of an author.
+//! - The posts have no publication state nor tags: every post is in the feeds, and there are no tag feeds.
+//! - Conditional GET: the `ETag` changes with any item (id and `updated_at`), `Last-Modified` is the latest update.
+//! - Items link to `server.post_page_url` (the post JSON by default, so their RSS `guid` is not a permalink).
+//!
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::middleware;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use tracing::debug;
+
+use crate::config::config;
+use crate::models::author::{Author, AuthorBmc};
+use crate::models::post::PostBmc;
+use crate::models::AppState;
+use crate::web::conditional::Validators;
+use crate::web::feed::{Feed, FeedFormat, FeedItem};
+use crate::web::rate_limit::mw_rate_limit;
+use crate::web::{page_url, public_url, Path, ServerResult};
+
+pub fn routes(app_state: AppState) -> Router {
+	Router::new()
+		.route("/feed.rss", get(|state, headers| handler_feed(state, headers, None, FeedFormat::Rss)))
+		.route("/feed.atom", get(|state, headers| handler_feed(state, headers, None, FeedFormat::Atom)))
+		.route("/feed.json", get(|state, headers| handler_feed(state, headers, None, FeedFormat::Json)))
+		.route("/author/:id/feed.rss", get(|state, headers, Path(id)| handler_feed(state, headers, Some(id), FeedFormat::Rss)))
+		.route("/author/:id/feed.atom", get(|state, headers, Path(id)| handler_feed(state, headers, Some(id), FeedFormat::Atom)))
+		.route("/author/:id/feed.json", get(|state, headers, Path(id)| handler_feed(state, headers, Some(id), FeedFormat::Json)))
+		.route_layer(middleware::from_fn_with_state(app_state.rate_limiter.group("feed"), mw_rate_limit))
+		.with_state(app_state)
+}
+
+/// Handler of a feed, of all the posts or of an author's (`404` for an unknown author)
+async fn handler_feed(
+	State(app_state): State<AppState>,
+	headers: HeaderMap,
+	author_id: Option<i64>,
+	format: FeedFormat,
+) -> ServerResult<Response> {
+	debug!("{:<12} - handler_feed", "HANDLER");
+
+	let feeds_config = &config().feeds;
+
+	// -- The feed author first, so an unknown one is a 404 (not an empty feed).
+	let mut author_names: BTreeMap<i64, String> = BTreeMap::new();
+	let mut title = feeds_config.title.clone();
+	if let Some(author_id) = author_id {
+		let author: Author = AuthorBmc::get_no_auth(&app_state, author_id).await?;
+		title = format!("{title} - {}", author.name);
+		author_names.insert(author.id, author.name);
+	}
+
+	let posts = PostBmc::list_latest(&app_state, author_id, feeds_config.max_items as i64).await?;
+
+	// -- Conditional GET, before gathering the other authors.
+	let version = posts.iter().fold(
+		format!("{}:{}", format.extension(), author_id.unwrap_or_default()),
+		|version, post| format!("{version}:{}-{}", post.id, post.updated_at.unix_timestamp_nanos()),
+	);
+	let updated = posts.iter().map(|post| post.updated_at).max();
+	let validators = Validators::from_version(&version, updated.unwrap_or(time::OffsetDateTime::UNIX_EPOCH));
+	if validators.is_not_modified(&headers) {
+		return Ok(validators.not_modified());
+	}
+
+	let other_ids: BTreeSet<i64> = posts.iter().map(|post| post.author_id).filter(|id| !author_names.contains_key(id)).collect();
+	if !other_ids.is_empty() {
+		let other_ids: Vec<i64> = other_ids.into_iter().collect();
+		author_names.extend(AuthorBmc::list_names(&app_state, &other_ids).await?);
+	}
+
+	// -- Render.
+	let site_url = public_url(&headers);
+	let self_path = match author_id {
+		Some(author_id) => format!("/author/{author_id}/feed.{}", format.extension()),
+		None => format!("/feed.{}", format.extension()),
+	};
+	let items = posts
+		.into_iter()
+		.map(|post| FeedItem {
+			id: post.id,
+			url: page_url(&site_url, &config().server.post_page_url, post.id),
+			author_name: author_names.get(&post.author_id).cloned().unwrap_or_default(),
+			title: post.title,
+			content: post.content,
+			published: post.created_at,
+			updated: post.updated_at,
+		})
+		.collect();
+	let feed = Feed {
+		title,
+		description: feeds_config.description.clone(),
+		self_url: format!("{site_url}{self_path}"),
+		site_url,
+		updated,
+		items,
+	};
+
+	Ok((
+		validators.headers(),
+		[(header::CONTENT_TYPE, format.content_type())],
+		feed.render(format),
+	)
+		.into_response())
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::ctx::Ctx;
+	use anyhow::Result;
+	use axum::body::HttpBody;
+	use serde_json::Value;
+	use serial_test::serial;
+
+	async fn body_json(response: Response) -> Result<Value> {
+		let mut body = response.into_body();
+		let mut content = Vec::new();
+		while let Some(chunk) = body.data().await {
+			content.extend_from_slice(&chunk?);
+		}
+
+		Ok(serde_json::from_slice(&content)?)
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_feed_authors_and_urls() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_author = _dev_utils::seed_authors(&ctx, &app_state, &[("fx feed author", "test_feed_authors@mail", "fx-pwd")])
+			.await?
+			.remove(0);
+		let fx_posts = _dev_utils::seed_posts(&ctx, &app_state, &[
+			("test_feed_authors title 01", "content 01", 1000),
+			("test_feed_authors title 02", "content 02", fx_author.id),
+		]).await?;
+
+		// -- Exec
+		let response = handler_feed(State(app_state.clone()), HeaderMap::new(), None, FeedFormat::Json).await?;
+
+		// -- Check
+		let feed = body_json(response).await?;
+		let items = feed["items"].as_array().cloned().unwrap_or_default();
+		for (post, author_name) in fx_posts.iter().zip(["Genesis", "fx feed author"]) {
+			let item = items.iter().find(|item| item["id"].as_str() == Some(&post.id.to_string())).expect("Should be in the feed");
+			assert_eq!(item["url"], format!("http://localhost/api/post/{}", post.id));
+			assert_eq!(item["authors"][0]["name"], author_name);
+		}
+
+		// -- Clean
+		for post in fx_posts {
+			PostBmc::delete(&ctx, &app_state, post.id).await?;
+		}
+		AuthorBmc::delete(&ctx, &app_state, fx_author.id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,208 @@
+//! Liveness and readiness probes, for the orchestrator
+//!
+//! NOTE: Readiness reports `draining` (503) during a graceful shutdown (see `shutdown`).
+//!
+//! NOTE: Merged outside the middleware layers (see `main`), so probes need no auth,
+//!       and are not in the request log nor the http metrics.
+//!
+//! NOTE: The check errors are logged, the probe (unauthenticated) only sends back `check failed`.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+use tracing::{debug, warn};
+
+use crate::config::config_loaded;
+use crate::models::{AppState, ModelResult};
+
+/// Max time for each dependency check, beyond which it is down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn routes(app_state: AppState) -> Router {
+	Router::new()
+		.route("/health/live", get(handler_health_live))
+		.route("/health/ready", get(handler_health_ready))
+		.with_state(app_state)
+}
+
+// region:    --- Reports
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+	/// `live`, `ready`, `not_ready` or `draining`
+	pub status: &'static str,
+	/// Dependency name to its check (readiness only)
+	#[serde(skip_serializing_if = "BTreeMap::is_empty")]
+	pub checks: BTreeMap<&'static str, CheckReport>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+pub struct CheckReport {
+	/// `up` or `down`
+	pub status: &'static str,
+	pub latency_ms: Option<f64>,
+	/// `check failed` or `timed out` (details in the logs)
+	pub error: Option<&'static str>,
+}
+
+impl CheckReport {
+	fn is_up(&self) -> bool {
+		self.status == "up"
+	}
+}
+// endregion: --- Reports
+
+/// The process is up (no dependency checked).
+async fn handler_health_live() -> Json<HealthReport> {
+	Json(HealthReport {
+		status: "live",
+		checks: BTreeMap::new(),
+	})
+}
+
+/// The service can take traffic, 503 otherwise.
+async fn handler_health_ready(State(app_state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+	debug!("{:<12} - handler_health_ready", "HANDLER");
+
+	if app_state.shutdown.is_draining() {
+		let report = HealthReport {
+			status: "draining",
+			checks: BTreeMap::new(),
+		};
+		return (StatusCode::SERVICE_UNAVAILABLE, Json(report));
+	}
+
+	let (db, migrations) = tokio::join!(
+		check("db", app_state.db_ping(), CHECK_TIMEOUT),
+		check("migrations", app_state.db_schema_check(), CHECK_TIMEOUT),
+	);
+	let config = CheckReport {
+		status: if config_loaded() { "up" } else { "down" },
+		latency_ms: None,
+		error: None,
+	};
+
+	let checks = BTreeMap::from([("db", db), ("migrations", migrations), ("config", config)]);
+
+	let (status_code, status) = if checks.values().all(CheckReport::is_up) {
+		(StatusCode::OK, "ready")
+	} else {
+		warn!("{:<12} - not ready - {checks:?}", "HEALTH");
+		(StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+	};
+
+	(status_code, Json(HealthReport { status, checks }))
+}
+
+/// Run the check, down if not done within `timeout`.
+async fn check(name: &str, check_fut: impl Future<Output = ModelResult<()>>, timeout: Duration) -> CheckReport {
+	let start = Instant::now();
+	let res = tokio::time::timeout(timeout, check_fut).await;
+	let latency_ms = Some(start.elapsed().as_secs_f64() * 1000.);
+
+	let error = match res {
+		Ok(Ok(())) => None,
+		Ok(Err(err)) => {
+			warn!("{:<12} - {name} check failed - {err}", "HEALTH");
+			Some("check failed")
+		},
+		Err(_) => {
+			warn!("{:<12} - {name} check timed out after {}ms", "HEALTH", timeout.as_millis());
+			Some("timed out")
+		},
+	};
+
+	CheckReport {
+		status: if error.is_none() { "up" } else { "down" },
+		latency_ms,
+		error,
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::config::config;
+	use crate::models::ModelError;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_health_ready_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let _ = config();
+
+		// -- Exec
+		let (status_code, Json(report)) = handler_health_ready(State(app_state)).await;
+
+		// -- Check
+		assert_eq!(status_code, StatusCode::OK);
+		assert_eq!(report.status, "ready");
+		assert_eq!(report.checks.keys().copied().collect::<Vec<_>>(), vec!["config", "db", "migrations"]);
+		assert!(report.checks.values().all(CheckReport::is_up), "{report:?}");
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_health_ready_err_draining() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		app_state.shutdown.start_draining();
+
+		// -- Exec
+		let (status_code, Json(report)) = handler_health_ready(State(app_state)).await;
+
+		// -- Check
+		assert_eq!(status_code, StatusCode::SERVICE_UNAVAILABLE);
+		assert_eq!(report.status, "draining");
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_health_check_err_timeout() -> Result<()> {
+		// -- Exec
+		let fx_timeout = Duration::from_millis(10);
+		let report = check(
+			"fx",
+			async {
+				tokio::time::sleep(fx_timeout * 10).await;
+				Ok(())
+			},
+			fx_timeout,
+		)
+		.await;
+
+		// -- Check
+		assert!(!report.is_up());
+		assert_eq!(report.error, Some("timed out"));
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_health_check_err_not_exposed() -> Result<()> {
+		// -- Exec
+		let report = check("fx", async { Err(ModelError::EntityNotFound { entity: "fx_secret", id: 1 }) }, CHECK_TIMEOUT).await;
+
+		// -- Check
+		assert!(!report.is_up());
+		assert_eq!(report.error, Some("check failed"));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
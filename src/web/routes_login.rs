@@ -1,37 +1,49 @@
-use crate::crypt::{pwd, EncryptContent};
+use crate::crypt::token::{generate_2fa_challenge_token, validate_2fa_challenge_token, Token};
+use crate::crypt::{pwd, totp, EncryptContent};
 use crate::ctx::Ctx;
-use crate::models::author::{Author, AuthorBmc, AuthorForCreate, AuthorForLogin};
+use crate::models::author::{Author, AuthorBmc, AuthorForCreate, AuthorForLogin, AuthorForTotp};
+use crate::models::recovery_code::RecoveryCodeBmc;
 use crate::models::AppState;
-use crate::web::custom_response::CustomResponse;
+use crate::web::custom_response::{CustomResponse, CustomResponseData};
 use crate::web::error::CrudError;
-use crate::web::{self, remove_token_cookie, ServerError, ServerResult};
+use crate::web::client_ip::ClientIp;
+use crate::web::login_guard::FailOutcome;
+use crate::web::metrics::{record_login, record_login_failure, record_signup};
+use crate::web::rate_limit::mw_rate_limit;
+use crate::web::{self, remove_token_cookie, ServerError, ServerResult, ValidJson};
 use axum::extract::State;
 use axum::http::StatusCode;
+use axum::middleware;
 use axum::routing::post;
 use axum::{Json, Router};
 use axum_extra::extract::WithRejection;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::net::IpAddr;
 use tower_cookies::{Cookie, Cookies};
 use tracing::debug;
+use validator::Validate;
 
-use super::auth::SignupPayload;
+use super::auth::{LoginChallenge, LoginTwoFactorPayload, SignupPayload};
 use super::{IncomingServerRequest, ServerResponse};
 
 pub fn routes(app_state: AppState) -> Router {
 	Router::new()
 		.route("/api/signup", post(api_signup_handler))
 		.route("/api/login", post(api_login_handler))
+		.route("/api/login/2fa", post(api_login_2fa_handler))
 		.route("/api/logoff", post(api_logoff_handler))
+		.route_layer(middleware::from_fn_with_state(app_state.rate_limiter.group("auth"), mw_rate_limit))
 		.with_state(app_state)
 }
 
 // region:    --- Login
 async fn api_login_handler(
 	State(app_state): State<AppState>,
+	ClientIp(client_ip): ClientIp,
 	cookies: Cookies,
-	Json(payload): Json<LoginPayload>,
-) -> ServerResponse<()> {
+	WithRejection(ValidJson(payload), _): IncomingServerRequest<LoginPayload>,
+) -> ServerResponse<LoginChallenge> {
 	debug!("{:<12} - api_login_handler", "HANDLER");
 
 	let LoginPayload {
@@ -39,30 +51,118 @@ async fn api_login_handler(
 		password: pwd_clear,
 	} = payload;
 
-	// -- Get the author.
-	let author: AuthorForLogin = AuthorBmc::first_by_email(&app_state, &email)
-		.await?
-		.ok_or(ServerError::LoginFailEmailNotFound)?;
-	let author_id = author.id;
+	let login_guard = &app_state.login_guard;
 
-	let ctx = Ctx::new(author_id);
+	// -- Reject early if the IP or account is backing off.
+	login_guard.check(client_ip, &email).map_err(|retry_after_sec| {
+		let err = ServerError::LoginFailTooManyAttempts { retry_after_sec };
+		record_login_failure(err.as_ref());
+		err
+	})?;
 
-	// -- Validate the password.
-	let Some(pwd) = author.password else {
-		return Err(ServerError::LoginFailAuthorHasNoPwd{ author_id });
+	// -- Check the credentials, and track the outcome.
+	let author = match login_check_credentials(&app_state, &email, &pwd_clear).await {
+		Ok(author) => author,
+		Err(err @ (ServerError::LoginFailEmailNotFound
+			| ServerError::LoginFailAuthorHasNoPwd { .. }
+			| ServerError::LoginFailPwdNotMatching { .. })) => {
+			return Err(track_login_failure(&app_state, client_ip, &email, err));
+		},
+		Err(err) => return Err(err),
 	};
 
-	pwd::validate_pwd(
-		&EncryptContent {
-			salt: author.password_salt.to_string(),
-			content: pwd_clear.clone(),
+	login_finish(&app_state, &cookies, &author, "password")
+}
+
+/// Finish the login of an authenticated author, setting the web token,
+/// or, if two-factor is enabled, sending back a challenge for the second step.
+///
+/// `login_method` is the metrics label of the login (`password`, `oidc`).
+pub(super) fn login_finish(
+	app_state: &AppState,
+	cookies: &Cookies,
+	author: &AuthorForLogin,
+	login_method: &'static str,
+) -> ServerResponse<LoginChallenge> {
+	// -- If two-factor is enabled, the login is pending the second factor.
+	if author.totp_enabled {
+		let challenge_token = generate_2fa_challenge_token(&author.email, &author.token_salt.to_string())?;
+
+		let response = Json(
+			CustomResponse::new(
+				true,
+				Some("Two-factor authentication required".to_string()),
+				Some(CustomResponseData::Item(LoginChallenge {
+					two_factor_required: true,
+					challenge_token: challenge_token.to_string()
+				}))
+			)
+		);
+
+		return Ok((StatusCode::ACCEPTED, response));
+	}
+
+	app_state.login_guard.record_success(&author.email);
+	record_login(login_method);
+
+	// -- Set web token.
+	web::set_token_cookie(cookies, &author.email, &author.token_salt.to_string())?;
+	web::set_csrf_cookie(cookies);
+
+	// Create the success body.
+	let response = Json(
+		CustomResponse::new(
+			true,
+			Some("Logged in successfully".to_string()),
+			None
+		)
+	);
+
+	Ok((StatusCode::OK, response))
+}
+
+/// Second step of the login, for authors with two-factor enabled.
+async fn api_login_2fa_handler(
+	State(app_state): State<AppState>,
+	ClientIp(client_ip): ClientIp,
+	cookies: Cookies,
+	WithRejection(ValidJson(payload), _): IncomingServerRequest<LoginTwoFactorPayload>,
+) -> ServerResponse<()> {
+	debug!("{:<12} - api_login_2fa_handler", "HANDLER");
+
+	let login_guard = &app_state.login_guard;
+
+	// -- Parse the challenge token.
+	let token: Token = payload
+		.challenge_token
+		.parse()
+		.map_err(|_| ServerError::LoginFail2faChallengeInvalid)?;
+	let email = token.ident.clone();
+
+	// -- Reject early if the IP or account is backing off.
+	login_guard.check(client_ip, &email).map_err(|retry_after_sec| {
+		let err = ServerError::LoginFailTooManyAttempts { retry_after_sec };
+		record_login_failure(err.as_ref());
+		err
+	})?;
+
+	// -- Check the second factor, and track the outcome.
+	//    (An invalid challenge is not tracked, as its email cannot be trusted)
+	let author = match login_check_second_factor(&app_state, &token, &payload).await {
+		Ok(author) => author,
+		Err(err @ ServerError::LoginFail2faCodeNotMatching { .. }) => {
+			return Err(track_login_failure(&app_state, client_ip, &email, err));
 		},
-		&pwd,
-	)
-	.map_err(|_| ServerError::LoginFailPwdNotMatching { author_id })?;
+		Err(err) => return Err(err),
+	};
+
+	login_guard.record_success(&email);
+	// (The first step method is not known here)
+	record_login("2fa");
 
 	// -- Set web token.
 	web::set_token_cookie(&cookies, &author.email, &author.token_salt.to_string())?;
+	web::set_csrf_cookie(&cookies);
 
 	// Create the success body.
 	let response = Json(
@@ -76,8 +176,96 @@ async fn api_login_handler(
 	Ok((StatusCode::OK, response))
 }
 
+/// Record a failed login attempt, returning the error to send back.
+fn track_login_failure(
+	app_state: &AppState,
+	client_ip: IpAddr,
+	email: &str,
+	err: ServerError,
+) -> ServerError {
+	record_login_failure(err.as_ref());
 
-#[derive(Debug, Deserialize)]
+	match app_state.login_guard.record_failure(client_ip, email) {
+		FailOutcome::LockedOut { retry_after_sec } => ServerError::LoginFailLockedOut { retry_after_sec },
+		FailOutcome::Recorded => err,
+	}
+}
+
+/// Get the author and validate the password.
+///
+/// NOTE: A password validation is always performed (against a dummy reference if need be),
+///       so that response times do not leak whether the email exists.
+async fn login_check_credentials(
+	app_state: &AppState,
+	email: &str,
+	pwd_clear: &str,
+) -> ServerResult<AuthorForLogin> {
+	// -- Get the author.
+	let Some(author) = AuthorBmc::first_by_email::<AuthorForLogin>(app_state, email).await? else {
+		pwd::validate_pwd_dummy(pwd_clear);
+		return Err(ServerError::LoginFailEmailNotFound);
+	};
+	let author_id = author.id;
+
+	// -- Validate the password.
+	let Some(pwd) = &author.password else {
+		pwd::validate_pwd_dummy(pwd_clear);
+		return Err(ServerError::LoginFailAuthorHasNoPwd{ author_id });
+	};
+
+	pwd::validate_pwd(
+		&EncryptContent {
+			salt: author.password_salt.to_string(),
+			content: pwd_clear.to_string(),
+		},
+		pwd,
+	)
+	.map_err(|_| ServerError::LoginFailPwdNotMatching { author_id })?;
+
+	Ok(author)
+}
+
+/// Validate the challenge token, then the TOTP code or recovery code.
+async fn login_check_second_factor(
+	app_state: &AppState,
+	token: &Token,
+	payload: &LoginTwoFactorPayload,
+) -> ServerResult<AuthorForTotp> {
+	// -- Get the author and validate the challenge token.
+	let author: AuthorForTotp = AuthorBmc::first_by_email(app_state, &token.ident)
+		.await?
+		.ok_or(ServerError::LoginFail2faChallengeInvalid)?;
+
+	validate_2fa_challenge_token(token, &author.token_salt.to_string())
+		.map_err(|_| ServerError::LoginFail2faChallengeInvalid)?;
+
+	let author_id = author.id;
+
+	let (true, Some(totp_secret)) = (author.totp_enabled, &author.totp_secret) else {
+		return Err(ServerError::LoginFail2faChallengeInvalid);
+	};
+
+	// -- Validate the second factor (each code can only be used once).
+	let is_valid = match (&payload.code, &payload.recovery_code) {
+		(Some(code), _) => match totp::validate_totp(totp_secret, code, author.totp_last_step) {
+			Ok(step) => AuthorBmc::consume_totp_step(app_state, author_id, step).await?,
+			Err(_) => false,
+		},
+		(None, Some(recovery_code)) => {
+			let code_hash = totp::hash_recovery_code(recovery_code, &author.password_salt.to_string())?;
+			RecoveryCodeBmc::consume(app_state, author_id, &code_hash).await?
+		},
+		(None, None) => false,
+	};
+
+	if !is_valid {
+		return Err(ServerError::LoginFail2faCodeNotMatching { author_id });
+	}
+
+	Ok(author)
+}
+
+#[derive(Debug, Deserialize, Validate)]
 struct LoginPayload {
 	email: String,
 	password: String,
@@ -88,7 +276,7 @@ struct LoginPayload {
 async fn api_signup_handler(
 	State(app_state): State<AppState>,
 	cookies: Cookies,
-	WithRejection(Json(payload), _): IncomingServerRequest<SignupPayload>,
+	WithRejection(ValidJson(payload), _): IncomingServerRequest<SignupPayload>,
 ) -> ServerResponse<()> {
 	debug!("{:<12} - api_signup_handler", "HANDLER");
 
@@ -126,6 +314,8 @@ async fn api_signup_handler(
 	// update author pwd
 	AuthorBmc::update_pwd(&ctx, &app_state, author_id, &pwd_clear).await?;
 
+	record_signup("password");
+
 	// Create the success body.
 	let response = Json(
 		CustomResponse::<()>::new(
@@ -144,7 +334,7 @@ async fn api_signup_handler(
 // region:    --- Logoff
 async fn api_logoff_handler(
 	cookies: Cookies,
-	Json(payload): Json<LogoffPayload>,
+	WithRejection(ValidJson(payload), _): IncomingServerRequest<LogoffPayload>,
 ) -> ServerResponse<()> {
 	debug!("{:<12} - api_logoff_handler", "HANDLER");
 	let should_logoff = payload.logoff;
@@ -165,8 +355,143 @@ async fn api_logoff_handler(
 	Ok((StatusCode::OK, body))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 struct LogoffPayload {
 	logoff: bool,
 }
-// endregion: --- Logoff
\ No newline at end of file
+// endregion: --- Logoff
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::web::handlers::two_factor::{handler_2fa_confirm, handler_2fa_enroll};
+	use crate::web::auth::TwoFactorCodePayload;
+	use crate::web::AUTH_TOKEN;
+	use anyhow::Result;
+	use serial_test::serial;
+	use std::marker::PhantomData;
+
+	/// An author with two-factor enabled (through the enrollment handlers), with its TOTP secret and recovery codes.
+	async fn fx_author_2fa(app_state: &AppState, email: &str) -> Result<(i64, String, Vec<String>)> {
+		let ctx = Ctx::root_ctx();
+		let author = _dev_utils::seed_authors(&ctx, app_state, &[(email, email, "fx-password")]).await?.remove(0);
+		AuthorBmc::update_pwd(&ctx, app_state, author.id, "fx-password").await?;
+		let author_ctx = Ctx::new(author.id)?;
+
+		let (_, Json(enrollment)) = handler_2fa_enroll(author_ctx.clone(), State(app_state.clone())).await?;
+		let Some(CustomResponseData::Item(enrollment)) = enrollment.data else {
+			panic!("Should have sent back the enrollment");
+		};
+		let code = totp::totp_code_now(&enrollment.secret, 0)?;
+		let payload = WithRejection(ValidJson(TwoFactorCodePayload { code }), PhantomData);
+		let (_, Json(confirmed)) = handler_2fa_confirm(author_ctx, State(app_state.clone()), payload).await?;
+		let Some(CustomResponseData::Collection(recovery_codes)) = confirmed.data else {
+			panic!("Should have sent back the recovery codes");
+		};
+
+		Ok((author.id, enrollment.secret, recovery_codes))
+	}
+
+	/// The first step of the login, its challenge token.
+	async fn fx_login_challenge(app_state: &AppState, client_ip: IpAddr, email: &str) -> Result<String> {
+		let cookies = Cookies::default();
+		let payload = LoginPayload { email: email.to_string(), password: "fx-password".to_string() };
+
+		let (status, Json(res)) =
+			api_login_handler(State(app_state.clone()), ClientIp(client_ip), cookies.clone(), WithRejection(ValidJson(payload), PhantomData)).await?;
+
+		assert_eq!(status, StatusCode::ACCEPTED);
+		assert!(cookies.get(AUTH_TOKEN).is_none(), "Should not be logged in before the second factor");
+		let Some(CustomResponseData::Item(challenge)) = res.data else {
+			panic!("Should have sent back a challenge");
+		};
+		assert!(challenge.two_factor_required);
+
+		Ok(challenge.challenge_token)
+	}
+
+	async fn login_2fa(
+		app_state: &AppState,
+		client_ip: IpAddr,
+		challenge_token: &str,
+		code: Option<&str>,
+		recovery_code: Option<&str>,
+	) -> ServerResult<Cookies> {
+		let cookies = Cookies::default();
+		let payload = LoginTwoFactorPayload {
+			challenge_token: challenge_token.to_string(),
+			code: code.map(String::from),
+			recovery_code: recovery_code.map(String::from),
+		};
+
+		api_login_2fa_handler(State(app_state.clone()), ClientIp(client_ip), cookies.clone(), WithRejection(ValidJson(payload), PhantomData)).await?;
+
+		Ok(cookies)
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_login_2fa_code_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let fx_email = "test_login_2fa_code_ok@mail";
+		let fx_ip: IpAddr = "198.51.100.27".parse()?;
+		let (author_id, secret, _) = fx_author_2fa(&app_state, fx_email).await?;
+		let challenge_token = fx_login_challenge(&app_state, fx_ip, fx_email).await?;
+		// (the current step was used by the confirmation)
+		let fx_code = totp::totp_code_now(&secret, 1)?;
+
+		// -- Exec
+		let cookies = login_2fa(&app_state, fx_ip, &challenge_token, Some(&fx_code), None).await?;
+
+		// -- Check
+		assert!(cookies.get(AUTH_TOKEN).is_some(), "Should be logged in");
+
+		// (the code is used, even with a new challenge)
+		let challenge_token = fx_login_challenge(&app_state, fx_ip, fx_email).await?;
+		let res = login_2fa(&app_state, fx_ip, &challenge_token, Some(&fx_code), None).await;
+		assert!(matches!(res, Err(ServerError::LoginFail2faCodeNotMatching { .. })), "{res:?}");
+
+		// (a tampered challenge)
+		let res = login_2fa(&app_state, fx_ip, &format!("{challenge_token}x"), Some("000000"), None).await;
+		assert!(matches!(res, Err(ServerError::LoginFail2faChallengeInvalid)), "{res:?}");
+
+		// -- Clean
+		AuthorBmc::delete(&Ctx::root_ctx(), &app_state, author_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_login_2fa_recovery_code_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let fx_email = "test_login_2fa_recovery_code_ok@mail";
+		let fx_ip: IpAddr = "198.51.100.28".parse()?;
+		let (author_id, _, recovery_codes) = fx_author_2fa(&app_state, fx_email).await?;
+		let fx_recovery_code = recovery_codes[0].to_uppercase();
+
+		// -- Exec
+		let challenge_token = fx_login_challenge(&app_state, fx_ip, fx_email).await?;
+		let cookies = login_2fa(&app_state, fx_ip, &challenge_token, None, Some(&fx_recovery_code)).await?;
+
+		// -- Check
+		assert!(cookies.get(AUTH_TOKEN).is_some(), "Should be logged in");
+
+		// (consumed)
+		let challenge_token = fx_login_challenge(&app_state, fx_ip, fx_email).await?;
+		let res = login_2fa(&app_state, fx_ip, &challenge_token, None, Some(&fx_recovery_code)).await;
+		assert!(matches!(res, Err(ServerError::LoginFail2faCodeNotMatching { .. })), "{res:?}");
+
+		// (the other ones are still valid)
+		login_2fa(&app_state, fx_ip, &challenge_token, None, Some(&recovery_codes[1])).await?;
+
+		// -- Clean
+		AuthorBmc::delete(&Ctx::root_ctx(), &app_state, author_id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,381 @@
+use crate::config;
+use crate::ctx::Ctx;
+use crate::models::author::{AuthorBmc, AuthorForCreateNoPwd, AuthorForLogin};
+use crate::models::author_identity::{AuthorIdentityBmc, AuthorIdentityForCreate};
+use crate::models::AppState;
+use crate::oidc::{self, IdTokenClaims, OidcFlow};
+use crate::web::custom_response::CustomResponse;
+use crate::web::metrics::record_signup;
+use crate::web::routes_login::login_finish;
+use crate::web::{Path, Query, ServerError, ServerResult};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::Redirect;
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use serde::Deserialize;
+use tower_cookies::{Cookie, Cookies};
+use tracing::debug;
+
+use super::auth::LoginChallenge;
+use super::ServerResponse;
+
+const OIDC_FLOW: &str = "oidc-flow";
+const OIDC_FLOW_PATH: &str = "/api/oidc";
+/// Max length of `authors.name`
+const AUTHOR_NAME_MAX_LEN: usize = 128;
+
+pub fn routes(app_state: AppState) -> Router {
+	Router::new()
+		.route("/api/oidc/:provider/login", get(api_oidc_login_handler))
+		.route("/api/oidc/:provider/callback", get(api_oidc_callback_handler))
+		.route("/api/oidc/:provider", delete(api_oidc_unlink_handler))
+		.with_state(app_state)
+}
+
+// region:    --- Login
+/// Redirect to the provider, keeping the flow in a signed cookie until the callback.
+async fn api_oidc_login_handler(
+	State(app_state): State<AppState>,
+	cookies: Cookies,
+	Path(provider): Path<String>,
+) -> ServerResult<Redirect> {
+	debug!("{:<12} - api_oidc_login_handler", "HANDLER");
+
+	let provider = oidc::provider_config(&provider).map_err(|_| ServerError::OidcProviderNotFound)?;
+
+	let (authorization_url, flow) = app_state.oidc.start_flow(provider).await?;
+
+	let mut cookie = Cookie::new(OIDC_FLOW, flow.encode(&config().auth.token_key)?);
+	cookie.set_http_only(true);
+	cookie.set_path(OIDC_FLOW_PATH);
+	cookies.add(cookie);
+
+	Ok(Redirect::to(&authorization_url))
+}
+
+/// Back from the provider: link the identity if logged in, otherwise log in (signing up if need be).
+async fn api_oidc_callback_handler(
+	State(app_state): State<AppState>,
+	ctx: Option<Ctx>,
+	cookies: Cookies,
+	Path(provider): Path<String>,
+	Query(params): Query<CallbackParams>,
+) -> ServerResponse<LoginChallenge> {
+	debug!("{:<12} - api_oidc_callback_handler", "HANDLER");
+
+	let provider = oidc::provider_config(&provider).map_err(|_| ServerError::OidcProviderNotFound)?;
+
+	let flow = take_flow(&cookies, &provider.name, params.state.as_deref())?;
+
+	let Some(code) = params.code else {
+		// e.g., the user denied access at the provider.
+		return Err(ServerError::OidcAuthorizationDenied(params.error.unwrap_or_default()));
+	};
+
+	// -- Finish the flow.
+	let claims = app_state.oidc.finish_flow(provider, &flow, &code).await?;
+
+	let identity = AuthorIdentityBmc::first_by_provider_subject(&app_state, &provider.name, &claims.sub).await?;
+
+	// -- Logged in, link the identity.
+	if let Some(ctx) = ctx {
+		match identity {
+			Some(identity) if identity.author_id != ctx.user_id() => {
+				return Err(ServerError::OidcIdentityLinkedToOtherAuthor);
+			},
+			Some(_) => (),
+			None => {
+				let identities = AuthorIdentityBmc::list_for_author(&ctx, &app_state, ctx.user_id()).await?;
+				if identities.iter().any(|identity| identity.provider == provider.name) {
+					return Err(ServerError::OidcProviderAlreadyLinked);
+				}
+
+				link_identity(&app_state, ctx.user_id(), &provider.name, &claims).await?;
+			},
+		}
+
+		let response = Json(
+			CustomResponse::new(
+				true,
+				Some(format!("Linked to your {} account", provider.name)),
+				None
+			)
+		);
+
+		return Ok((StatusCode::OK, response));
+	}
+
+	// -- Known identity, log in.
+	let author_id = match identity {
+		Some(identity) => identity.author_id,
+		None => oidc_signup(&app_state, &provider.name, &claims).await?,
+	};
+
+	let author: AuthorForLogin = AuthorBmc::get_no_auth(&app_state, author_id).await?;
+
+	login_finish(&app_state, &cookies, &author, "oidc")
+}
+
+/// Get the flow of the callback from its cookie (single use, removed), checking it is for this provider and `state`.
+fn take_flow(cookies: &Cookies, provider: &str, state: Option<&str>) -> ServerResult<OidcFlow> {
+	let flow_cookie = cookies.get(OIDC_FLOW).map(|c| c.value().to_string());
+	let mut cookie = Cookie::named(OIDC_FLOW);
+	cookie.set_path(OIDC_FLOW_PATH);
+	cookies.remove(cookie);
+
+	let flow = flow_cookie
+		.ok_or(ServerError::OidcFlowInvalid)
+		.and_then(|value| OidcFlow::decode(&value, &config().auth.token_key).map_err(|_| ServerError::OidcFlowInvalid))?;
+
+	if flow.provider != provider || state != Some(flow.state.as_str()) {
+		return Err(ServerError::OidcFlowInvalid);
+	}
+
+	Ok(flow)
+}
+
+/// Create an author (without password) for a new identity.
+///
+/// NOTE: An identity is never linked automatically to an existing author with the same email,
+///       the author must log in first and link it (so that a provider cannot take over an account).
+async fn oidc_signup(
+	app_state: &AppState,
+	provider: &str,
+	claims: &IdTokenClaims,
+) -> ServerResult<i64> {
+	let (Some(email), true) = (&claims.email, claims.email_verified) else {
+		return Err(ServerError::OidcEmailNotVerified);
+	};
+
+	if AuthorBmc::first_by_email::<AuthorForLogin>(app_state, email).await?.is_some() {
+		return Err(ServerError::OidcEmailTaken);
+	}
+
+	let name = claims
+		.name
+		.as_deref()
+		.filter(|name| !name.trim().is_empty())
+		.unwrap_or(email)
+		.chars()
+		.take(AUTHOR_NAME_MAX_LEN)
+		.collect();
+
+	let author_id = AuthorBmc::create_no_pwd(app_state, AuthorForCreateNoPwd { name, email: email.clone() }).await?;
+
+	// -- Link the identity, not leaving an author without login method behind.
+	if let Err(err) = link_identity(app_state, author_id, provider, claims).await {
+		AuthorBmc::delete(&Ctx::root_ctx(), app_state, author_id).await?;
+		return Err(err);
+	}
+
+	record_signup("oidc");
+
+	Ok(author_id)
+}
+
+async fn link_identity(
+	app_state: &AppState,
+	author_id: i64,
+	provider: &str,
+	claims: &IdTokenClaims,
+) -> ServerResult<()> {
+	let data = AuthorIdentityForCreate {
+		author_id,
+		provider: provider.to_string(),
+		subject: claims.sub.clone(),
+		email: claims.email.clone(),
+	};
+
+	AuthorIdentityBmc::create(app_state, data).await?;
+
+	Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct CallbackParams {
+	code: Option<String>,
+	state: Option<String>,
+	error: Option<String>,
+}
+// endregion: --- Login
+
+// region:    --- Unlink
+/// Unlink a provider identity, unless it is the only way left for the author to log in.
+async fn api_oidc_unlink_handler(
+	State(app_state): State<AppState>,
+	ctx: Ctx,
+	Path(provider): Path<String>,
+) -> ServerResponse<()> {
+	debug!("{:<12} - api_oidc_unlink_handler", "HANDLER");
+
+	let author_id = ctx.user_id();
+
+	// -- Checks & Guards
+	let author: AuthorForLogin = AuthorBmc::get(&ctx, &app_state, author_id).await?;
+	let identities = AuthorIdentityBmc::list_for_author(&ctx, &app_state, author_id).await?;
+
+	if !identities.iter().any(|identity| identity.provider == provider) {
+		return Err(ServerError::OidcIdentityNotLinked);
+	}
+
+	if author.password.is_none() && identities.len() == 1 {
+		return Err(ServerError::OidcIdentityIsLastLoginMethod);
+	}
+
+	// -- Unlink
+	AuthorIdentityBmc::delete_for_author_provider(&ctx, &app_state, author_id, &provider).await?;
+
+	let response = Json(
+		CustomResponse::new(
+			true,
+			Some(format!("Unlinked your {provider} account")),
+			None
+		)
+	);
+
+	Ok((StatusCode::OK, response))
+}
+// endregion: --- Unlink
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use serde_json::json;
+	use serial_test::serial;
+
+	fn fx_claims(sub: &str, email: &str, email_verified: bool) -> IdTokenClaims {
+		serde_json::from_value(json!({ "sub": sub, "email": email, "email_verified": email_verified, "name": "Fx Oidc" }))
+			.unwrap()
+	}
+
+	/// The flow cookie, as set by the login handler.
+	fn fx_flow_cookies(flow: &OidcFlow) -> Result<Cookies> {
+		let cookies = Cookies::default();
+		cookies.add(Cookie::new(OIDC_FLOW, flow.encode(&config().auth.token_key)?));
+
+		Ok(cookies)
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_oidc_login_err_provider_not_found() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+
+		// -- Exec
+		let res = api_oidc_login_handler(State(app_state), Cookies::default(), Path("fx-unknown".to_string())).await;
+
+		// -- Check
+		assert!(matches!(res, Err(ServerError::OidcProviderNotFound)), "{res:?}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_oidc_take_flow() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_flow = OidcFlow::new("mock");
+
+		// -- Exec & Check
+		let cookies = fx_flow_cookies(&fx_flow)?;
+		let flow = take_flow(&cookies, "mock", Some(&fx_flow.state))?;
+		assert_eq!(flow.state, fx_flow.state);
+		// (single use)
+		let res = take_flow(&cookies, "mock", Some(&fx_flow.state));
+		assert!(matches!(res, Err(ServerError::OidcFlowInvalid)), "{res:?}");
+
+		// (state or provider not matching)
+		let res = take_flow(&fx_flow_cookies(&fx_flow)?, "mock", Some("fx-other-state"));
+		assert!(matches!(res, Err(ServerError::OidcFlowInvalid)), "{res:?}");
+		let res = take_flow(&fx_flow_cookies(&fx_flow)?, "mock", None);
+		assert!(matches!(res, Err(ServerError::OidcFlowInvalid)), "{res:?}");
+		let res = take_flow(&fx_flow_cookies(&fx_flow)?, "other", Some(&fx_flow.state));
+		assert!(matches!(res, Err(ServerError::OidcFlowInvalid)), "{res:?}");
+
+		// (tampered)
+		let cookies = Cookies::default();
+		cookies.add(Cookie::new(OIDC_FLOW, format!("{}x", fx_flow.encode(&config().auth.token_key)?)));
+		let res = take_flow(&cookies, "mock", Some(&fx_flow.state));
+		assert!(matches!(res, Err(ServerError::OidcFlowInvalid)), "{res:?}");
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_oidc_signup_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let fx_signup_claims = fx_claims("fx-sub-signup", "test_oidc_signup_ok@mail", true);
+
+		// -- Exec
+		let author_id = oidc_signup(&app_state, "mock", &fx_signup_claims).await?;
+
+		// -- Check
+		let identity = AuthorIdentityBmc::first_by_provider_subject(&app_state, "mock", "fx-sub-signup").await?;
+		assert_eq!(identity.map(|identity| identity.author_id), Some(author_id));
+		let author: AuthorForLogin = AuthorBmc::get_no_auth(&app_state, author_id).await?;
+		assert!(author.password.is_none());
+
+		// (the email is now taken)
+		let res = oidc_signup(&app_state, "mock", &fx_claims("fx-sub-other", "test_oidc_signup_ok@mail", true)).await;
+		assert!(matches!(res, Err(ServerError::OidcEmailTaken)), "{res:?}");
+
+		// -- Clean
+		AuthorBmc::delete(&Ctx::root_ctx(), &app_state, author_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_oidc_signup_err_email_not_verified() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let fx_signup_claims = fx_claims("fx-sub-not-verified", "test_oidc_signup_err_email_not_verified@mail", false);
+
+		// -- Exec
+		let res = oidc_signup(&app_state, "mock", &fx_signup_claims).await;
+
+		// -- Check
+		assert!(matches!(res, Err(ServerError::OidcEmailNotVerified)), "{res:?}");
+		let identity = AuthorIdentityBmc::first_by_provider_subject(&app_state, "mock", "fx-sub-not-verified").await?;
+		assert!(identity.is_none());
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_oidc_unlink() -> Result<()> {
+		// -- Setup & Fixtures
+		let app_state = _dev_utils::init_test().await;
+		let author_id = oidc_signup(&app_state, "mock", &fx_claims("fx-sub-unlink", "test_oidc_unlink@mail", true)).await?;
+		let ctx = Ctx::new(author_id)?;
+
+		// -- Exec & Check
+		let res = api_oidc_unlink_handler(State(app_state.clone()), ctx.clone(), Path("other".to_string())).await;
+		assert!(matches!(res, Err(ServerError::OidcIdentityNotLinked)), "{res:?}");
+
+		// (no password, the only login method)
+		let res = api_oidc_unlink_handler(State(app_state.clone()), ctx.clone(), Path("mock".to_string())).await;
+		assert!(matches!(res, Err(ServerError::OidcIdentityIsLastLoginMethod)), "{res:?}");
+
+		AuthorBmc::update_pwd(&ctx, &app_state, author_id, "fx-password").await?;
+		let (status, Json(res)) = api_oidc_unlink_handler(State(app_state.clone()), ctx, Path("mock".to_string())).await?;
+		assert_eq!(status, StatusCode::OK);
+		assert!(res.status);
+		let identity = AuthorIdentityBmc::first_by_provider_subject(&app_state, "mock", "fx-sub-unlink").await?;
+		assert!(identity.is_none());
+
+		// -- Clean
+		AuthorBmc::delete(&Ctx::root_ctx(), &app_state, author_id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,166 @@
+//! Sitemap of the public pages (authors and posts) and `robots.txt` (see `web::sitemap`)
+//!
+//! NOTE: Merged ahead of the static files fallback, so these win over the same files in the web folder.
+//!
+//! - The URLs are the pages of `server.author_page_url` and `server.post_page_url` (the JSON by default).
+//!
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::middleware;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use tracing::debug;
+
+use crate::config::config;
+use crate::models::author::AuthorBmc;
+use crate::models::post::PostBmc;
+use crate::models::{AppState, ModelError};
+use crate::web::rate_limit::mw_rate_limit;
+use crate::web::sitemap::{self, SitemapUrl, MAX_URLS};
+use crate::web::{page_url, public_url, Path, ServerResult};
+
+const XML_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+pub fn routes(app_state: AppState) -> Router {
+	Router::new()
+		.route("/sitemap.xml", get(handler_sitemap))
+		.route("/sitemap/:page", get(handler_sitemap_page))
+		.route("/robots.txt", get(handler_robots))
+		.route_layer(middleware::from_fn_with_state(app_state.rate_limiter.group("sitemap"), mw_rate_limit))
+		.with_state(app_state)
+}
+
+/// Handler of the sitemap, or of its index when above `MAX_URLS`
+async fn handler_sitemap(State(app_state): State<AppState>, headers: HeaderMap) -> ServerResult<Response> {
+	debug!("{:<12} - handler_sitemap", "HANDLER");
+
+	let author_count = AuthorBmc::count(&app_state).await?;
+	let post_count = PostBmc::count(&app_state).await?;
+
+	let page_count = sitemap::page_count(author_count + post_count, MAX_URLS);
+	if page_count == 1 {
+		return sitemap_page(&app_state, &headers, author_count, 1).await;
+	}
+
+	let base_url = public_url(&headers);
+	let locs: Vec<String> = (1..=page_count).map(|page| format!("{base_url}/sitemap/{page}.xml")).collect();
+
+	Ok(([(header::CONTENT_TYPE, XML_CONTENT_TYPE)], sitemap::render_index(&locs)).into_response())
+}
+
+/// Handler of a page of the sitemap index (e.g., `/sitemap/2.xml`)
+async fn handler_sitemap_page(
+	State(app_state): State<AppState>,
+	headers: HeaderMap,
+	Path(page): Path<String>,
+) -> ServerResult<Response> {
+	debug!("{:<12} - handler_sitemap_page", "HANDLER");
+
+	let author_count = AuthorBmc::count(&app_state).await?;
+	let post_count = PostBmc::count(&app_state).await?;
+
+	let page = page
+		.strip_suffix(".xml")
+		.and_then(|page| page.parse::<i64>().ok())
+		.filter(|page| (1..=sitemap::page_count(author_count + post_count, MAX_URLS)).contains(page))
+		.ok_or(ModelError::EntityNotFound { entity: "sitemap", id: 0 })?;
+
+	sitemap_page(&app_state, &headers, author_count, page).await
+}
+
+async fn sitemap_page(app_state: &AppState, headers: &HeaderMap, author_count: i64, page: i64) -> ServerResult<Response> {
+	let ((author_offset, author_limit), (post_offset, post_limit)) = sitemap::page_ranges(author_count, page, MAX_URLS);
+
+	let server_config = &config().server;
+	let base_url = public_url(headers);
+
+	let mut urls = Vec::new();
+	if author_limit > 0 {
+		let authors = AuthorBmc::list_versions(app_state, author_offset, author_limit).await?;
+		urls.extend(authors.into_iter().map(|(id, lastmod)| SitemapUrl {
+			loc: page_url(&base_url, &server_config.author_page_url, id),
+			lastmod,
+		}));
+	}
+	if post_limit > 0 {
+		let posts = PostBmc::list_versions(app_state, post_offset, post_limit).await?;
+		urls.extend(posts.into_iter().map(|(id, lastmod)| SitemapUrl {
+			loc: page_url(&base_url, &server_config.post_page_url, id),
+			lastmod,
+		}));
+	}
+
+	Ok(([(header::CONTENT_TYPE, XML_CONTENT_TYPE)], sitemap::render_urlset(&urls)).into_response())
+}
+
+/// Handler of `robots.txt`, `robots.content` when set, else allowing all but `robots.disallow`
+async fn handler_robots(headers: HeaderMap) -> Response {
+	debug!("{:<12} - handler_robots", "HANDLER");
+
+	let robots_config = &config().robots;
+
+	let content = match &robots_config.content {
+		Some(content) => content.clone(),
+		None => robots_txt(&robots_config.disallow, &public_url(&headers)),
+	};
+
+	([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], content).into_response()
+}
+
+fn robots_txt(disallow: &[String], base_url: &str) -> String {
+	let mut content = String::from("User-agent: *\n");
+	if disallow.is_empty() {
+		// (an empty `Disallow` allows all)
+		content.push_str("Disallow:\n");
+	}
+	for path in disallow {
+		content.push_str(&format!("Disallow: {path}\n"));
+	}
+	content.push_str(&format!("\nSitemap: {base_url}/sitemap.xml\n"));
+
+	content
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[test]
+	fn test_robots_txt() -> Result<()> {
+		// -- Exec
+		let all = robots_txt(&[], "https://blog.example.com");
+		let some = robots_txt(&["/api/media/".to_string(), "/admin/".to_string()], "https://blog.example.com");
+
+		// -- Check
+		assert_eq!(all, "User-agent: *\nDisallow:\n\nSitemap: https://blog.example.com/sitemap.xml\n");
+		assert_eq!(
+			some,
+			"User-agent: *\nDisallow: /api/media/\nDisallow: /admin/\n\nSitemap: https://blog.example.com/sitemap.xml\n"
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_sitemap_page_urls() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_headers = |host: &str| HeaderMap::from_iter([(header::HOST, host.parse().unwrap())]);
+
+		// -- Exec & Check
+		// (`server.public_url` not set in the tests, from the `Host`, when only a host and port)
+		assert_eq!(public_url(&fx_headers("blog.example.com:8080")), "http://blog.example.com:8080");
+		assert_eq!(public_url(&fx_headers("evil.example.com/x?y")), "http://localhost");
+		assert_eq!(public_url(&fx_headers("user@evil.example.com")), "http://localhost");
+		assert_eq!(public_url(&HeaderMap::new()), "http://localhost");
+
+		assert_eq!(page_url("https://blog.example.com", "/posts/{id}", 1000), "https://blog.example.com/posts/1000");
+		assert_eq!(page_url("https://blog.example.com", "https://www.example.com/p/{id}.html", 7), "https://www.example.com/p/7.html");
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
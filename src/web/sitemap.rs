@@ -0,0 +1,119 @@
+//! Rendering of the sitemaps (sitemaps.org protocol 0.9)
+//!
+//! NOTE: Pure rendering and paging, the routes (`web::routes_sitemap`) list the urls.
+//!
+//! - A sitemap has at most `MAX_URLS` urls: above, `/sitemap.xml` is an index of pages (`/sitemap/1.xml`, ...).
+//! - The pages list the authors first, then the posts, each by id (so a page stays stable while the ids grow).
+//!
+
+use std::fmt::Write;
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use super::feed::xml_escape;
+
+/// Max urls of a sitemap (per the protocol)
+pub const MAX_URLS: i64 = 50_000;
+
+#[derive(Debug)]
+pub struct SitemapUrl {
+	pub loc: String,
+	/// From `updated_at`
+	pub lastmod: OffsetDateTime,
+}
+
+pub fn render_urlset(urls: &[SitemapUrl]) -> String {
+	let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+	xml.push_str(r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+	for url in urls {
+		let _ = write!(
+			xml,
+			"<url><loc>{}</loc><lastmod>{}</lastmod></url>",
+			xml_escape(&url.loc),
+			w3c_datetime(url.lastmod),
+		);
+	}
+	xml.push_str("</urlset>");
+
+	xml
+}
+
+/// Index of the sitemap pages (`locs`, their urls)
+pub fn render_index(locs: &[String]) -> String {
+	let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+	xml.push_str(r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+	for loc in locs {
+		let _ = write!(xml, "<sitemap><loc>{}</loc></sitemap>", xml_escape(loc));
+	}
+	xml.push_str("</sitemapindex>");
+
+	xml
+}
+
+/// Pages of `total` urls (at least one, even empty).
+pub fn page_count(total: i64, max_urls: i64) -> i64 {
+	((total + max_urls - 1) / max_urls).max(1)
+}
+
+/// The `(offset, limit)` of the authors and of the posts on `page` (from 1), the authors first.
+pub fn page_ranges(author_count: i64, page: i64, max_urls: i64) -> ((i64, i64), (i64, i64)) {
+	let start = (page - 1) * max_urls;
+	let end = start + max_urls;
+
+	let authors_start = start.min(author_count);
+	let authors = (authors_start, end.min(author_count) - authors_start);
+
+	let posts_start = (start - author_count).max(0);
+	let posts = (posts_start, (end - author_count).max(0) - posts_start);
+
+	(authors, posts)
+}
+
+/// W3C Datetime, to the second (e.g., `2026-10-19T10:00:00Z`)
+fn w3c_datetime(date: OffsetDateTime) -> String {
+	date.replace_nanosecond(0).unwrap_or(date).format(&Rfc3339).unwrap_or_default()
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[test]
+	fn test_sitemap_render() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_lastmod = OffsetDateTime::parse("2026-10-19T10:00:00.123456Z", &Rfc3339)?;
+		let fx_urls = [SitemapUrl { loc: "https://blog.example.com/api/post/1000?a=1&b=2".to_string(), lastmod: fx_lastmod }];
+
+		// -- Exec
+		let urlset = render_urlset(&fx_urls);
+		let index = render_index(&["https://blog.example.com/sitemap/1.xml".to_string()]);
+
+		// -- Check
+		assert!(urlset.contains("<loc>https://blog.example.com/api/post/1000?a=1&amp;b=2</loc>"));
+		assert!(urlset.contains("<lastmod>2026-10-19T10:00:00Z</lastmod>"));
+		assert!(index.contains("<sitemap><loc>https://blog.example.com/sitemap/1.xml</loc></sitemap>"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_sitemap_pages() -> Result<()> {
+		// -- Check
+		assert_eq!(page_count(0, 10), 1);
+		assert_eq!(page_count(10, 10), 1);
+		assert_eq!(page_count(11, 10), 2);
+
+		// (3 authors, then the posts)
+		assert_eq!(page_ranges(3, 1, 10), ((0, 3), (0, 7)));
+		assert_eq!(page_ranges(3, 2, 10), ((3, 0), (7, 10)));
+		// (more authors than a page)
+		assert_eq!(page_ranges(15, 1, 10), ((0, 10), (0, 0)));
+		assert_eq!(page_ranges(15, 2, 10), ((10, 5), (0, 5)));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests